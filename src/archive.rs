@@ -0,0 +1,268 @@
+use crate::config::ReleaseConfig;
+use crate::github::{FetchLimits, PullRequestApi, ReleaseApi, ReleaseInfo};
+use crate::release_notes::{CommitHistory, parse_release_marker, render_release_body};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_ARCHIVE_DIR: &str = "release-notes";
+
+pub struct ArchiveScope<'a> {
+    pub directory: Option<&'a str>,
+    /// Prefix stripped from each tag for `$RESOLVED_VERSION`.
+    pub tag_prefix: &'a str,
+    pub repository: Option<&'a str>,
+    pub fallback_branch: Option<&'a str>,
+    pub pull_request_api: PullRequestApi,
+    pub per_page: u32,
+}
+
+#[derive(Debug)]
+struct ArchiveEntry<'a> {
+    release: &'a ReleaseInfo,
+    branch: Option<String>,
+    since: Option<String>,
+    /// Tag of the previous release on the same branch.
+    previous_tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FrontMatter<'a> {
+    title: &'a str,
+    tag: &'a str,
+    date: &'a str,
+    prerelease: bool,
+}
+
+/// Renders every published release in scope into `<output_dir>/<tag>.md`,
+/// regenerating notes from the PRs merged since the previous release on the same branch.
+pub fn write_archive(
+    client: &dyn ReleaseApi,
+    releases: &[ReleaseInfo],
+    config: Option<&ReleaseConfig>,
+    scope: &ArchiveScope,
+    output_dir: &Path,
+) -> Result<usize> {
+    fs::create_dir_all(output_dir).with_context(|| {
+        format!(
+            "Failed to create archive directory {}",
+            output_dir.display()
+        )
+    })?;
+
+    let entries = plan_archive(releases, scope);
+    for entry in &entries {
+        let pull_requests = match &entry.branch {
+            Some(branch) => {
+                client
+                    .fetch_merged_pull_requests(
+                        branch,
+                        entry.since.as_deref(),
+                        Some(release_date(entry.release)),
+                        scope.per_page,
+                        FetchLimits::default(),
+                        scope.pull_request_api,
+                    )?
+                    .pull_requests
+            }
+            None => {
+                println!(
+                    "Archiving {} without changes because its branch is unknown",
+                    entry.release.tag_name
+                );
+                Vec::new()
+            }
+        };
+        let history = CommitHistory {
+            tag: entry.release.tag_name.clone(),
+            resolved_version: entry
+                .release
+                .tag_name
+                .strip_prefix(scope.tag_prefix)
+                .unwrap_or(&entry.release.tag_name)
+                .to_string(),
+            previous_tag: entry.previous_tag.clone(),
+            repository_url: Some(client.repository_url()),
+            ..CommitHistory::default()
+        };
+        let body = render_release_body(&pull_requests, &history, config);
+        let path = output_dir.join(archive_file_name(&entry.release.tag_name));
+        fs::write(&path, render_archive_file(entry.release, &body)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(entries.len())
+}
+
+fn plan_archive<'a>(releases: &'a [ReleaseInfo], scope: &ArchiveScope) -> Vec<ArchiveEntry<'a>> {
+    let scoped = scope.directory.is_some() || scope.repository.is_some();
+    let mut published: Vec<(&ReleaseInfo, Option<String>)> = releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| {
+            let marker = parse_release_marker(release.body.as_deref().unwrap_or(""));
+            if scoped {
+                let marker = marker.as_ref()?;
+                if marker.directory.as_deref() != scope.directory
+                    || marker.repository.as_deref() != scope.repository
+                {
+                    return None;
+                }
+            }
+            let branch = marker
+                .map(|marker| marker.branch)
+                .filter(|branch| !branch.is_empty())
+                .or_else(|| {
+                    Some(release.target_commitish.clone())
+                        .filter(|value| !value.is_empty() && !is_commit_sha(value))
+                })
+                .or_else(|| scope.fallback_branch.map(|value| value.to_string()));
+            Some((release, branch))
+        })
+        .collect();
+
+    published.sort_by(|left, right| release_date(left.0).cmp(release_date(right.0)));
+
+    let mut entries: Vec<ArchiveEntry> = Vec::with_capacity(published.len());
+    for (release, branch) in published {
+        let previous = entries.iter().rev().find(|entry| entry.branch == branch);
+        let since = previous.map(|entry| release_date(entry.release).to_string());
+        let previous_tag = previous.map(|entry| entry.release.tag_name.clone());
+        entries.push(ArchiveEntry {
+            release,
+            branch,
+            since,
+            previous_tag,
+        });
+    }
+    entries
+}
+
+fn release_date(release: &ReleaseInfo) -> &str {
+    release
+        .published_at
+        .as_deref()
+        .unwrap_or(&release.created_at)
+}
+
+fn is_commit_sha(value: &str) -> bool {
+    value.len() == 40 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn archive_file_name(tag_name: &str) -> String {
+    let sanitized: String = tag_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{sanitized}.md")
+}
+
+fn render_archive_file(release: &ReleaseInfo, body: &str) -> Result<String> {
+    let title = release
+        .name
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or(&release.tag_name);
+    let front_matter = serde_yaml::to_string(&FrontMatter {
+        title,
+        tag: &release.tag_name,
+        date: release_date(release),
+        prerelease: release.prerelease,
+    })?;
+    Ok(format!("---\n{front_matter}---\n\n{}\n", body.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release_notes::release_marker;
+
+    fn release(id: u64, tag: &str, target: &str, published_at: &str, body: &str) -> ReleaseInfo {
+        ReleaseInfo {
+            id,
+            tag_name: tag.to_string(),
+            name: None,
+            body: Some(body.to_string()),
+            draft: false,
+            prerelease: false,
+            target_commitish: target.to_string(),
+            created_at: published_at.to_string(),
+            published_at: Some(published_at.to_string()),
+            assets: Vec::new(),
+            upload_url: None,
+        }
+    }
+
+    #[test]
+    fn windows_releases_per_branch() {
+        let releases = vec![
+            release(3, "v1.2.0", "main", "2024-03-01T00:00:00Z", ""),
+            release(1, "v1.0.0", "main", "2024-01-01T00:00:00Z", ""),
+            release(2, "v0.9.1", "maint", "2024-02-01T00:00:00Z", ""),
+        ];
+        let scope = ArchiveScope {
+            directory: None,
+            tag_prefix: "v",
+            repository: None,
+            fallback_branch: None,
+            pull_request_api: PullRequestApi::Rest,
+            per_page: 100,
+        };
+
+        let entries = plan_archive(&releases, &scope);
+
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.release.id, entry.since.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(1, None), (2, None), (3, Some("2024-01-01T00:00:00Z"))]
+        );
+    }
+
+    #[test]
+    fn scopes_releases_by_directory_marker() {
+        let app = release_marker("main", Some("crates/app"), None, None);
+        let worker = release_marker("main", Some("crates/worker"), None, None);
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        let releases = vec![
+            release(1, "app-1.0.0", sha, "2024-01-01T00:00:00Z", &app),
+            release(2, "worker-1.0.0", sha, "2024-01-02T00:00:00Z", &worker),
+        ];
+        let scope = ArchiveScope {
+            directory: Some("crates/app"),
+            tag_prefix: "v",
+            repository: None,
+            fallback_branch: Some("trunk"),
+            pull_request_api: PullRequestApi::Rest,
+            per_page: 100,
+        };
+
+        let entries = plan_archive(&releases, &scope);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].release.id, 1);
+        assert_eq!(entries[0].branch.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn renders_front_matter() {
+        let release = release(1, "v1.0.0", "main", "2024-01-01T00:00:00Z", "");
+
+        let file = render_archive_file(&release, "* Add login\n").unwrap();
+
+        assert_eq!(
+            file,
+            "---\ntitle: v1.0.0\ntag: v1.0.0\ndate: 2024-01-01T00:00:00Z\nprerelease: false\n---\n\n* Add login\n"
+        );
+        assert_eq!(archive_file_name("app/v1.0.0"), "app-v1.0.0.md");
+    }
+}
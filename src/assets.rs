@@ -0,0 +1,317 @@
+use crate::github::{AssetInfo, ReleaseInfo};
+use anyhow::{Context, Result, bail};
+use aws_lc_rs::digest::{SHA256, digest};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub const CHECKSUMS_FILE_NAME: &str = "checksums.txt";
+const CHECKSUM_EXTENSION: &str = ".sha256";
+
+pub struct AssetNameContext<'a> {
+    pub version: &'a str,
+    pub tag: &'a str,
+    pub target: &'a str,
+}
+
+/// Expands newline-separated asset paths relative to `cwd`. `*` and `?`
+/// wildcards are supported within any path component.
+pub fn resolve_asset_paths(input: &str, cwd: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for pattern in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let matches = expand_pattern(pattern, cwd)?;
+        if matches.is_empty() {
+            bail!("No files matched asset pattern: {pattern}");
+        }
+        for path in matches {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn expand_pattern(pattern: &str, cwd: &Path) -> Result<Vec<PathBuf>> {
+    let pattern_path = Path::new(pattern);
+    let mut candidates = vec![if pattern_path.is_absolute() {
+        PathBuf::from("/")
+    } else {
+        cwd.to_path_buf()
+    }];
+
+    for component in pattern_path.components() {
+        let Component::Normal(part) = component else {
+            if component == Component::ParentDir {
+                candidates = candidates.into_iter().map(|path| path.join("..")).collect();
+            }
+            continue;
+        };
+        let part = part.to_string_lossy();
+        if !part.contains(['*', '?']) {
+            candidates = candidates
+                .into_iter()
+                .map(|path| path.join(part.as_ref()))
+                .filter(|path| path.exists())
+                .collect();
+            continue;
+        }
+
+        let mut next = Vec::new();
+        for dir in candidates.iter().filter(|path| path.is_dir()) {
+            let entries = fs::read_dir(dir)
+                .with_context(|| format!("Failed to read directory {}", dir.display()))?;
+            let mut names: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| wildcard_match(&part, name))
+                .collect();
+            names.sort();
+            next.extend(names.into_iter().map(|name| dir.join(name)));
+        }
+        candidates = next;
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+/// Matches `value` against a pattern where `*` is any run of characters and `?` is one.
+pub fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            v = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Renders an upload name from a template such as `${name}-${version}-${target}${ext}`.
+/// `${name}` is the file name without its extension and `${ext}` includes the leading dot.
+pub fn render_asset_name(template: &str, path: &Path, context: &AssetNameContext) -> String {
+    let file_name = path
+        .file_name()
+        .map(|value| value.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (name, ext) = split_extension(&file_name);
+    if template.trim().is_empty() {
+        return file_name;
+    }
+    template
+        .replace("${name}", name)
+        .replace("${ext}", ext)
+        .replace("${version}", context.version)
+        .replace("${tag}", context.tag)
+        .replace("${target}", context.target)
+}
+
+/// Splits a file name into stem and extension, keeping compound archive
+/// extensions like `.tar.gz` together.
+fn split_extension(file_name: &str) -> (&str, &str) {
+    for compound in [".tar.gz", ".tar.xz", ".tar.bz2", ".tar.zst"] {
+        if let Some(stem) = file_name.strip_suffix(compound)
+            && !stem.is_empty()
+        {
+            return (stem, &file_name[stem.len()..]);
+        }
+    }
+    match file_name.rfind('.') {
+        Some(index) if index > 0 => (&file_name[..index], &file_name[index..]),
+        _ => (file_name, ""),
+    }
+}
+
+/// Picks the assets on extra drafts that are missing from the surviving draft,
+/// preferring the newest draft when several carry the same asset name.
+pub fn assets_to_carry<'a>(
+    primary: &[AssetInfo],
+    extras: &[&'a ReleaseInfo],
+) -> Vec<&'a AssetInfo> {
+    let mut carried: Vec<&AssetInfo> = Vec::new();
+    for release in extras {
+        for asset in &release.assets {
+            let exists = primary.iter().any(|existing| existing.name == asset.name)
+                || carried.iter().any(|existing| existing.name == asset.name);
+            if !exists {
+                carried.push(asset);
+            }
+        }
+    }
+    carried
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    digest(&SHA256, content)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Whether an asset is one breezy generates for checksums, and so is left out
+/// of `checksums.txt`.
+pub fn is_checksum_asset(name: &str) -> bool {
+    name == CHECKSUMS_FILE_NAME || name.ends_with(CHECKSUM_EXTENSION)
+}
+
+pub fn checksum_asset_name(name: &str) -> String {
+    format!("{name}{CHECKSUM_EXTENSION}")
+}
+
+/// Rejects upload names that would overwrite each other on the release,
+/// including, with `checksums`, the files generated alongside them.
+pub fn check_asset_names(names: &[String], checksums: bool) -> Result<()> {
+    for (index, name) in names.iter().enumerate() {
+        if names[..index].contains(name) {
+            bail!(
+                "More than one asset would be uploaded as {name}; set asset-name-template to tell them apart."
+            );
+        }
+        if checksums && is_checksum_asset(name) {
+            bail!(
+                "Asset {name} would collide with the generated checksum files; rename it or turn off checksums."
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Renders `sha256sum` output for `(name, hex)` pairs, sorted by name, so
+/// `sha256sum -c` can verify downloads.
+pub fn render_checksums(entries: &[(String, String)]) -> String {
+    let mut entries: Vec<&(String, String)> = entries.iter().collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(name, hex)| format!("{hex}  {name}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(id: u64, name: &str) -> AssetInfo {
+        AssetInfo {
+            id,
+            name: name.to_string(),
+            digest: None,
+        }
+    }
+
+    fn draft(id: u64, assets: Vec<AssetInfo>) -> ReleaseInfo {
+        ReleaseInfo {
+            id,
+            tag_name: "v1.0.0".to_string(),
+            name: None,
+            body: None,
+            draft: true,
+            prerelease: false,
+            target_commitish: "main".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            published_at: None,
+            assets,
+            upload_url: None,
+        }
+    }
+
+    #[test]
+    fn carries_missing_assets_once() {
+        let primary = vec![asset(1, "linux.tar.gz")];
+        let newer = draft(2, vec![asset(10, "linux.tar.gz"), asset(11, "mac.tar.gz")]);
+        let older = draft(3, vec![asset(20, "mac.tar.gz"), asset(21, "win.zip")]);
+
+        let carried = assets_to_carry(&primary, &[&newer, &older]);
+
+        let ids: Vec<u64> = carried.iter().map(|asset| asset.id).collect();
+        assert_eq!(ids, vec![11, 21]);
+    }
+
+    #[test]
+    fn renders_asset_name_template() {
+        let context = AssetNameContext {
+            version: "1.2.0",
+            tag: "v1.2.0",
+            target: "x86_64-unknown-linux-gnu",
+        };
+
+        let name = render_asset_name(
+            "${name}-${version}-${target}${ext}",
+            Path::new("dist/breezy.tar.gz"),
+            &context,
+        );
+        let untemplated = render_asset_name("", Path::new("dist/breezy.exe"), &context);
+
+        assert_eq!(name, "breezy-1.2.0-x86_64-unknown-linux-gnu.tar.gz");
+        assert_eq!(untemplated, "breezy.exe");
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(wildcard_match("breezy-*.tar.gz", "breezy-linux.tar.gz"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("file?.txt", "file1.txt"));
+        assert!(!wildcard_match("breezy-*.zip", "breezy-linux.tar.gz"));
+    }
+
+    #[test]
+    fn expands_patterns_relative_to_cwd() {
+        let dir = std::env::temp_dir().join(format!("breezy-assets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("dist")).unwrap();
+        fs::write(dir.join("dist/a.zip"), "a").unwrap();
+        fs::write(dir.join("dist/b.zip"), "b").unwrap();
+        fs::write(dir.join("dist/notes.txt"), "c").unwrap();
+
+        let paths = resolve_asset_paths("dist/*.zip\ndist/a.zip\n", &dir).unwrap();
+
+        assert_eq!(paths, vec![dir.join("dist/a.zip"), dir.join("dist/b.zip")]);
+        assert!(resolve_asset_paths("dist/*.exe", &dir).is_err());
+    }
+
+    #[test]
+    fn renders_sorted_checksums() {
+        let entries = vec![
+            ("b.tar.gz".to_string(), sha256_hex(b"")),
+            ("a.zip".to_string(), sha256_hex(b"abc")),
+        ];
+
+        assert_eq!(
+            render_checksums(&entries),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  a.zip\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  b.tar.gz\n"
+        );
+        assert!(is_checksum_asset(CHECKSUMS_FILE_NAME));
+        assert!(is_checksum_asset(&checksum_asset_name("a.zip")));
+        assert!(!is_checksum_asset("a.zip"));
+    }
+
+    #[test]
+    fn rejects_colliding_asset_names() {
+        let names =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        assert!(check_asset_names(&names(&["a.zip", "b.zip"]), true).is_ok());
+        assert!(check_asset_names(&names(&["a.zip", "b.zip", "a.zip"]), false).is_err());
+        assert!(check_asset_names(&names(&["a.zip", "a.zip.sha256"]), true).is_err());
+        assert!(check_asset_names(&names(&[CHECKSUMS_FILE_NAME]), true).is_err());
+        assert!(check_asset_names(&names(&[CHECKSUMS_FILE_NAME]), false).is_ok());
+    }
+}
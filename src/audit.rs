@@ -0,0 +1,340 @@
+use crate::assets::sha256_hex;
+use crate::github::{
+    self, AssetInfo, CommitInfo, FetchLimits, MergedPullRequests, PullRequestApi, ReleaseAccess,
+    ReleaseApi, ReleaseFields, ReleaseInfo, TagInfo,
+};
+use crate::release_notes::IssueReference;
+use crate::signing::Tagger;
+use crate::version::{DirEntry, TagSource};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// One change breezy made to a repository. Bodies are recorded as SHA-256
+/// hashes so the log shows what changed without repeating the notes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    pub action: &'static str,
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Asset name or id the change touched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_after: Option<String>,
+    pub at: String,
+}
+
+/// Every mutation made during a run, in order, shared by the clients of all
+/// repositories involved.
+#[derive(Default)]
+pub struct AuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.entries())?)
+    }
+}
+
+/// A [`ReleaseApi`] that records each successful create, update, publish,
+/// upload, and delete in an [`AuditLog`] before handing the result back.
+pub struct AuditedApi<A: ?Sized> {
+    inner: Box<A>,
+    repository: String,
+    log: Arc<AuditLog>,
+    /// Tag and body hash of each release seen, for `body_before`.
+    known: Mutex<HashMap<u64, (String, Option<String>)>>,
+}
+
+impl<A: ReleaseApi + ?Sized> AuditedApi<A> {
+    pub fn new(inner: Box<A>, repository: &str, log: Arc<AuditLog>) -> Self {
+        Self {
+            inner,
+            repository: repository.to_string(),
+            log,
+            known: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entry(&self, action: &'static str) -> AuditEntry {
+        AuditEntry {
+            action,
+            repository: self.repository.clone(),
+            release_id: None,
+            tag: None,
+            asset: None,
+            body_before: None,
+            body_after: None,
+            at: github::format_timestamp(github::unix_now()),
+        }
+    }
+
+    fn record(&self, entry: AuditEntry) {
+        self.log.entries.lock().unwrap().push(entry);
+    }
+
+    fn remember(&self, release: &ReleaseInfo) {
+        self.known.lock().unwrap().insert(
+            release.id,
+            (release.tag_name.clone(), body_hash(release.body.as_deref())),
+        );
+    }
+
+    fn known(&self, release_id: u64) -> (Option<String>, Option<String>) {
+        match self.known.lock().unwrap().get(&release_id) {
+            Some((tag, body)) => (Some(tag.clone()), body.clone()),
+            None => (None, None),
+        }
+    }
+
+    /// Records a change to a release's notes, then remembers its new body.
+    fn record_release(&self, action: &'static str, release_id: u64, release: &ReleaseInfo) {
+        let (_, body_before) = self.known(release_id);
+        self.record(AuditEntry {
+            release_id: Some(release.id),
+            tag: Some(release.tag_name.clone()),
+            body_before,
+            body_after: body_hash(release.body.as_deref()),
+            ..self.entry(action)
+        });
+        self.remember(release);
+    }
+}
+
+fn body_hash(body: Option<&str>) -> Option<String> {
+    body.map(|body| sha256_hex(body.as_bytes()))
+}
+
+impl<A: ReleaseApi + ?Sized> TagSource for AuditedApi<A> {
+    fn tag_names(&self) -> Result<Vec<String>> {
+        self.inner.tag_names()
+    }
+}
+
+impl<A: ReleaseApi + ?Sized> ReleaseApi for AuditedApi<A> {
+    fn allow_optional(&self, feature: &str) -> bool {
+        self.inner.allow_optional(feature)
+    }
+
+    fn degraded_features(&self) -> Vec<String> {
+        self.inner.degraded_features()
+    }
+
+    fn repository_url(&self) -> String {
+        self.inner.repository_url()
+    }
+
+    fn list_releases(
+        &self,
+        per_page: u32,
+        enough: &mut dyn FnMut(&[ReleaseInfo]) -> bool,
+    ) -> Result<Vec<ReleaseInfo>> {
+        let releases = self.inner.list_releases(per_page, enough)?;
+        for release in &releases {
+            self.remember(release);
+        }
+        Ok(releases)
+    }
+
+    fn delete_release(&self, release_id: u64) -> Result<()> {
+        self.inner.delete_release(release_id)?;
+        let (tag, body_before) = self.known(release_id);
+        self.record(AuditEntry {
+            release_id: Some(release_id),
+            tag,
+            body_before,
+            ..self.entry("delete-release")
+        });
+        Ok(())
+    }
+
+    fn upload_release_asset(
+        &self,
+        release: &ReleaseInfo,
+        name: &str,
+        content: Vec<u8>,
+    ) -> Result<AssetInfo> {
+        let asset = self.inner.upload_release_asset(release, name, content)?;
+        self.record(AuditEntry {
+            release_id: Some(release.id),
+            tag: Some(release.tag_name.clone()),
+            asset: Some(name.to_string()),
+            ..self.entry("upload-asset")
+        });
+        Ok(asset)
+    }
+
+    fn download_release_asset(&self, asset_id: u64) -> Result<Vec<u8>> {
+        self.inner.download_release_asset(asset_id)
+    }
+
+    fn delete_release_asset(&self, asset_id: u64) -> Result<()> {
+        self.inner.delete_release_asset(asset_id)?;
+        self.record(AuditEntry {
+            asset: Some(asset_id.to_string()),
+            ..self.entry("delete-asset")
+        });
+        Ok(())
+    }
+
+    fn update_release(&self, release_id: u64, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+        let release = self.inner.update_release(release_id, fields)?;
+        self.record_release("update-release", release_id, &release);
+        Ok(release)
+    }
+
+    fn create_release(&self, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+        let release = self.inner.create_release(fields)?;
+        self.record_release("create-release", release.id, &release);
+        Ok(release)
+    }
+
+    fn update_release_notes(&self, release_id: u64, name: &str, body: &str) -> Result<ReleaseInfo> {
+        let release = self.inner.update_release_notes(release_id, name, body)?;
+        self.record_release("update-release-notes", release_id, &release);
+        Ok(release)
+    }
+
+    fn publish_release(
+        &self,
+        release_id: u64,
+        target_commitish: Option<&str>,
+        fields: &ReleaseFields,
+    ) -> Result<ReleaseInfo> {
+        let release = self
+            .inner
+            .publish_release(release_id, target_commitish, fields)?;
+        self.record_release("publish-release", release_id, &release);
+        Ok(release)
+    }
+
+    fn check_release_access(&self) -> Result<ReleaseAccess> {
+        self.inner.check_release_access()
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        self.inner.tag_exists(tag)
+    }
+
+    fn fetch_release_by_tag(&self, tag: &str) -> Result<Option<ReleaseInfo>> {
+        let release = self.inner.fetch_release_by_tag(tag)?;
+        if let Some(release) = &release {
+            self.remember(release);
+        }
+        Ok(release)
+    }
+
+    fn fetch_commit_authors(
+        &self,
+        base_tag: &str,
+        branch: &str,
+        per_page: u32,
+    ) -> Result<Vec<String>> {
+        self.inner.fetch_commit_authors(base_tag, branch, per_page)
+    }
+
+    fn fetch_closing_issues(&self, number: u64) -> Result<Vec<IssueReference>> {
+        self.inner.fetch_closing_issues(number)
+    }
+
+    fn fetch_reviewers(&self, number: u64, author: &str) -> Result<Vec<String>> {
+        self.inner.fetch_reviewers(number, author)
+    }
+
+    fn fetch_user_name(&self, login: &str) -> Result<Option<String>> {
+        self.inner.fetch_user_name(login)
+    }
+
+    fn fetch_first_merged_pull_request(
+        &self,
+        repository: Option<&str>,
+        login: &str,
+    ) -> Result<Option<u64>> {
+        self.inner
+            .fetch_first_merged_pull_request(repository, login)
+    }
+
+    fn create_annotated_tag(
+        &self,
+        tag: &str,
+        object: &str,
+        message: &str,
+        tagger: &Tagger,
+        timestamp: u64,
+    ) -> Result<()> {
+        self.inner
+            .create_annotated_tag(tag, object, message, tagger, timestamp)?;
+        self.record(AuditEntry {
+            tag: Some(tag.to_string()),
+            ..self.entry("create-tag")
+        });
+        Ok(())
+    }
+
+    fn list_all_tags(&self, per_page: u32) -> Result<Vec<TagInfo>> {
+        self.inner.list_all_tags(per_page)
+    }
+
+    fn resolve_commit_sha(&self, reference: &str) -> Result<String> {
+        self.inner.resolve_commit_sha(reference)
+    }
+
+    fn fetch_commit(&self, reference: &str) -> Result<CommitInfo> {
+        self.inner.fetch_commit(reference)
+    }
+
+    fn fetch_file_contents(&self, path: &str, reference: Option<&str>) -> Result<Option<String>> {
+        self.inner.fetch_file_contents(path, reference)
+    }
+
+    fn list_directory(&self, path: &str, reference: &str) -> Result<Vec<DirEntry>> {
+        self.inner.list_directory(path, reference)
+    }
+
+    fn fetch_merged_pull_requests(
+        &self,
+        branch: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+        per_page: u32,
+        limits: FetchLimits,
+        api: PullRequestApi,
+    ) -> Result<MergedPullRequests> {
+        self.inner
+            .fetch_merged_pull_requests(branch, since, until, per_page, limits, api)
+    }
+
+    fn fetch_milestone_pull_requests(
+        &self,
+        branch: &str,
+        milestone: &str,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        self.inner
+            .fetch_milestone_pull_requests(branch, milestone, per_page, limits)
+    }
+
+    fn fetch_compared_pull_requests(
+        &self,
+        base_tag: &str,
+        branch: &str,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        self.inner
+            .fetch_compared_pull_requests(base_tag, branch, per_page, limits)
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: String,
+    /// The response's `Link` header, so a revalidated page still paginates.
+    #[serde(default)]
+    pub link: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    pub fn load(&self, url: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.entry_path(url)).ok()?;
+        let entry: CachedResponse = serde_json::from_str(&content).ok()?;
+        // Guard against hash collisions by checking the stored key.
+        if entry.url != url {
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub fn store(&self, url: &str, etag: &str, link: Option<&str>, body: &str) -> Result<()> {
+        let entry = CachedResponse {
+            url: url.to_string(),
+            etag: etag.to_string(),
+            link: link.map(str::to_string),
+            body: body.to_string(),
+        };
+        let path = self.entry_path(url);
+        let content = serde_json::to_string(&entry)?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write cache entry {}", path.display()))
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", cache_key_hash(url)))
+    }
+}
+
+fn cache_key_hash(value: &str) -> u64 {
+    value.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> ResponseCache {
+        let dir = std::env::temp_dir().join(format!("breezy-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ResponseCache::new(&dir).unwrap()
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let cache = temp_cache("round-trip");
+        let url = "https://api.github.com/repos/o/r/releases?per_page=100&page=1";
+        let link = "<https://api.github.com/repos/o/r/releases?page=2>; rel=\"next\"";
+        cache.store(url, "W/\"abc\"", Some(link), "[]").unwrap();
+
+        let entry = cache.load(url).unwrap();
+
+        assert_eq!(entry.etag, "W/\"abc\"");
+        assert_eq!(entry.link.as_deref(), Some(link));
+        assert_eq!(entry.body, "[]");
+        assert!(cache.load("https://api.github.com/other").is_none());
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        assert_eq!(cache_key_hash(""), FNV_OFFSET_BASIS);
+        assert_eq!(cache_key_hash("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::{Result, bail};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    FullYear,
+    ShortYear,
+    PaddedYear,
+    Month,
+    PaddedMonth,
+    Day,
+    PaddedDay,
+    Micro,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Field(Field),
+    Literal(char),
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("YYYY", Field::FullYear),
+    ("MICRO", Field::Micro),
+    ("YY", Field::ShortYear),
+    ("0Y", Field::PaddedYear),
+    ("MM", Field::Month),
+    ("0M", Field::PaddedMonth),
+    ("DD", Field::Day),
+    ("0D", Field::PaddedDay),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_days(i64::try_from(seconds / 86_400).unwrap_or(0))
+    }
+
+    /// Converts days since 1970-01-01 to a UTC calendar date.
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self { year, month, day }
+    }
+}
+
+/// A calendar versioning scheme such as `YYYY.0M.MICRO`, see <https://calver.org>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalVer {
+    tokens: Vec<Token>,
+}
+
+impl CalVer {
+    pub fn new(format: &str) -> Result<Self> {
+        let mut tokens = Vec::new();
+        let mut rest = format.trim();
+        while !rest.is_empty() {
+            if let Some((name, field)) = FIELDS.iter().find(|(name, _)| rest.starts_with(name)) {
+                tokens.push(Token::Field(*field));
+                rest = &rest[name.len()..];
+            } else {
+                let c = rest.chars().next().unwrap_or_default();
+                if c.is_ascii_digit() || c.is_ascii_alphabetic() {
+                    bail!("Unknown calver token at {rest:?} in {format:?}.");
+                }
+                tokens.push(Token::Literal(c));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if !tokens
+            .iter()
+            .any(|token| matches!(token, Token::Field(field) if *field != Field::Micro))
+        {
+            bail!("calver format {format:?} needs at least one date field such as YYYY.");
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Returns the version for `today`: the previous version's MICRO plus one when
+    /// its date fields match today, otherwise MICRO 0.
+    pub fn next(&self, previous: Option<&str>, today: Date) -> String {
+        let micro = previous
+            .and_then(|previous| self.parse(previous))
+            .filter(|(fields, _)| {
+                fields.iter().all(|(field, value)| {
+                    *field == Field::Micro || Some(*value) == value_of(*field, today)
+                })
+            })
+            .and_then(|(fields, _)| {
+                fields
+                    .iter()
+                    .find(|(field, _)| *field == Field::Micro)
+                    .map(|(_, micro)| micro + 1)
+            })
+            .unwrap_or(0);
+
+        let mut version = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Literal(c) => version.push(*c),
+                Token::Field(Field::Micro) => version.push_str(&micro.to_string()),
+                Token::Field(field) => {
+                    let value = value_of(*field, today).unwrap_or_default();
+                    match field {
+                        Field::PaddedYear | Field::PaddedMonth | Field::PaddedDay => {
+                            version.push_str(&format!("{value:02}"))
+                        }
+                        _ => version.push_str(&value.to_string()),
+                    }
+                }
+            }
+        }
+        version
+    }
+
+    /// A CalVer version is only a prerelease when a `-` suffix follows the format,
+    /// e.g. `2024.05.0-rc.1`; SemVer rules would misread some calendar versions.
+    /// `None` when `version` isn't in the format, such as a SemVer tag from
+    /// before the switch to calver, so SemVer rules apply to it.
+    pub fn is_prerelease(&self, version: &str) -> Option<bool> {
+        let (_, rest) = self.parse(version)?;
+        if rest.is_empty() || rest.starts_with('+') {
+            return Some(false);
+        }
+        rest.strip_prefix('-').map(|suffix| !suffix.is_empty())
+    }
+
+    /// Matches `version` against the format, returning each field's value and the
+    /// unmatched remainder.
+    fn parse<'a>(&self, version: &'a str) -> Option<(Vec<(Field, i64)>, &'a str)> {
+        let mut rest = version.trim();
+        let mut fields = Vec::new();
+        for token in &self.tokens {
+            match token {
+                Token::Literal(c) => rest = rest.strip_prefix(*c)?,
+                Token::Field(field) => {
+                    let end = rest
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(rest.len());
+                    let digits = &rest[..end];
+                    let width_matches = match field {
+                        Field::FullYear => digits.len() == 4,
+                        Field::PaddedYear | Field::PaddedMonth | Field::PaddedDay => {
+                            digits.len() == 2
+                        }
+                        _ => true,
+                    };
+                    let value: i64 = digits.parse().ok().filter(|_| width_matches)?;
+                    let in_range = match field {
+                        Field::Month | Field::PaddedMonth => (1..=12).contains(&value),
+                        Field::Day | Field::PaddedDay => (1..=31).contains(&value),
+                        _ => true,
+                    };
+                    if !in_range {
+                        return None;
+                    }
+                    fields.push((*field, value));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        Some((fields, rest))
+    }
+}
+
+fn value_of(field: Field, date: Date) -> Option<i64> {
+    match field {
+        Field::FullYear => Some(date.year),
+        Field::ShortYear | Field::PaddedYear => Some(date.year - 2000),
+        Field::Month | Field::PaddedMonth => Some(i64::from(date.month)),
+        Field::Day | Field::PaddedDay => Some(i64::from(date.day)),
+        Field::Micro => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::is_prerelease_version;
+
+    #[test]
+    fn computes_next_calendar_version() {
+        let calver = CalVer::new("YYYY.0M.MICRO").unwrap();
+        let today = Date {
+            year: 2024,
+            month: 5,
+            day: 17,
+        };
+
+        assert_eq!(calver.next(None, today), "2024.05.0");
+        assert_eq!(calver.next(Some("2024.05.3"), today), "2024.05.4");
+        assert_eq!(calver.next(Some("2024.04.7"), today), "2024.05.0");
+        assert_eq!(
+            CalVer::new("YY.MM.DD").unwrap().next(None, today),
+            "24.5.17"
+        );
+        assert!(CalVer::new("MICRO").is_err());
+        assert!(CalVer::new("YYYY.Q").is_err());
+    }
+
+    #[test]
+    fn detects_calver_prereleases() {
+        let calver = CalVer::new("YYYY.0M.0D-MICRO").unwrap();
+
+        assert_eq!(calver.is_prerelease("2024.05.01-3"), Some(false));
+        assert!(is_prerelease_version("2024.05.01-3"));
+        assert_eq!(calver.is_prerelease("2024.05.01-3-rc.1"), Some(true));
+        assert_eq!(calver.is_prerelease("2024.05.01-3+build.7"), Some(false));
+        assert_eq!(calver.is_prerelease("not-a-date"), None);
+
+        let calver = CalVer::new("YYYY.0M.MICRO").unwrap();
+        assert_eq!(calver.is_prerelease("2024.05.0-rc.1"), Some(true));
+        assert_eq!(calver.is_prerelease("1.2.0-rc.1"), None);
+        assert_eq!(calver.is_prerelease("2024.13.0"), None);
+        assert_eq!(calver.is_prerelease("2024.05.0.1"), None);
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(
+            Date::from_days(0),
+            Date {
+                year: 1970,
+                month: 1,
+                day: 1
+            }
+        );
+        assert_eq!(
+            Date::from_days(19_782),
+            Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            }
+        );
+    }
+}
@@ -1,42 +1,388 @@
+use crate::calver::CalVer;
+use crate::pattern::Pattern;
+use crate::resolver::{Bump, BumpSource, VersionResolver};
+use crate::version::{VersionFile, VersionSource};
 use anyhow::{Context, Result, anyhow, bail};
 use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const DEFAULT_CHANGE_TEMPLATE: &str = "$TITLE";
+pub const DEFAULT_CONFIG_PATH: &str = ".github/breezy.yml";
 const DEFAULT_CATEGORY_HEADING_LEVEL: u8 = 2;
+const DEFAULT_HIGHLIGHT_LABEL: &str = "highlight";
+const DEFAULT_HIGHLIGHT_TITLE: &str = "Highlights";
+
+/// Built-in gitmoji categories used when `categorize-by: gitmoji` has no explicit categories.
+const DEFAULT_GITMOJI_CATEGORIES: &[(&str, &[&str])] = &[
+    ("Features", &["✨", ":sparkles:", "🎉", ":tada:"]),
+    (
+        "Bug Fixes",
+        &[
+            "🐛",
+            ":bug:",
+            "🚑",
+            ":ambulance:",
+            "🩹",
+            ":adhesive_bandage:",
+        ],
+    ),
+    ("Performance", &["⚡", ":zap:"]),
+    ("Documentation", &["📝", ":memo:"]),
+    ("Refactoring", &["♻", ":recycle:"]),
+    (
+        "Maintenance",
+        &[
+            "🔧",
+            ":wrench:",
+            "⬆",
+            ":arrow_up:",
+            "🔨",
+            ":hammer:",
+            "👷",
+            ":construction_worker:",
+        ],
+    ),
+];
+
+/// Built-in Conventional Commits categories used when `categorize-by: conventional`
+/// has no explicit categories. The `breaking` type matches any `!` title.
+const DEFAULT_CONVENTIONAL_CATEGORIES: &[(&str, &[&str])] = &[
+    ("Breaking Changes", &["breaking"]),
+    ("Features", &["feat"]),
+    ("Bug Fixes", &["fix"]),
+    ("Performance", &["perf"]),
+    ("Documentation", &["docs"]),
+    (
+        "Maintenance",
+        &["refactor", "chore", "build", "ci", "test", "style"],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CategorizeBy {
+    #[default]
+    Labels,
+    Gitmoji,
+    Conventional,
+}
+
+/// Where `$CONTRIBUTORS` finds people to credit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContributorsFrom {
+    /// PR authors only.
+    #[default]
+    PullRequests,
+    /// PR authors plus the authors of every commit since the previous tag.
+    Commits,
+}
+
+/// What orders the changes within each category.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    MergedAt,
+    Title,
+    Number,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// A `replacers` entry: every match of `pattern` in the rendered notes is
+/// replaced with `replace`.
+#[derive(Debug, Clone)]
+pub struct Replacer {
+    pub pattern: Pattern,
+    pub replace: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct ReleaseCategory {
     pub title: String,
     pub heading_level: u8,
     pub labels: Vec<String>,
+    pub gitmoji: Vec<String>,
+    pub types: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ReleaseConfig {
     pub language: Option<String>,
+    pub crate_name: Option<String>,
+    pub package_name: Option<String>,
+    pub version_file: Option<VersionFile>,
+    pub version_source: Option<VersionSource>,
+    pub version_resolver: Option<VersionResolver>,
+    pub calver: Option<CalVer>,
+    pub build_metadata: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub discussion_category: Option<String>,
     pub tag_template: Option<String>,
     pub name_template: Option<String>,
+    pub categorize_by: CategorizeBy,
     pub categories: Vec<ReleaseCategory>,
+    pub group_by_scope: bool,
+    pub scopes: Vec<(String, String)>,
     pub exclude_labels: Vec<String>,
+    pub highlight_label: String,
+    pub highlight_title: String,
     pub change_template: String,
     pub template: Option<String>,
+    pub contributors_from: ContributorsFrom,
+    /// Extra `owner/repo`s whose PRs merged into the same branch join the notes.
+    pub repositories: Vec<String>,
+    pub direct_commits: bool,
+    pub milestone: Option<String>,
+    /// Appends a `New Contributors` section for authors whose first merged
+    /// PR is in the release.
+    pub new_contributors: bool,
+    /// Logins left out of `$CONTRIBUTORS` and `New Contributors`, such as bots.
+    pub exclude_contributors: Vec<String>,
+    /// Appends a `**Full Changelog**` compare link from the previous tag.
+    pub full_changelog: bool,
+    pub sort_by: SortBy,
+    pub sort_direction: SortDirection,
+    pub replacers: Vec<Replacer>,
+}
+
+impl Default for ReleaseConfig {
+    fn default() -> Self {
+        Self {
+            language: None,
+            crate_name: None,
+            package_name: None,
+            version_file: None,
+            version_source: None,
+            version_resolver: None,
+            calver: None,
+            build_metadata: None,
+            ca_bundle: None,
+            discussion_category: None,
+            tag_template: None,
+            name_template: None,
+            categorize_by: CategorizeBy::default(),
+            categories: Vec::new(),
+            group_by_scope: false,
+            scopes: Vec::new(),
+            exclude_labels: Vec::new(),
+            highlight_label: DEFAULT_HIGHLIGHT_LABEL.to_string(),
+            highlight_title: DEFAULT_HIGHLIGHT_TITLE.to_string(),
+            change_template: DEFAULT_CHANGE_TEMPLATE.to_string(),
+            template: None,
+            contributors_from: ContributorsFrom::default(),
+            repositories: Vec::new(),
+            direct_commits: false,
+            milestone: None,
+            new_contributors: false,
+            exclude_contributors: Vec::new(),
+            full_changelog: false,
+            sort_by: SortBy::default(),
+            sort_direction: SortDirection::default(),
+            replacers: Vec::new(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct RawConfig {
     language: Option<String>,
+    #[serde(rename = "crate")]
+    crate_name: Option<String>,
+    #[serde(rename = "package")]
+    package_name: Option<String>,
+    #[serde(rename = "version-file")]
+    version_file: Option<RawVersionFile>,
+    #[serde(rename = "version-source")]
+    version_source: Option<RawVersionSource>,
+    #[serde(rename = "version-resolver")]
+    version_resolver: Option<RawVersionResolver>,
+    calver: Option<String>,
+    #[serde(rename = "build-metadata")]
+    build_metadata: Option<String>,
+    #[serde(rename = "ca-bundle")]
+    ca_bundle: Option<String>,
+    #[serde(rename = "discussion-category")]
+    discussion_category: Option<String>,
     #[serde(rename = "tag-template")]
     tag_template: Option<String>,
     #[serde(rename = "name-template")]
     name_template: Option<String>,
+    #[serde(rename = "categorize-by")]
+    categorize_by: Option<String>,
     categories: Option<Vec<RawCategory>>,
+    #[serde(rename = "group-by-scope")]
+    group_by_scope: Option<bool>,
+    scopes: Option<serde_yaml::Mapping>,
     #[serde(rename = "exclude-labels")]
     exclude_labels: Option<Vec<String>>,
+    #[serde(rename = "highlight-label")]
+    highlight_label: Option<String>,
+    #[serde(rename = "highlight-title")]
+    highlight_title: Option<String>,
     #[serde(rename = "change-template")]
     change_template: Option<String>,
     template: Option<String>,
+    #[serde(rename = "contributors-from")]
+    contributors_from: Option<String>,
+    repositories: Option<Vec<String>>,
+    #[serde(rename = "direct-commits")]
+    direct_commits: Option<bool>,
+    milestone: Option<String>,
+    #[serde(rename = "new-contributors")]
+    new_contributors: Option<bool>,
+    #[serde(rename = "exclude-contributors")]
+    exclude_contributors: Option<Vec<String>>,
+    #[serde(rename = "full-changelog")]
+    full_changelog: Option<bool>,
+    #[serde(rename = "sort-by")]
+    sort_by: Option<String>,
+    #[serde(rename = "sort-direction")]
+    sort_direction: Option<String>,
+    replacers: Option<Vec<RawReplacer>>,
+}
+
+#[derive(Deserialize)]
+struct RawReplacer {
+    search: String,
+    #[serde(default)]
+    replace: String,
+}
+
+impl RawReplacer {
+    fn compile(self) -> Result<Replacer> {
+        // Accept release-drafter's `/pattern/flags` form. Matching is always
+        // global and case-sensitive, so `g` is the only flag that means anything.
+        let source = match self
+            .search
+            .strip_prefix('/')
+            .and_then(|rest| rest.rsplit_once('/'))
+            .filter(|(pattern, flags)| {
+                !pattern.is_empty() && flags.chars().all(|c| c.is_ascii_lowercase())
+            }) {
+            Some((pattern, flags)) => {
+                if let Some(flag) = flags.chars().find(|&flag| flag != 'g') {
+                    bail!(
+                        "Unsupported replacers flag {flag} in {}; only g is accepted.",
+                        self.search
+                    );
+                }
+                pattern
+            }
+            None => &self.search,
+        };
+        if source.is_empty() {
+            bail!("replacers entries require a search pattern.");
+        }
+        let pattern = Pattern::new(source)
+            .with_context(|| format!("Invalid replacers search pattern: {}", self.search))?;
+        Ok(Replacer {
+            pattern,
+            replace: self.replace,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawVersionFile {
+    path: String,
+    pattern: String,
+}
+
+impl RawVersionFile {
+    fn compile(self) -> Result<VersionFile> {
+        let path = self.path.trim().to_string();
+        if path.is_empty() {
+            bail!("version-file requires a path.");
+        }
+        let pattern = Pattern::new(&self.pattern)
+            .with_context(|| format!("Invalid version-file pattern: {}", self.pattern))?;
+        if pattern.group_count() != 1 {
+            bail!(
+                "version-file pattern must have exactly one capture group; use (?:...) for other groups."
+            );
+        }
+        Ok(VersionFile { path, pattern })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawVersionSource {
+    path: String,
+    pointer: String,
+}
+
+impl RawVersionSource {
+    fn build(self) -> Result<VersionSource> {
+        let path = self.path.trim().to_string();
+        if path.is_empty() {
+            bail!("version-source requires a path.");
+        }
+        let pointer = self.pointer.trim().to_string();
+        if !pointer.starts_with('/') {
+            bail!("version-source pointer must start with /, e.g. /meta/version.");
+        }
+        Ok(VersionSource { path, pointer })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawVersionResolver {
+    from: Option<String>,
+    major: Option<RawBumpLabels>,
+    minor: Option<RawBumpLabels>,
+    patch: Option<RawBumpLabels>,
+    default: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawBumpLabels {
+    labels: OneOrMany,
+}
+
+impl RawVersionResolver {
+    fn build(self) -> Result<VersionResolver> {
+        let defaults = VersionResolver::default();
+        let labels = |raw: Option<RawBumpLabels>, fallback: Vec<String>| {
+            raw.map_or(fallback, |raw| normalize_labels(raw.labels.into_vec()))
+        };
+        let default = match self
+            .default
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("patch") => Bump::Patch,
+            Some("minor") => Bump::Minor,
+            Some("major") => Bump::Major,
+            Some(other) => {
+                bail!("Unknown version-resolver default: {other}. Expected major, minor, or patch.")
+            }
+        };
+        let source = match self
+            .from
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("labels") => BumpSource::Labels,
+            Some("conventional") => BumpSource::Conventional,
+            Some(other) => {
+                bail!("Unknown version-resolver from: {other}. Expected labels or conventional.")
+            }
+        };
+        Ok(VersionResolver {
+            source,
+            major_labels: labels(self.major, defaults.major_labels),
+            minor_labels: labels(self.minor, defaults.minor_labels),
+            patch_labels: labels(self.patch, defaults.patch_labels),
+            default,
+        })
+    }
 }
 
 #[derive(Deserialize)]
@@ -47,6 +393,24 @@ struct RawCategory {
     h3: Option<String>,
     labels: Option<Vec<String>>,
     label: Option<String>,
+    gitmoji: Option<OneOrMany>,
+    types: Option<OneOrMany>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
 }
 
 impl ReleaseConfig {
@@ -63,6 +427,8 @@ impl ReleaseConfig {
                     h3,
                     labels: raw_labels,
                     label,
+                    gitmoji,
+                    types,
                 } = category;
                 let (title, heading_level) = resolve_category_heading(title, h1, h2, h3)?;
                 let mut labels = Vec::new();
@@ -76,22 +442,171 @@ impl ReleaseConfig {
                     title,
                     heading_level,
                     labels: normalize_labels(labels),
+                    gitmoji: normalize_gitmoji(
+                        gitmoji.map(OneOrMany::into_vec).unwrap_or_default(),
+                    ),
+                    types: normalize_labels(types.map(OneOrMany::into_vec).unwrap_or_default()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let categorize_by = match raw
+            .categorize_by
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("labels") => CategorizeBy::Labels,
+            Some("gitmoji") => CategorizeBy::Gitmoji,
+            Some("conventional") => CategorizeBy::Conventional,
+            Some(other) => bail!(
+                "Unknown categorize-by value: {other}. Expected labels, gitmoji, or conventional."
+            ),
+        };
+        let contributors_from = match raw
+            .contributors_from
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("pull-requests") => ContributorsFrom::PullRequests,
+            Some("commits") => ContributorsFrom::Commits,
+            Some(other) => bail!(
+                "Unknown contributors-from value: {other}. Expected pull-requests or commits."
+            ),
+        };
+        let sort_by = match raw
+            .sort_by
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("merged_at") => SortBy::MergedAt,
+            Some("title") => SortBy::Title,
+            Some("number") => SortBy::Number,
+            Some(other) => {
+                bail!("Unknown sort-by value: {other}. Expected merged_at, title, or number.")
+            }
+        };
+        let sort_direction = match raw
+            .sort_direction
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("ascending") => SortDirection::Ascending,
+            Some("descending") => SortDirection::Descending,
+            Some(other) => {
+                bail!("Unknown sort-direction value: {other}. Expected ascending or descending.")
+            }
+        };
+        let categories = match categorize_by {
+            _ if !categories.is_empty() => categories,
+            CategorizeBy::Labels => categories,
+            CategorizeBy::Gitmoji => {
+                default_categories(DEFAULT_GITMOJI_CATEGORIES, |category, codes| {
+                    category.gitmoji = codes
                 })
+            }
+            CategorizeBy::Conventional => {
+                default_categories(DEFAULT_CONVENTIONAL_CATEGORIES, |category, types| {
+                    category.types = types
+                })
+            }
+        };
+        let scopes = raw
+            .scopes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| match (key, value) {
+                (serde_yaml::Value::String(key), serde_yaml::Value::String(value)) => {
+                    Ok((key.trim().to_lowercase(), value.trim().to_string()))
+                }
+                _ => bail!("Scopes must map scope names to display names."),
             })
             .collect::<Result<Vec<_>>>()?;
 
+        let mut repositories: Vec<String> = Vec::new();
+        for repository in raw.repositories.unwrap_or_default() {
+            let repository = repository.trim().to_string();
+            let valid = repository.split_once('/').is_some_and(|(owner, repo)| {
+                !owner.is_empty() && !repo.is_empty() && !repo.contains('/')
+            });
+            if !valid {
+                bail!("Invalid repositories entry {repository}; expected owner/repo.");
+            }
+            if !repositories.contains(&repository) {
+                repositories.push(repository);
+            }
+        }
+
+        if raw.version_resolver.is_some() && raw.calver.is_some() {
+            bail!("Use either version-resolver or calver, not both.");
+        }
+
         Ok(ReleaseConfig {
             language: raw.language.map(|value| value.trim().to_lowercase()),
+            crate_name: non_empty(raw.crate_name),
+            package_name: non_empty(raw.package_name),
+            version_file: raw.version_file.map(RawVersionFile::compile).transpose()?,
+            version_source: raw
+                .version_source
+                .map(RawVersionSource::build)
+                .transpose()?,
+            version_resolver: raw
+                .version_resolver
+                .map(RawVersionResolver::build)
+                .transpose()?,
+            calver: raw.calver.as_deref().map(CalVer::new).transpose()?,
+            build_metadata: non_empty(raw.build_metadata),
+            ca_bundle: non_empty(raw.ca_bundle),
+            discussion_category: non_empty(raw.discussion_category),
             tag_template: raw.tag_template.map(|value| value.trim().to_string()),
             name_template: raw.name_template.map(|value| value.trim().to_string()),
+            categorize_by,
             categories,
+            group_by_scope: raw
+                .group_by_scope
+                .unwrap_or(categorize_by == CategorizeBy::Conventional),
+            scopes,
             exclude_labels: normalize_labels(raw.exclude_labels.unwrap_or_default()),
+            highlight_label: raw
+                .highlight_label
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| DEFAULT_HIGHLIGHT_LABEL.to_string()),
+            highlight_title: raw
+                .highlight_title
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| DEFAULT_HIGHLIGHT_TITLE.to_string()),
             change_template: raw
                 .change_template
                 .map(|value| value.trim().to_string())
                 .filter(|value| !value.is_empty())
                 .unwrap_or_else(|| DEFAULT_CHANGE_TEMPLATE.to_string()),
             template: raw.template.map(|value| value.trim().to_string()),
+            contributors_from,
+            repositories,
+            direct_commits: raw.direct_commits.unwrap_or(false),
+            milestone: non_empty(raw.milestone),
+            new_contributors: raw.new_contributors.unwrap_or(false),
+            exclude_contributors: raw
+                .exclude_contributors
+                .unwrap_or_default()
+                .into_iter()
+                .map(|login| login.trim().trim_start_matches('@').to_string())
+                .filter(|login| !login.is_empty())
+                .collect(),
+            full_changelog: raw.full_changelog.unwrap_or(false),
+            sort_by,
+            sort_direction,
+            replacers: raw
+                .replacers
+                .unwrap_or_default()
+                .into_iter()
+                .map(RawReplacer::compile)
+                .collect::<Result<_>>()?,
         })
     }
 }
@@ -141,11 +656,21 @@ fn resolve_path(input: &str, cwd: &Path) -> Result<PathBuf> {
 fn read_config(path: &Path) -> Result<ReleaseConfig> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    parse_config(&content)
+}
+
+pub fn parse_config(content: &str) -> Result<ReleaseConfig> {
     let raw: RawConfig =
-        serde_yaml::from_str(&content).map_err(|error| anyhow!("Invalid config YAML: {error}"))?;
+        serde_yaml::from_str(content).map_err(|error| anyhow!("Invalid config YAML: {error}"))?;
     ReleaseConfig::from_raw(raw)
 }
 
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 fn normalize_labels(labels: Vec<String>) -> Vec<String> {
     labels
         .into_iter()
@@ -154,6 +679,42 @@ fn normalize_labels(labels: Vec<String>) -> Vec<String> {
         .collect()
 }
 
+/// Drops emoji variation selectors so `⚡️` and `⚡` compare equal.
+pub fn strip_variation_selectors(value: &str) -> String {
+    value.chars().filter(|c| *c != '\u{fe0f}').collect()
+}
+
+fn normalize_gitmoji(values: Vec<String>) -> Vec<String> {
+    values
+        .iter()
+        .map(|value| strip_variation_selectors(value.trim()))
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn default_categories(
+    defaults: &[(&str, &[&str])],
+    assign: impl Fn(&mut ReleaseCategory, Vec<String>),
+) -> Vec<ReleaseCategory> {
+    defaults
+        .iter()
+        .map(|(title, keys)| {
+            let mut category = ReleaseCategory {
+                title: title.to_string(),
+                heading_level: DEFAULT_CATEGORY_HEADING_LEVEL,
+                labels: Vec::new(),
+                gitmoji: Vec::new(),
+                types: Vec::new(),
+            };
+            assign(
+                &mut category,
+                keys.iter().map(|key| key.to_string()).collect(),
+            );
+            category
+        })
+        .collect()
+}
+
 fn resolve_category_heading(
     title: Option<String>,
     h1: Option<String>,
@@ -185,11 +746,6 @@ fn resolve_category_heading(
 mod tests {
     use super::*;
 
-    fn parse_config(yaml: &str) -> Result<ReleaseConfig> {
-        let raw: RawConfig = serde_yaml::from_str(yaml)?;
-        ReleaseConfig::from_raw(raw)
-    }
-
     #[test]
     fn parses_title_as_h2() {
         let config = parse_config(
@@ -255,4 +811,254 @@ categories:
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn gitmoji_mode_uses_default_categories() {
+        let config = parse_config("categorize-by: gitmoji").unwrap();
+
+        assert_eq!(config.categorize_by, CategorizeBy::Gitmoji);
+        assert_eq!(config.categories[0].title, "Features");
+        assert!(config.categories[0].gitmoji.contains(&"✨".to_string()));
+    }
+
+    #[test]
+    fn parses_custom_gitmoji_mapping() {
+        let config = parse_config(
+            r#"
+categorize-by: gitmoji
+categories:
+  - title: Speed
+    gitmoji: "⚡️"
+  - title: Chores
+    gitmoji: [":wrench:", "🔧"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.categories.len(), 2);
+        assert_eq!(config.categories[0].gitmoji, vec!["⚡".to_string()]);
+        assert_eq!(config.categories[1].gitmoji.len(), 2);
+    }
+
+    #[test]
+    fn conventional_mode_groups_by_scope() {
+        let config = parse_config(
+            r#"
+categorize-by: conventional
+scopes:
+  cli: Command line
+  API: Public API
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.categorize_by, CategorizeBy::Conventional);
+        assert!(config.group_by_scope);
+        assert_eq!(config.categories[1].types, vec!["feat".to_string()]);
+        assert_eq!(
+            config.scopes,
+            vec![
+                ("cli".to_string(), "Command line".to_string()),
+                ("api".to_string(), "Public API".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_version_file() {
+        let config = parse_config(
+            r#"
+version-file:
+  path: src/app/__init__.py
+  pattern: '__version__ = "([^"]+)"'
+"#,
+        )
+        .unwrap();
+        let version_file = config.version_file.unwrap();
+
+        assert_eq!(version_file.path, "src/app/__init__.py");
+        assert_eq!(
+            version_file
+                .pattern
+                .captures("__version__ = \"0.2.0\"")
+                .and_then(|captures| captures.get(1)),
+            Some("0.2.0")
+        );
+        assert!(parse_config("version-file:\n  path: VERSION\n  pattern: '\\d+'\n").is_err());
+        assert!(parse_config("version-file:\n  path: VERSION\n  pattern: '(\\d+'\n").is_err());
+    }
+
+    #[test]
+    fn parses_version_source() {
+        let config = parse_config(
+            "version-source: { path: \"app/manifest.json\", pointer: \"/meta/version\" }\n",
+        )
+        .unwrap();
+        let version_source = config.version_source.unwrap();
+
+        assert_eq!(version_source.path, "app/manifest.json");
+        assert_eq!(version_source.pointer, "/meta/version");
+        assert!(parse_config("version-source:\n  path: a.json\n  pointer: meta\n").is_err());
+    }
+
+    #[test]
+    fn parses_calver() {
+        let config = parse_config("calver: YYYY.0M.MICRO\n").unwrap();
+
+        assert_eq!(config.calver, Some(CalVer::new("YYYY.0M.MICRO").unwrap()));
+        assert!(parse_config("calver: version\n").is_err());
+        assert!(parse_config("calver: YYYY.MICRO\nversion-resolver:\n  default: minor\n").is_err());
+    }
+
+    #[test]
+    fn parses_ca_bundle() {
+        let config = parse_config("ca-bundle: certs/ghes-ca.pem\n").unwrap();
+
+        assert_eq!(config.ca_bundle.as_deref(), Some("certs/ghes-ca.pem"));
+        assert_eq!(parse_config("ca-bundle: \"\"\n").unwrap().ca_bundle, None);
+    }
+
+    #[test]
+    fn parses_discussion_category() {
+        let config = parse_config("discussion-category: Announcements\n").unwrap();
+
+        assert_eq!(config.discussion_category.as_deref(), Some("Announcements"));
+    }
+
+    #[test]
+    fn parses_version_resolver() {
+        let config = parse_config(
+            r#"
+version-resolver:
+  major:
+    labels: [breaking]
+  minor:
+    labels: [feature, enhancement]
+  default: minor
+"#,
+        )
+        .unwrap();
+        let resolver = config.version_resolver.unwrap();
+
+        assert_eq!(resolver.major_labels, vec!["breaking".to_string()]);
+        assert_eq!(resolver.minor_labels.len(), 2);
+        assert_eq!(resolver.patch_labels, vec!["patch".to_string()]);
+        assert_eq!(resolver.default, Bump::Minor);
+        assert_eq!(resolver.source, BumpSource::Labels);
+        assert!(parse_config("version-resolver:\n  default: huge\n").is_err());
+        let conventional = parse_config("version-resolver:\n  from: conventional\n").unwrap();
+        assert_eq!(
+            conventional.version_resolver.unwrap().source,
+            BumpSource::Conventional
+        );
+    }
+
+    #[test]
+    fn parses_contributors_from() {
+        assert_eq!(
+            parse_config("contributors-from: commits")
+                .unwrap()
+                .contributors_from,
+            ContributorsFrom::Commits
+        );
+        assert_eq!(
+            parse_config("template: $CHANGES")
+                .unwrap()
+                .contributors_from,
+            ContributorsFrom::PullRequests
+        );
+        assert!(parse_config("contributors-from: everyone").is_err());
+    }
+
+    #[test]
+    fn parses_repositories() {
+        let config =
+            parse_config("repositories:\n  - acme/api\n  - ' acme/web '\n  - acme/api\n").unwrap();
+        assert_eq!(config.repositories, vec!["acme/api", "acme/web"]);
+        assert!(parse_config("repositories:\n  - acme\n").is_err());
+        assert!(parse_config("repositories:\n  - acme/api/extra\n").is_err());
+    }
+
+    #[test]
+    fn parses_direct_commits() {
+        assert!(parse_config("direct-commits: true").unwrap().direct_commits);
+        assert!(!parse_config("template: $CHANGES").unwrap().direct_commits);
+    }
+
+    #[test]
+    fn parses_milestone() {
+        let config = parse_config("milestone: \" v2.0 \"").unwrap();
+        assert_eq!(config.milestone.as_deref(), Some("v2.0"));
+        assert!(parse_config("milestone: \"\"").unwrap().milestone.is_none());
+    }
+
+    #[test]
+    fn parses_new_contributors() {
+        assert!(
+            parse_config("new-contributors: true")
+                .unwrap()
+                .new_contributors
+        );
+        assert!(!parse_config("template: $CHANGES").unwrap().new_contributors);
+    }
+
+    #[test]
+    fn parses_exclude_contributors() {
+        let config =
+            parse_config("exclude-contributors:\n  - dependabot[bot]\n  - \" @renovate[bot] \"\n")
+                .unwrap();
+        assert_eq!(
+            config.exclude_contributors,
+            vec!["dependabot[bot]", "renovate[bot]"]
+        );
+    }
+
+    #[test]
+    fn parses_full_changelog() {
+        assert!(parse_config("full-changelog: true").unwrap().full_changelog);
+        assert!(!parse_config("template: $CHANGES").unwrap().full_changelog);
+    }
+
+    #[test]
+    fn parses_sort_order() {
+        let config = parse_config("sort-by: title\nsort-direction: Descending").unwrap();
+        assert_eq!(config.sort_by, SortBy::Title);
+        assert_eq!(config.sort_direction, SortDirection::Descending);
+
+        let config = parse_config("template: $CHANGES").unwrap();
+        assert_eq!(config.sort_by, SortBy::MergedAt);
+        assert_eq!(config.sort_direction, SortDirection::Ascending);
+        assert!(parse_config("sort-by: author").is_err());
+        assert!(parse_config("sort-direction: sideways").is_err());
+    }
+
+    #[test]
+    fn parses_replacers() {
+        let config = parse_config(
+            "replacers:\n  - search: '/CVE-(\\d{4})-(\\d+)/g'\n    replace: 'CVE $1-$2'\n  - search: '^chore: '\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.replacers.len(), 2);
+        assert_eq!(
+            config.replacers[0]
+                .pattern
+                .replace_all("Fix CVE-2024-1234", &config.replacers[0].replace),
+            "Fix CVE 2024-1234"
+        );
+        assert_eq!(config.replacers[1].replace, "");
+        assert!(parse_config("replacers:\n  - search: '('\n").is_err());
+        assert!(parse_config("replacers:\n  - search: ''\n").is_err());
+        assert!(parse_config("replacers:\n  - search: '/fix/'\n").is_ok());
+        assert!(parse_config("replacers:\n  - search: '/fix/gi'\n").is_err());
+        // A path isn't read as a pattern with flags.
+        let config =
+            parse_config("replacers:\n  - search: '/api/v1'\n    replace: '/api/v2'\n").unwrap();
+        assert_eq!(
+            config.replacers[0]
+                .pattern
+                .replace_all("GET /api/v1/users", &config.replacers[0].replace),
+            "GET /api/v2/users"
+        );
+    }
 }
@@ -0,0 +1,1213 @@
+use crate::github::{
+    self, AssetInfo, ClientOptions, CommitInfo, FetchLimits, GitHubStatus, MergedPullRequests,
+    PullRequestApi, ReleaseAccess, ReleaseApi, ReleaseFields, ReleaseInfo, TagInfo,
+};
+use crate::release_notes::{IssueReference, PullRequestInfo};
+use crate::signing::Tagger;
+use crate::version::{DirEntry, TagSource};
+use anyhow::{Context, Result, anyhow, bail};
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Gitea caps list responses at `MAX_RESPONSE_ITEMS`, 50 by default.
+const PAGE_LIMIT: u32 = 50;
+const MULTIPART_BOUNDARY: &str = "breezy-asset-boundary-7f3a9c";
+
+#[derive(Deserialize)]
+struct GiteaRelease {
+    id: u64,
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    target_commitish: String,
+    created_at: String,
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<GiteaAsset>,
+}
+
+#[derive(Deserialize)]
+struct GiteaAsset {
+    id: u64,
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Serialize)]
+struct GiteaReleaseRequest<'a> {
+    tag_name: &'a str,
+    name: &'a str,
+    body: &'a str,
+    prerelease: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_commitish: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    draft: Option<bool>,
+}
+
+impl<'a> GiteaReleaseRequest<'a> {
+    fn new(fields: &ReleaseFields<'a>, draft: Option<bool>) -> Self {
+        Self {
+            tag_name: fields.tag_name,
+            name: fields.name,
+            body: fields.body,
+            prerelease: fields.prerelease,
+            target_commitish: fields.target_commitish,
+            draft,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GiteaTagRequest<'a> {
+    tag_name: &'a str,
+    target: &'a str,
+    message: &'a str,
+}
+
+#[derive(Deserialize)]
+struct GiteaPullRequest {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    user: Option<GiteaUser>,
+    #[serde(default)]
+    labels: Vec<GiteaLabel>,
+    #[serde(default)]
+    merged: bool,
+    merged_at: Option<String>,
+    updated_at: String,
+    html_url: String,
+    base: GiteaBranch,
+    #[serde(default)]
+    milestone: Option<github::Milestone>,
+}
+
+#[derive(Deserialize)]
+struct GiteaMilestone {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct GiteaBranch {
+    #[serde(rename = "ref")]
+    reference: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaUserProfile {
+    #[serde(default)]
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaReview {
+    user: Option<GiteaUser>,
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaCommit {
+    sha: String,
+    author: Option<GiteaUser>,
+    commit: GiteaCommitDetails,
+}
+
+#[derive(Deserialize)]
+struct GiteaCommitDetails {
+    committer: Option<GiteaCommitter>,
+}
+
+#[derive(Deserialize)]
+struct GiteaCommitter {
+    date: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GiteaRepository {
+    permissions: Option<GiteaPermissions>,
+}
+
+#[derive(Deserialize)]
+struct GiteaPermissions {
+    #[serde(default)]
+    pull: bool,
+    #[serde(default)]
+    push: bool,
+}
+
+#[derive(Deserialize)]
+struct GiteaContentEntry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Gitea silently clamps `limit` to its configured maximum, which would read
+/// as a short final page, so never ask for more than the default maximum.
+fn page_limit(per_page: u32) -> u32 {
+    per_page.clamp(1, PAGE_LIMIT)
+}
+
+/// Rewrites a Gitea timestamp, which carries the server's local offset such as
+/// `+02:00`, in UTC like GitHub's, so later comparisons and sorts of the text
+/// line up. An unreadable timestamp is kept as is.
+fn utc_timestamp(timestamp: String) -> String {
+    crate::app_auth::parse_timestamp(&timestamp).map_or(timestamp, github::format_timestamp)
+}
+
+/// Keeps the closed PRs that were merged into `branch` inside the window.
+/// Returns `false` once a PR last updated before `since` is seen, since every
+/// later one in `recentupdate` order is older still.
+fn collect_gitea_pull_requests(
+    pulls: Vec<GiteaPullRequest>,
+    branch: &str,
+    since: Option<&str>,
+    until: Option<&str>,
+    pull_requests: &mut Vec<PullRequestInfo>,
+) -> bool {
+    for pull in pulls {
+        if since.is_some_and(|since| github::is_before(&pull.updated_at, since)) {
+            return false;
+        }
+        let Some(merged_at) = pull.merged_at.filter(|_| pull.merged) else {
+            continue;
+        };
+        if pull.base.reference != branch
+            || since.is_some_and(|since| github::is_before(&merged_at, since))
+            || until.is_some_and(|until| github::is_before(until, &merged_at))
+        {
+            continue;
+        }
+        pull_requests.push(PullRequestInfo {
+            number: pull.number,
+            title: pull.title,
+            body: pull.body,
+            author: pull
+                .user
+                .map(|user| user.login)
+                .unwrap_or_else(|| "unknown".to_string()),
+            labels: pull.labels.into_iter().map(|label| label.name).collect(),
+            url: pull.html_url,
+            merged_at: Some(utc_timestamp(merged_at)),
+            milestone: pull.milestone.map(|milestone| milestone.title),
+            ..PullRequestInfo::default()
+        });
+    }
+    true
+}
+
+/// Wraps `content` as the `attachment` field of a `multipart/form-data` body,
+/// which is how every Gitea version accepts asset uploads.
+fn multipart_attachment(name: &str, content: &[u8]) -> Vec<u8> {
+    let filename = name.replace(['"', '\r', '\n'], "_");
+    let mut body = format!(
+        "--{MULTIPART_BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"attachment\"; filename=\"{filename}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{MULTIPART_BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+/// Releases and PRs on a Gitea or Forgejo server. The API mirrors GitHub's
+/// closely enough to share [`ReleaseApi`], with a few gaps: no search, no
+/// closing-issue references, no "Latest" flag or release discussions, and
+/// tags are created by the server, so they can't carry a signature.
+pub struct GiteaClient {
+    client: Client,
+    api_base: String,
+    owner: String,
+    repo: String,
+    max_attempts: u32,
+    max_pages: Option<u32>,
+    http_timeout: Option<Duration>,
+    debug: bool,
+    /// Release id and download URL for each asset seen, since Gitea addresses
+    /// assets through their release.
+    assets: Mutex<HashMap<u64, (u64, String)>>,
+}
+
+impl GiteaClient {
+    /// `api_base` is the server's API root, e.g. `https://codeberg.org/api/v1`.
+    pub fn new(
+        api_base: &str,
+        token: &str,
+        owner: &str,
+        repo: &str,
+        options: &ClientOptions,
+    ) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        headers.insert(USER_AGENT, HeaderValue::from_static("breezy"));
+        let mut auth = HeaderValue::from_str(&format!("token {token}"))?;
+        auth.set_sensitive(true);
+        headers.insert(AUTHORIZATION, auth);
+
+        Ok(Self {
+            client: github::build_http_client(headers, options)?,
+            api_base: api_base.trim().trim_end_matches('/').to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            max_attempts: options.max_attempts.max(1),
+            max_pages: options.max_pages,
+            http_timeout: options.http_timeout,
+            debug: options.debug,
+            assets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn repo_url(&self, path: &str) -> String {
+        format!("{}/repos/{}/{}{path}", self.api_base, self.owner, self.repo)
+    }
+
+    fn send(&self, request: RequestBuilder, send_context: &'static str) -> Result<Response> {
+        let request = github::timed_request(request, self.http_timeout, send_context)?;
+        self.execute(request, send_context)
+    }
+
+    /// Sends an asset upload or download without `http-timeout`.
+    fn send_transfer(
+        &self,
+        request: RequestBuilder,
+        send_context: &'static str,
+    ) -> Result<Response> {
+        let request = request.build().context(send_context)?;
+        self.execute(request, send_context)
+    }
+
+    /// Sends a request, retrying gateway errors and dropped connections.
+    fn execute(&self, request: Request, send_context: &'static str) -> Result<Response> {
+        let mut request = request;
+        let mut attempt = 1;
+        loop {
+            let retry = if attempt < self.max_attempts {
+                request.try_clone()
+            } else {
+                None
+            };
+            let method = request.method().clone();
+            let traced = self.debug.then(|| {
+                (
+                    request.method().clone(),
+                    request.url().clone(),
+                    Instant::now(),
+                )
+            });
+            let outcome = self.client.execute(request);
+            if let Some((method, url, started)) = traced {
+                println!(
+                    "{}",
+                    github::request_debug_line(&method, &url, &outcome, started.elapsed())
+                );
+            }
+            let reason = match &outcome {
+                Ok(response) => {
+                    github::retryable_status(&method, response.status(), response.headers())
+                }
+                Err(error) => github::retryable_error(&method, error),
+            };
+            match (reason, retry) {
+                (Some(reason), Some(next)) => {
+                    let delay = github::backoff_delay(attempt, github::random_jitter());
+                    github::check_deadline(delay).context(send_context)?;
+                    println!(
+                        "Gitea request failed with {reason}; retrying in {}ms (attempt {} of {})",
+                        delay.as_millis(),
+                        attempt + 1,
+                        self.max_attempts
+                    );
+                    thread::sleep(delay);
+                    request = next;
+                    attempt += 1;
+                }
+                _ => return outcome.context(send_context),
+            }
+        }
+    }
+
+    fn get_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        send_context: &'static str,
+        status_context: &'static str,
+    ) -> Result<T> {
+        Ok(self
+            .send(request, send_context)?
+            .error_for_github_status()
+            .context(status_context)?
+            .json()?)
+    }
+
+    /// Reads every page of a list endpoint; Gitea signals the end with a
+    /// short page.
+    fn get_all_pages<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        per_page: u32,
+        send_context: &'static str,
+        status_context: &'static str,
+    ) -> Result<Vec<T>> {
+        let contexts = (send_context, status_context);
+        self.get_pages_until(url, per_page, contexts, |item| item, &mut |_| false)
+    }
+
+    /// Pages through `url`, converting each item, until a short page or until
+    /// `enough` is satisfied by the items so far.
+    fn get_pages_until<T: DeserializeOwned, U>(
+        &self,
+        url: &str,
+        per_page: u32,
+        (send_context, status_context): (&'static str, &'static str),
+        convert: impl Fn(T) -> U,
+        enough: &mut dyn FnMut(&[U]) -> bool,
+    ) -> Result<Vec<U>> {
+        let limit = page_limit(per_page);
+        let mut items = Vec::new();
+        for page in 1.. {
+            let batch: Vec<T> = self.get_json(
+                self.client
+                    .get(url)
+                    .query(&[("page", page), ("limit", limit)]),
+                send_context,
+                status_context,
+            )?;
+            let done = batch.len() < limit as usize;
+            items.extend(batch.into_iter().map(&convert));
+            if done || enough(&items) {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn remember_asset(&self, release_id: u64, asset: &GiteaAsset) {
+        self.assets
+            .lock()
+            .unwrap()
+            .insert(asset.id, (release_id, asset.browser_download_url.clone()));
+    }
+
+    fn known_asset(&self, asset_id: u64) -> Result<(u64, String)> {
+        self.assets
+            .lock()
+            .unwrap()
+            .get(&asset_id)
+            .cloned()
+            .with_context(|| format!("Release asset {asset_id} is not on a listed release."))
+    }
+
+    fn release_info(&self, release: GiteaRelease) -> ReleaseInfo {
+        for asset in &release.assets {
+            self.remember_asset(release.id, asset);
+        }
+        ReleaseInfo {
+            id: release.id,
+            tag_name: release.tag_name,
+            name: release.name,
+            body: release.body,
+            draft: release.draft,
+            prerelease: release.prerelease,
+            target_commitish: release.target_commitish,
+            created_at: utc_timestamp(release.created_at),
+            published_at: release.published_at.map(utc_timestamp),
+            assets: release
+                .assets
+                .into_iter()
+                .map(|asset| AssetInfo {
+                    id: asset.id,
+                    name: asset.name,
+                    digest: None,
+                })
+                .collect(),
+            upload_url: None,
+        }
+    }
+
+    fn save_release(
+        &self,
+        request: RequestBuilder,
+        send_context: &'static str,
+        status_context: &'static str,
+    ) -> Result<ReleaseInfo> {
+        let release: GiteaRelease = self.get_json(request, send_context, status_context)?;
+        Ok(self.release_info(release))
+    }
+
+    /// Lists the closed PRs merged into `branch` between `since` and `until`,
+    /// newest first, optionally only those in the milestone with id
+    /// `milestone`.
+    fn list_merged_pull_requests(
+        &self,
+        branch: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+        milestone: Option<u64>,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        github::with_deadline(limits, || {
+            self.list_pull_request_pages(branch, since, until, milestone, per_page, limits)
+        })
+    }
+
+    fn list_pull_request_pages(
+        &self,
+        branch: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+        milestone: Option<u64>,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        let url = self.repo_url("/pulls");
+        let limit = page_limit(per_page);
+        let mut pull_requests = Vec::new();
+        let started = Instant::now();
+        let mut truncated = None;
+        for page in 1.. {
+            let mut request = self.client.get(&url).query(&[
+                ("state", "closed"),
+                ("sort", "recentupdate"),
+                ("page", &page.to_string()),
+                ("limit", &limit.to_string()),
+            ]);
+            if let Some(milestone) = milestone {
+                request = request.query(&[("milestone", milestone)]);
+            }
+            let pulls: Vec<GiteaPullRequest> = match self.get_json(
+                request,
+                "Failed to list pull requests.",
+                "Gitea pull requests request returned an error.",
+            ) {
+                Ok(pulls) => pulls,
+                Err(error) => {
+                    truncated = Some(github::deadline_reason(error)?);
+                    break;
+                }
+            };
+            let done = pulls.len() < limit as usize;
+            let more = collect_gitea_pull_requests(pulls, branch, since, until, &mut pull_requests);
+            if done || !more {
+                break;
+            }
+            if let Some(reason) = limits
+                .exceeded(pull_requests.len(), started.elapsed())
+                .or_else(|| github::page_cap_reached(self.max_pages, page))
+            {
+                truncated = Some(reason);
+                break;
+            }
+        }
+
+        Ok(github::finish_merged_pull_requests(
+            pull_requests,
+            truncated,
+            limits,
+        ))
+    }
+}
+
+impl TagSource for GiteaClient {
+    fn tag_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list_all_tags(PAGE_LIMIT)?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect())
+    }
+}
+
+impl ReleaseApi for GiteaClient {
+    fn allow_optional(&self, _feature: &str) -> bool {
+        true
+    }
+
+    fn degraded_features(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn repository_url(&self) -> String {
+        let server = self
+            .api_base
+            .strip_suffix("/api/v1")
+            .unwrap_or(&self.api_base);
+        format!("{server}/{}/{}", self.owner, self.repo)
+    }
+
+    fn list_releases(
+        &self,
+        per_page: u32,
+        enough: &mut dyn FnMut(&[ReleaseInfo]) -> bool,
+    ) -> Result<Vec<ReleaseInfo>> {
+        self.get_pages_until(
+            &self.repo_url("/releases"),
+            per_page,
+            (
+                "Failed to list releases.",
+                "Gitea releases request returned an error.",
+            ),
+            |release: GiteaRelease| self.release_info(release),
+            enough,
+        )
+    }
+
+    fn delete_release(&self, release_id: u64) -> Result<()> {
+        self.send(
+            self.client
+                .delete(self.repo_url(&format!("/releases/{release_id}"))),
+            "Failed to delete release.",
+        )?
+        .error_for_github_status()
+        .context("Gitea release delete request returned an error.")?;
+        Ok(())
+    }
+
+    fn upload_release_asset(
+        &self,
+        release: &ReleaseInfo,
+        name: &str,
+        content: Vec<u8>,
+    ) -> Result<AssetInfo> {
+        let release_id = release.id;
+        let request = self
+            .client
+            .post(self.repo_url(&format!("/releases/{release_id}/assets")))
+            .query(&[("name", name)])
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+            )
+            .body(multipart_attachment(name, &content));
+        let asset: GiteaAsset = self
+            .send_transfer(request, "Failed to upload release asset.")?
+            .error_for_github_status()
+            .context("Gitea release asset upload returned an error.")?
+            .json()?;
+        self.remember_asset(release_id, &asset);
+        Ok(AssetInfo {
+            id: asset.id,
+            name: asset.name,
+            digest: None,
+        })
+    }
+
+    fn download_release_asset(&self, asset_id: u64) -> Result<Vec<u8>> {
+        let (_, url) = self.known_asset(asset_id)?;
+        let response = self
+            .send_transfer(
+                self.client
+                    .get(url)
+                    .header(ACCEPT, "application/octet-stream"),
+                "Failed to download release asset.",
+            )?
+            .error_for_github_status()
+            .context("Gitea release asset download returned an error.")?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    fn delete_release_asset(&self, asset_id: u64) -> Result<()> {
+        let (release_id, _) = self.known_asset(asset_id)?;
+        self.send(
+            self.client
+                .delete(self.repo_url(&format!("/releases/{release_id}/assets/{asset_id}"))),
+            "Failed to delete release asset.",
+        )?
+        .error_for_github_status()
+        .context("Gitea release asset delete request returned an error.")?;
+        self.assets.lock().unwrap().remove(&asset_id);
+        Ok(())
+    }
+
+    fn update_release(&self, release_id: u64, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+        self.save_release(
+            self.client
+                .patch(self.repo_url(&format!("/releases/{release_id}")))
+                .json(&GiteaReleaseRequest::new(fields, None)),
+            "Failed to update release.",
+            "Gitea release update request returned an error.",
+        )
+    }
+
+    fn create_release(&self, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+        self.save_release(
+            self.client
+                .post(self.repo_url("/releases"))
+                .json(&GiteaReleaseRequest::new(fields, Some(true))),
+            "Failed to create release.",
+            "Gitea release create request returned an error.",
+        )
+    }
+
+    fn update_release_notes(&self, release_id: u64, name: &str, body: &str) -> Result<ReleaseInfo> {
+        self.save_release(
+            self.client
+                .patch(self.repo_url(&format!("/releases/{release_id}")))
+                .json(&github::ReleaseNotesRequest { name, body }),
+            "Failed to update release notes.",
+            "Gitea release notes update request returned an error.",
+        )
+    }
+
+    fn publish_release(
+        &self,
+        release_id: u64,
+        target_commitish: Option<&str>,
+        fields: &ReleaseFields,
+    ) -> Result<ReleaseInfo> {
+        if fields.make_latest.is_some() || fields.discussion_category_name.is_some() {
+            println!("Gitea has no Latest flag or release discussions; ignoring them");
+        }
+        let mut request = GiteaReleaseRequest::new(fields, Some(false));
+        request.target_commitish = target_commitish.or(fields.target_commitish);
+        self.save_release(
+            self.client
+                .patch(self.repo_url(&format!("/releases/{release_id}")))
+                .json(&request),
+            "Failed to publish release.",
+            "Gitea release publish request returned an error.",
+        )
+    }
+
+    fn check_release_access(&self) -> Result<ReleaseAccess> {
+        let response = self.send(
+            self.client.get(self.repo_url("")),
+            "Failed to check release access.",
+        )?;
+        if matches!(
+            response.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+        ) {
+            return Ok(ReleaseAccess {
+                read: false,
+                write: false,
+            });
+        }
+        let repository: GiteaRepository = response
+            .error_for_github_status()
+            .context("Gitea repository request returned an error.")?
+            .json()?;
+        // Anonymous reads of a public repository carry no permissions.
+        let permissions = repository.permissions.unwrap_or(GiteaPermissions {
+            pull: true,
+            push: false,
+        });
+        Ok(ReleaseAccess {
+            read: permissions.pull || permissions.push,
+            write: permissions.push,
+        })
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        let response = self.send(
+            self.client.get(self.repo_url(&format!("/tags/{tag}"))),
+            "Failed to look up tag.",
+        )?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        response
+            .error_for_github_status()
+            .context("Gitea tag lookup returned an error.")?;
+        Ok(true)
+    }
+
+    fn fetch_release_by_tag(&self, tag: &str) -> Result<Option<ReleaseInfo>> {
+        let response = self.send(
+            self.client
+                .get(self.repo_url(&format!("/releases/tags/{tag}"))),
+            "Failed to look up release.",
+        )?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let release: GiteaRelease = response
+            .error_for_github_status()
+            .context("Gitea release lookup returned an error.")?
+            .json()?;
+        Ok(Some(self.release_info(release)).filter(|release| !release.draft))
+    }
+
+    fn fetch_commit_authors(
+        &self,
+        base_tag: &str,
+        branch: &str,
+        per_page: u32,
+    ) -> Result<Vec<String>> {
+        let base = self.fetch_commit(base_tag)?;
+        let limit = page_limit(per_page);
+        let mut logins: Vec<String> = Vec::new();
+        // Gitea's compare response varies by version, so walk the branch's
+        // history back to the tag instead.
+        'pages: for page in 1.. {
+            let commits: Vec<GiteaCommit> = self.get_json(
+                self.client.get(self.repo_url("/commits")).query(&[
+                    ("sha", branch),
+                    ("stat", "false"),
+                    ("files", "false"),
+                    ("verification", "false"),
+                    ("page", &page.to_string()),
+                    ("limit", &limit.to_string()),
+                ]),
+                "Failed to list commits.",
+                "Gitea commits request returned an error.",
+            )?;
+            let done = commits.len() < limit as usize;
+            for commit in commits {
+                let committed_at = commit.commit.committer.and_then(|committer| committer.date);
+                if commit.sha == base.sha
+                    || base
+                        .committed_at
+                        .as_deref()
+                        .zip(committed_at.as_deref())
+                        .is_some_and(|(base, date)| github::is_before(date, base))
+                {
+                    break 'pages;
+                }
+                if let Some(author) = commit.author
+                    && !logins.contains(&author.login)
+                {
+                    logins.push(author.login);
+                }
+            }
+            if done {
+                break;
+            }
+            if let Some(reason) = github::page_cap_reached(self.max_pages, page) {
+                println!("Crediting commit authors from a partial history: {reason}");
+                break;
+            }
+        }
+        logins.reverse();
+        Ok(logins)
+    }
+
+    fn fetch_closing_issues(&self, _number: u64) -> Result<Vec<IssueReference>> {
+        // Gitea doesn't expose which issues a PR closes.
+        Ok(Vec::new())
+    }
+
+    fn fetch_reviewers(&self, number: u64, author: &str) -> Result<Vec<String>> {
+        let reviews: Vec<GiteaReview> = self.get_all_pages(
+            &self.repo_url(&format!("/pulls/{number}/reviews")),
+            PAGE_LIMIT,
+            "Failed to list pull request reviews.",
+            "Gitea pull request reviews request returned an error.",
+        )?;
+        Ok(github::reviewer_logins(
+            reviews
+                .iter()
+                .filter(|review| review.state != "REQUEST_REVIEW")
+                .map(|review| {
+                    (
+                        review.user.as_ref().map(|user| user.login.as_str()),
+                        review.state.as_str(),
+                    )
+                }),
+            author,
+        ))
+    }
+
+    fn fetch_user_name(&self, login: &str) -> Result<Option<String>> {
+        let response = self.send(
+            self.client.get(format!("{}/users/{login}", self.api_base)),
+            "Failed to look up user.",
+        )?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+        let user: GiteaUserProfile = response
+            .error_for_github_status()
+            .context("Gitea user request returned an error.")?
+            .json()?;
+        Ok(Some(user.full_name).filter(|name| !name.trim().is_empty()))
+    }
+
+    fn fetch_first_merged_pull_request(
+        &self,
+        _repository: Option<&str>,
+        _login: &str,
+    ) -> Result<Option<u64>> {
+        // Gitea can't search merged pull requests by author, so nobody is
+        // shown as a new contributor.
+        Ok(None)
+    }
+
+    fn create_annotated_tag(
+        &self,
+        tag: &str,
+        object: &str,
+        message: &str,
+        _tagger: &Tagger,
+        _timestamp: u64,
+    ) -> Result<()> {
+        if message.contains("-----BEGIN SSH SIGNATURE-----") {
+            bail!(
+                "tag-signing-key is not supported with provider gitea, which creates the tag object itself."
+            );
+        }
+        // Gitea tags as the token's user; the tagger and date can't be chosen.
+        self.send(
+            self.client
+                .post(self.repo_url("/tags"))
+                .json(&GiteaTagRequest {
+                    tag_name: tag,
+                    target: object,
+                    message,
+                }),
+            "Failed to create tag.",
+        )?
+        .error_for_github_status()
+        .context("Gitea tag create request returned an error.")?;
+        Ok(())
+    }
+
+    fn list_all_tags(&self, per_page: u32) -> Result<Vec<TagInfo>> {
+        self.get_all_pages(
+            &self.repo_url("/tags"),
+            per_page,
+            "Failed to list tags.",
+            "Gitea tags request returned an error.",
+        )
+    }
+
+    fn resolve_commit_sha(&self, reference: &str) -> Result<String> {
+        Ok(self.fetch_commit(reference)?.sha)
+    }
+
+    fn fetch_commit(&self, reference: &str) -> Result<CommitInfo> {
+        let commits: Vec<GiteaCommit> = self.get_json(
+            self.client.get(self.repo_url("/commits")).query(&[
+                ("sha", reference),
+                ("limit", "1"),
+                ("stat", "false"),
+                ("files", "false"),
+                ("verification", "false"),
+            ]),
+            "Failed to fetch commit reference.",
+            "Gitea commit request returned an error.",
+        )?;
+        let commit = commits
+            .into_iter()
+            .next()
+            .with_context(|| format!("No commit found for {reference}."))?;
+        Ok(CommitInfo {
+            sha: commit.sha,
+            committed_at: commit
+                .commit
+                .committer
+                .and_then(|committer| committer.date)
+                .map(utc_timestamp),
+        })
+    }
+
+    fn fetch_file_contents(&self, path: &str, reference: Option<&str>) -> Result<Option<String>> {
+        let mut request = self.client.get(self.repo_url(&format!("/raw/{path}")));
+        if let Some(reference) = reference {
+            request = request.query(&[("ref", reference)]);
+        }
+        let response = self.send(request, "Failed to fetch file contents.")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response
+            .error_for_github_status()
+            .context("Gitea raw file request returned an error.")?;
+        Ok(Some(response.text()?))
+    }
+
+    fn list_directory(&self, path: &str, reference: &str) -> Result<Vec<DirEntry>> {
+        let response = self.send(
+            self.client
+                .get(self.repo_url(&format!("/contents/{path}")))
+                .query(&[("ref", reference)]),
+            "Failed to list directory contents.",
+        )?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let value: serde_json::Value = response
+            .error_for_github_status()
+            .context("Gitea contents request returned an error.")?
+            .json()?;
+        // A file path returns an object rather than an array; it has no entries.
+        if !value.is_array() {
+            return Ok(Vec::new());
+        }
+        let entries: Vec<GiteaContentEntry> = serde_json::from_value(value)?;
+        let mut entries: Vec<DirEntry> = entries
+            .into_iter()
+            .map(|entry| DirEntry {
+                is_dir: entry.kind == "dir",
+                name: entry.name,
+            })
+            .collect();
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(entries)
+    }
+
+    fn fetch_merged_pull_requests(
+        &self,
+        branch: &str,
+        since: Option<&str>,
+        until: Option<&str>,
+        per_page: u32,
+        limits: FetchLimits,
+        api: PullRequestApi,
+    ) -> Result<MergedPullRequests> {
+        if api == PullRequestApi::GraphQl {
+            bail!("pull-request-api graphql is not available with provider gitea.");
+        }
+        self.list_merged_pull_requests(branch, since, until, None, per_page, limits)
+    }
+
+    fn fetch_milestone_pull_requests(
+        &self,
+        branch: &str,
+        milestone: &str,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        // Gitea looks a milestone up by title when it isn't an id.
+        let mut url = Url::parse(&self.repo_url("/milestones"))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Gitea API URL can't take a path."))?
+            .push(milestone);
+        let response = self.send(self.client.get(url), "Failed to look up milestone.")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            bail!("Milestone {milestone} was not found.");
+        }
+        let found: GiteaMilestone = response
+            .error_for_github_status()
+            .context("Gitea milestone request returned an error.")?
+            .json()?;
+        self.list_merged_pull_requests(branch, None, None, Some(found.id), per_page, limits)
+    }
+
+    /// Gitea can't map commits back to PRs on every version, so this lists
+    /// the PRs merged since the tag's commit instead.
+    fn fetch_compared_pull_requests(
+        &self,
+        base_tag: &str,
+        branch: &str,
+        per_page: u32,
+        limits: FetchLimits,
+    ) -> Result<MergedPullRequests> {
+        let since = self.fetch_commit(base_tag)?.committed_at;
+        self.fetch_merged_pull_requests(
+            branch,
+            since.as_deref(),
+            None,
+            per_page,
+            limits,
+            PullRequestApi::Rest,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_merged_pull_requests_into_branch() {
+        let pulls: Vec<GiteaPullRequest> = serde_json::from_str(
+            r#"[
+                {"number": 4, "title": "Fix", "body": null, "user": {"login": "octo"},
+                 "labels": [{"name": "bug"}], "merged": true,
+                 "merged_at": "2024-05-10T00:00:00Z", "updated_at": "2024-05-10T00:00:00Z",
+                 "html_url": "https://gitea.example.com/o/r/pulls/4", "base": {"ref": "main"}},
+                {"number": 5, "title": "Closed", "body": null, "user": null, "labels": [],
+                 "merged": false, "merged_at": null, "updated_at": "2024-05-09T00:00:00Z",
+                 "html_url": "https://gitea.example.com/o/r/pulls/5", "base": {"ref": "main"}},
+                {"number": 6, "title": "Backport", "body": null, "user": null, "labels": [],
+                 "merged": true, "merged_at": "2024-05-08T00:00:00Z",
+                 "updated_at": "2024-05-08T00:00:00Z",
+                 "html_url": "https://gitea.example.com/o/r/pulls/6", "base": {"ref": "release"}},
+                {"number": 3, "title": "Old", "body": null, "user": null, "labels": [],
+                 "merged": true, "merged_at": "2024-04-01T00:00:00Z",
+                 "updated_at": "2024-04-01T00:00:00Z",
+                 "html_url": "https://gitea.example.com/o/r/pulls/3", "base": {"ref": "main"}}
+            ]"#,
+        )
+        .unwrap();
+        let mut pull_requests = Vec::new();
+
+        let more = collect_gitea_pull_requests(
+            pulls,
+            "main",
+            Some("2024-05-01T00:00:00Z"),
+            None,
+            &mut pull_requests,
+        );
+
+        assert!(!more);
+        assert_eq!(pull_requests.len(), 1);
+        assert_eq!(pull_requests[0].number, 4);
+        assert_eq!(pull_requests[0].author, "octo");
+        assert_eq!(
+            pull_requests[0].url,
+            "https://gitea.example.com/o/r/pulls/4"
+        );
+    }
+
+    #[test]
+    fn compares_offset_timestamps_as_instants() {
+        let pull = |number: u64, merged_at: &str, updated_at: &str| -> GiteaPullRequest {
+            serde_json::from_value(serde_json::json!({
+                "number": number, "title": "PR", "body": null, "user": null, "labels": [],
+                "merged": true, "merged_at": merged_at, "updated_at": updated_at,
+                "html_url": "https://gitea.example.com/o/r/pulls/1", "base": {"ref": "main"}
+            }))
+            .unwrap()
+        };
+        let mut pull_requests = Vec::new();
+
+        // 23:30+02:00 sorts after a 23:00Z since as text but is earlier, so
+        // paging stops there.
+        let more = collect_gitea_pull_requests(
+            vec![
+                pull(4, "2024-05-02T03:00:00+02:00", "2024-05-02T03:00:00+02:00"),
+                pull(3, "2024-05-02T01:30:00+02:00", "2024-05-02T01:30:00+02:00"),
+                pull(2, "2024-05-01T22:00:00+00:00", "2024-05-02T01:30:00+02:00"),
+                pull(1, "2024-05-01T23:30:00+02:00", "2024-05-01T23:30:00+02:00"),
+            ],
+            "main",
+            Some("2024-05-01T23:00:00Z"),
+            Some("2024-05-02T00:30:00Z"),
+            &mut pull_requests,
+        );
+
+        assert!(!more);
+        let merged: Vec<&str> = pull_requests
+            .iter()
+            .map(|pull_request| pull_request.merged_at.as_deref().unwrap())
+            .collect();
+        assert_eq!(merged, vec!["2024-05-01T23:30:00Z"]);
+    }
+
+    #[test]
+    fn keeps_paging_while_offset_updates_are_inside_the_window() {
+        let pulls: Vec<GiteaPullRequest> = serde_json::from_str(
+            r#"[
+                {"number": 7, "title": "Late", "body": null, "user": null, "labels": [],
+                 "merged": true, "merged_at": "2024-05-01T08:00:00+09:00",
+                 "updated_at": "2024-05-01T08:00:00+09:00",
+                 "html_url": "https://gitea.example.com/o/r/pulls/7", "base": {"ref": "main"}}
+            ]"#,
+        )
+        .unwrap();
+        let mut pull_requests = Vec::new();
+
+        // 08:00+09:00 is 23:00Z the day before, after the window opened.
+        let more = collect_gitea_pull_requests(
+            pulls,
+            "main",
+            Some("2024-04-30T22:00:00Z"),
+            None,
+            &mut pull_requests,
+        );
+
+        assert!(more);
+        assert_eq!(pull_requests.len(), 1);
+    }
+
+    #[test]
+    fn rewrites_timestamps_in_utc() {
+        assert_eq!(
+            utc_timestamp("2024-05-01T08:00:00+09:00".to_string()),
+            "2024-04-30T23:00:00Z"
+        );
+        assert_eq!(
+            utc_timestamp("2024-05-01T08:00:00Z".to_string()),
+            "2024-05-01T08:00:00Z"
+        );
+        assert_eq!(utc_timestamp("soon".to_string()), "soon");
+    }
+
+    #[test]
+    fn wraps_assets_as_multipart_attachments() {
+        let body = multipart_attachment("app \"v1\".tar.gz", b"data");
+
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            format!(
+                "--{MULTIPART_BOUNDARY}\r\n\
+                 Content-Disposition: form-data; name=\"attachment\"; filename=\"app _v1_.tar.gz\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n\
+                 data\r\n--{MULTIPART_BOUNDARY}--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn lists_a_milestones_pull_requests_by_its_id() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_base = format!("http://{}/api/v1", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let responses = [
+                r#"{"id": 7, "title": "v2.0 beta"}"#,
+                r#"[{"number": 4, "title": "Fix", "body": null, "user": {"login": "octo"},
+                     "labels": [], "merged": true, "merged_at": "2024-05-10T00:00:00Z",
+                     "updated_at": "2024-05-10T00:00:00Z",
+                     "html_url": "https://gitea.example.com/o/r/pulls/4",
+                     "base": {"ref": "main"}, "milestone": {"title": "v2.0 beta"}}]"#,
+            ];
+            let mut requested = Vec::new();
+            for (body, stream) in responses.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 2048];
+                let read = std::io::Read::read(&mut stream, &mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).to_string();
+                requested.push(request.lines().next().unwrap().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                std::io::Write::write_all(&mut stream, response.as_bytes()).unwrap();
+            }
+            requested
+        });
+        let client =
+            GiteaClient::new(&api_base, "token", "o", "r", &ClientOptions::default()).unwrap();
+
+        let fetched = client
+            .fetch_milestone_pull_requests("main", "v2.0 beta", 50, FetchLimits::default())
+            .unwrap();
+
+        assert_eq!(fetched.pull_requests.len(), 1);
+        assert_eq!(fetched.pull_requests[0].number, 4);
+        let requested = server.join().unwrap();
+        assert_eq!(
+            requested[0],
+            "GET /api/v1/repos/o/r/milestones/v2.0%20beta HTTP/1.1"
+        );
+        assert!(requested[1].contains("milestone=7"));
+    }
+}
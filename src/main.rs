@@ -1,145 +1,1628 @@
+mod app_auth;
+mod archive;
+mod assets;
+mod audit;
+mod cache;
+mod calver;
 mod config;
+mod gitea;
 mod github;
+mod pattern;
+mod pr_cache;
 mod release_notes;
+mod resolver;
+mod signing;
 mod version;
+mod xml;
+mod zon;
 
 use anyhow::{Context, Result, anyhow, bail};
+use audit::{AuditLog, AuditedApi};
+use cache::ResponseCache;
 use config::ReleaseConfig;
-use github::ReleaseInfo;
-use release_notes::{build_release_notes, release_marker};
+use github::{
+    FetchLimits, MakeLatest, MergedPullRequests, PullRequestApi, ReleaseAccess, ReleaseApi,
+    ReleaseFields, ReleaseInfo,
+};
+use release_notes::{
+    CommitHistory, DirectCommit, PullRequestInfo, build_release_notes, fill_variables,
+    has_release_signature, release_header, release_marker,
+};
+use reqwest::{Certificate, StatusCode};
+use std::collections::HashMap;
 use std::env;
-use std::path::Path;
-use version::{is_prerelease_version, parse_languages, resolve_version};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+use version::{
+    LocalFiles, SemVer, VersionOptions, directory_depth, is_prerelease_version, parse_languages,
+    resolve_version, select_previous_tag,
+};
 
 const MAX_PER_PAGE: u32 = 100;
+const DEFAULT_CACHE_DIR_NAME: &str = "breezy-cache";
+const DEFAULT_TAGGER_NAME: &str = "github-actions[bot]";
+const DEFAULT_TAGGER_EMAIL: &str = "41898282+github-actions[bot]@users.noreply.github.com";
 
-struct DraftSelection {
-    primary: Option<u64>,
-    extras: Vec<u64>,
+enum ContentSource {
+    Local,
+    Api,
+}
+
+enum PreviousSource {
+    Releases,
+    Tags,
+}
+
+enum Provider {
+    GitHub,
+    Gitea,
+}
+
+/// The merge-date range to collect PRs from, and the tag to compare against
+/// when the range starts at one and runs to the branch head.
+#[derive(Clone, Copy, Default)]
+struct PullRequestWindow<'a> {
+    since: Option<&'a str>,
+    until: Option<&'a str>,
+    compare_base: Option<&'a str>,
+}
+
+struct DraftSelection<'a> {
+    primary: Option<&'a ReleaseInfo>,
+    extras: Vec<&'a ReleaseInfo>,
+}
+
+struct Clients {
+    source: Box<dyn ReleaseApi + Sync>,
+    target: Option<Box<dyn ReleaseApi + Sync>>,
+    /// Clients for the config's extra `repositories`, by `owner/repo`.
+    extra_sources: Vec<(String, Box<dyn ReleaseApi + Sync>)>,
+    source_repository: String,
+    /// `owner/repo` that releases are kept in.
+    release_repository: String,
+    /// Items requested per page of every paginated listing.
+    per_page: u32,
+}
+
+impl Clients {
+    /// Releases live in the target repository when one is set; PRs always come from the source.
+    fn releases(&self) -> &(dyn ReleaseApi + Sync) {
+        self.target.as_deref().unwrap_or(self.source.as_ref())
+    }
+
+    /// The source repository is only recorded in markers when drafts live elsewhere.
+    fn marker_repository(&self) -> Option<&str> {
+        self.target
+            .as_ref()
+            .map(|_| self.source_repository.as_str())
+    }
 }
 
 fn main() {
-    if let Err(error) = run() {
+    let mut args = env::args().skip(1);
+    let command = args
+        .next()
+        .or_else(|| read_input("command"))
+        .filter(|value| !value.trim().is_empty());
+    let audit = Arc::new(AuditLog::default());
+    let result = match command.as_deref().map(str::trim) {
+        None | Some("draft") => run(&audit),
+        Some("archive") => run_archive(args.next(), &audit),
+        Some("backfill") => run_backfill(&audit),
+        Some(other) => Err(anyhow!(
+            "Unknown command: {other}. Expected draft, archive, or backfill."
+        )),
+    };
+    // Whatever was changed before a failure is still reported.
+    if let Err(error) = write_audit(&audit) {
+        eprintln!("Failed to write the audit log: {error:#}");
+    }
+    if let Err(error) = result {
         eprintln!("{error:#}");
         std::process::exit(1);
     }
 }
 
-fn run() -> Result<()> {
+/// Builds the API clients and loads the config. Local config is read first so
+/// it can supply client settings such as `ca-bundle`; config fetched from the
+/// API can't, since the client is needed to fetch it.
+fn connect(cwd: &Path, audit: &Arc<AuditLog>) -> Result<(Clients, Option<ReleaseConfig>)> {
+    match resolve_content_source("config-from", read_input("config-from"))? {
+        ContentSource::Local => {
+            let config = config::load_config(read_input("config-file"), cwd)?;
+            let clients = build_clients(cwd, config.as_ref(), audit)?;
+            Ok((clients, config))
+        }
+        ContentSource::Api => {
+            let clients = build_clients(cwd, None, audit)?;
+            let config = load_config_from_api(clients.source.as_ref(), read_input("config-file"))?;
+            // Clients for the config's `repositories` can only be made once
+            // it has been read.
+            let clients = match &config {
+                Some(config) if !config.repositories.is_empty() => {
+                    build_clients(cwd, Some(config), audit)?
+                }
+                _ => clients,
+            };
+            Ok((clients, config))
+        }
+    }
+}
+
+fn build_clients(
+    cwd: &Path,
+    config: Option<&ReleaseConfig>,
+    audit: &Arc<AuditLog>,
+) -> Result<Clients> {
+    let provider = resolve_provider(read_input("provider"))?;
+    let credentials = resolve_credentials()?;
+
+    let (owner, repo) = parse_repository()?;
+    let target_repository = read_input("target-repo")
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| parse_repository_slug(value.trim(), "target-repo"))
+        .transpose()?;
+    let cache = resolve_cache_dir(read_input("cache-dir"), env::var("RUNNER_TEMP").ok(), cwd)
+        .map(|dir| ResponseCache::new(&dir))
+        .transpose()?;
+    let rate_limit_threshold =
+        parse_u64_input("rate-limit-threshold", github::DEFAULT_RATE_LIMIT_THRESHOLD)?;
+    let max_attempts = parse_u64_input("max-attempts", u64::from(github::DEFAULT_MAX_ATTEMPTS))?;
+    let rate_limit_wait =
+        parse_u64_input("rate-limit-wait", github::DEFAULT_RATE_LIMIT_WAIT.as_secs())?;
+    let api_url = env::var("GITHUB_API_URL")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| github::DEFAULT_API_URL.to_string());
+    let options = github::ClientOptions {
+        cache,
+        rate_limit_threshold,
+        max_attempts: u32::try_from(max_attempts).unwrap_or(u32::MAX),
+        rate_limit_wait: Duration::from_secs(rate_limit_wait),
+        proxy: read_input("proxy")
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        root_certificates: read_input("ca-bundle")
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .or_else(|| config.and_then(|config| config.ca_bundle.clone()))
+            .map(|path| load_ca_bundle(&cwd.join(path)))
+            .transpose()?
+            .unwrap_or_default(),
+        http_timeout: parse_timeout_input("http-timeout", github::DEFAULT_HTTP_TIMEOUT)?,
+        connect_timeout: parse_timeout_input("connect-timeout", github::DEFAULT_CONNECT_TIMEOUT)?,
+        max_pages: u32::try_from(parse_u64_input("max-pages", 0)?)
+            .ok()
+            .filter(|&max_pages| max_pages > 0),
+        server_url: env::var("GITHUB_SERVER_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| github::DEFAULT_SERVER_URL.to_string()),
+        graphql_url: env::var("GITHUB_GRAPHQL_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| github::graphql_url(&api_url)),
+        api_url,
+        // Re-running a job with debug logging sets RUNNER_DEBUG.
+        debug: resolve_flag("debug", read_input("debug"))?
+            || env::var("RUNNER_DEBUG").is_ok_and(|value| value == "1"),
+    };
+    let per_page = resolve_per_page(parse_u64_input("per-page", u64::from(MAX_PER_PAGE))?)?;
+
+    let connect = |owner: &str, repo: &str| -> Result<Box<dyn ReleaseApi + Sync>> {
+        let client: Box<dyn ReleaseApi + Sync> = match &provider {
+            Provider::GitHub => Box::new(github::GitHubClient::new(
+                &credentials,
+                owner,
+                repo,
+                options.clone(),
+            )?),
+            Provider::Gitea => {
+                let github::Credentials::Token(token) = &credentials else {
+                    bail!("app-id and private-key are not supported with provider gitea.");
+                };
+                let api_url = read_input("api-url")
+                    .filter(|value| !value.trim().is_empty())
+                    .context("provider gitea requires the api-url input, e.g. https://codeberg.org/api/v1.")?;
+                Box::new(gitea::GiteaClient::new(
+                    &api_url, token, owner, repo, &options,
+                )?)
+            }
+        };
+        Ok(Box::new(AuditedApi::new(
+            client,
+            &format!("{owner}/{repo}"),
+            Arc::clone(audit),
+        )))
+    };
+    let source = connect(&owner, &repo)?;
+    let extra_sources = config
+        .map(|config| config.repositories.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|repository| **repository != format!("{owner}/{repo}"))
+        .map(|repository| {
+            let (extra_owner, extra_repo) = parse_repository_slug(repository, "repositories")?;
+            Ok((repository.clone(), connect(&extra_owner, &extra_repo)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let target = target_repository
+        .as_ref()
+        .map(|(target_owner, target_repo)| connect(target_owner, target_repo))
+        .transpose()?;
+
+    Ok(Clients {
+        source,
+        target,
+        extra_sources,
+        source_repository: format!("{owner}/{repo}"),
+        release_repository: target_repository
+            .as_ref()
+            .map_or(format!("{owner}/{repo}"), |(target_owner, target_repo)| {
+                format!("{target_owner}/{target_repo}")
+            }),
+        per_page,
+    })
+}
+
+/// GitHub App credentials take precedence over `github-token`, which defaults to
+/// the workflow token.
+fn resolve_credentials() -> Result<github::Credentials> {
+    let app_id = read_input("app-id").filter(|value| !value.trim().is_empty());
+    let private_key = read_input("private-key").filter(|value| !value.trim().is_empty());
+    match (app_id, private_key) {
+        (Some(app_id), Some(private_key)) => Ok(github::Credentials::App(Arc::new(
+            app_auth::AppKey::new(&app_id, &private_key)?,
+        ))),
+        (Some(_), None) => bail!("app-id is set but private-key is missing."),
+        (None, Some(_)) => bail!("private-key is set but app-id is missing."),
+        (None, None) => {
+            let token = read_input("github-token")
+                .or_else(|| env::var("GITHUB_TOKEN").ok())
+                .unwrap_or_default();
+            if token.trim().is_empty() {
+                bail!(
+                    "Missing GitHub token. Set the github-token input, GITHUB_TOKEN env, or app-id and private-key."
+                );
+            }
+            Ok(github::Credentials::Token(token))
+        }
+    }
+}
+
+fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+    let pem =
+        fs::read(path).with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("Failed to parse CA bundle {}", path.display()))?;
+    if certificates.is_empty() {
+        bail!("CA bundle {} contains no certificates.", path.display());
+    }
+    Ok(certificates)
+}
+
+fn run(audit: &Arc<AuditLog>) -> Result<()> {
+    let cwd = env::current_dir().context("Unable to resolve current working directory.")?;
+    let (clients, config) = connect(&cwd, audit)?;
+    write_draft(&clients, config, &cwd)
+}
+
+/// Creates or updates the branch's draft release through `clients`, with
+/// everything else read from the action's inputs and environment.
+fn write_draft(clients: &Clients, config: Option<ReleaseConfig>, cwd: &Path) -> Result<()> {
     let branch = resolve_branch()?;
     let directory = resolve_directory(read_input("directory"))?;
     let tag_prefix = read_input("tag-prefix").unwrap_or_else(|| "v".to_string());
-    let token = read_input("github-token")
-        .or_else(|| env::var("GITHUB_TOKEN").ok())
-        .unwrap_or_default();
 
-    if token.trim().is_empty() {
-        bail!("Missing GitHub token. Set the github-token input or GITHUB_TOKEN env.");
+    let access = clients.releases().check_release_access()?;
+    if let Some(message) = missing_permissions_message(&clients.release_repository, access) {
+        bail!(message);
     }
+    let client = clients.source.as_ref();
+    let target_client = clients.target.as_deref();
+    let release_client = clients.releases();
+    let marker_repository = clients.marker_repository();
 
-    let cwd = env::current_dir().context("Unable to resolve current working directory.")?;
-    let config = config::load_config(read_input("config-file"), &cwd)?;
+    let version_file = config
+        .as_ref()
+        .and_then(|config| config.version_file.clone());
+    let version_source = config
+        .as_ref()
+        .and_then(|config| config.version_source.clone());
     let language_input = read_input("language").unwrap_or_default();
-    let language_source = resolve_language(&language_input, config.as_ref())?;
+    let version_override = parse_version_override(read_input("version"));
+    let member_input = |name: &str, configured: Option<&String>| {
+        read_input(name)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .or_else(|| configured.cloned())
+    };
+    let crate_name = member_input(
+        "crate",
+        config
+            .as_ref()
+            .and_then(|config| config.crate_name.as_ref()),
+    );
+    let package_name = member_input(
+        "package",
+        config
+            .as_ref()
+            .and_then(|config| config.package_name.as_ref()),
+    );
+    // Drafts for different workspace members on one branch are kept apart.
+    let member = crate_name.clone().or_else(|| package_name.clone());
+    let version_resolver = config
+        .as_ref()
+        .and_then(|config| config.version_resolver.as_ref())
+        .filter(|_| version_override.is_none());
+    let calver = config.as_ref().and_then(|config| config.calver.as_ref());
+    let resolve_calver = calver.is_some() && version_override.is_none();
+    // An explicit version, a version-resolver, or calver can stand in for manifests,
+    // so the language is optional with any of them.
+    let manifest_optional = version_file.is_some()
+        || version_source.is_some()
+        || version_resolver.is_some()
+        || calver.is_some()
+        || version_override.is_some();
+    let language_source = match configured_language(&language_input, config.as_ref()) {
+        Some(language) => language,
+        None if manifest_optional => String::new(),
+        None => bail!("Missing required input: language"),
+    };
     let languages = parse_languages(&language_source);
-    if languages.is_empty() {
+    if languages.is_empty() && !manifest_optional {
         bail!("No language archetypes provided.");
     }
+    let tag_match_prefix =
+        resolve_tag_match_prefix(&tag_prefix, directory.as_deref(), config.as_ref());
+
+    let version_options = VersionOptions {
+        version_file,
+        version_source,
+        crate_name,
+        package_name,
+        helm_app_version: resolve_helm_version_field(read_input("helm-version-field"))?,
+        version_filename: read_input("version-filename")
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+        tags: Some(client),
+        tag_prefix: tag_match_prefix.clone(),
+        directory_depth: directory.as_deref().map_or(0, directory_depth),
+        strict: resolve_version_check(read_input("version-check"))?,
+    };
+
+    let current_sha = resolve_current_sha();
+    let manifest_version =
+        match resolve_content_source("version-from", read_input("version-from"))? {
+            _ if version_override.is_some() => None,
+            _ if languages.is_empty()
+                && version_options.version_file.is_none()
+                && version_options.version_source.is_none() =>
+            {
+                None
+            }
+            ContentSource::Local => {
+                let version_root = match &directory {
+                    Some(directory) => cwd.join(directory),
+                    None => cwd.to_path_buf(),
+                };
+                Some(resolve_version(
+                    &LocalFiles::new(&version_root),
+                    &languages,
+                    &version_options,
+                )?)
+            }
+            ContentSource::Api => {
+                let sha = current_sha
+                    .as_deref()
+                    .context("version-from: api requires the GITHUB_SHA environment variable.")?;
+                let files = github::RepositoryFiles::new(client, directory.as_deref(), sha);
+                Some(resolve_version(&files, &languages, &version_options)?)
+            }
+        }
+        .map(|info| info.version)
+        .or(version_override);
+    let manifest_tag = manifest_version.as_deref().map(|version| {
+        resolve_tag_name(
+            version,
+            None,
+            &tag_prefix,
+            directory.as_deref(),
+            config.as_ref(),
+        )
+    });
+
+    let marker = release_marker(
+        &branch,
+        directory.as_deref(),
+        member.as_deref(),
+        marker_repository,
+    );
+    let scope_label = format_scope_label(&branch, directory.as_deref().or(member.as_deref()));
 
-    let version_root = match &directory {
-        Some(directory) => cwd.join(directory),
-        None => cwd.clone(),
+    let previous_source = resolve_previous_source(read_input("previous-from"))?;
+    let previous_from_tags = match previous_source {
+        PreviousSource::Releases => None,
+        PreviousSource::Tags => Some(previous_tag_boundary(
+            client,
+            clients.per_page,
+            &tag_match_prefix,
+            manifest_tag.as_deref(),
+        )?),
+    };
+    let since_input = read_input("since")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let until = read_input("until")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(|value| resolve_window_bound(client, "until", &value, true))
+        .transpose()?;
+    let explicit_window = since_input.is_some() || until.is_some();
+    let since_override = since_input
+        .map(|value| -> Result<(String, Option<String>)> {
+            println!("Collecting pull requests since {value} instead of the previous release");
+            let timestamp = resolve_window_bound(client, "since", &value, false)?;
+            let tag = (!is_timestamp(&value)).then_some(value);
+            Ok((timestamp, tag))
+        })
+        .transpose()?;
+    // Returns the window's start and the tag to compare from, if any.
+    let window_start = |since: Option<String>, previous_tag: Option<&String>| match &since_override
+    {
+        Some((timestamp, tag)) => (Some(timestamp.clone()), tag.clone()),
+        // Release tags in a target repository aren't refs in the source, so
+        // that boundary can't be compared and falls back to search.
+        None => (
+            since,
+            previous_tag.cloned().filter(|_| {
+                target_client.is_none() || matches!(previous_source, PreviousSource::Tags)
+            }),
+        ),
+    };
+    let milestone = read_input("milestone")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| config.as_ref().and_then(|config| config.milestone.clone()));
+    let collector = PullRequestCollector {
+        clients,
+        config: config.as_ref(),
+        branch: &branch,
+        marker: &marker,
+        until: until.as_deref(),
+        milestone: milestone.as_deref(),
+        // An explicit window is a one-off regeneration, not the running draft.
+        cache_path: read_input("pr-cache-file")
+            .filter(|value| !value.trim().is_empty() && !explicit_window)
+            .map(|path| cwd.join(path.trim())),
     };
-    let version_info = resolve_version(&version_root, &languages)?;
 
+    // Target repositories don't share the source's branches or commits, so
+    // published releases there can only be matched by marker.
+    let branch_filter = target_client.map_or(Some(branch.as_str()), |_| None);
+    let marker_filter = if directory.is_some() || target_client.is_some() {
+        Some(marker.as_str())
+    } else {
+        None
+    };
+    let per_page = clients.per_page;
+    let marker_text = marker.as_str();
+    let keep_duplicates =
+        resolve_flag("keep-duplicate-drafts", read_input("keep-duplicate-drafts"))?;
+    // The listing reports the latest published release as soon as it reaches
+    // it, usually on the first page, then keeps looking for the scope's draft
+    // while PRs are collected. Collection waits for that report, since the
+    // window may start there and a run for an already released commit may
+    // stop without any PRs.
+    let (releases, latest_published, released, prefetched) = thread::scope(|scope| {
+        let (latest_sender, latest_receiver) = mpsc::channel();
+        let listing = scope.spawn(move || {
+            let mut reported = false;
+            let releases = list_scope_releases(
+                release_client,
+                per_page,
+                marker_text,
+                branch_filter,
+                marker_filter,
+                keep_duplicates,
+                &mut |latest| {
+                    let _ = latest_sender.send(Some(latest.clone()));
+                    reported = true;
+                },
+            );
+            if releases.is_ok() && !reported {
+                let _ = latest_sender.send(None);
+            }
+            releases
+        });
+        let collect = || -> Result<_> {
+            // A listing that fails before reporting anything returns its
+            // error below, so nothing is collected for it.
+            let Ok(latest) = latest_receiver.recv() else {
+                return Ok((None, false, None));
+            };
+            let released = match (&latest, current_sha.as_deref()) {
+                (Some(latest), Some(current_sha)) if target_client.is_none() => {
+                    published_release_matches_commit(client, latest, current_sha)?
+                }
+                _ => false,
+            };
+            // A released commit only needs PRs when the scope has a draft to
+            // update, which the rest of the listing decides.
+            let collected = if released {
+                None
+            } else {
+                let (since, previous_tag) = previous_from_tags
+                    .clone()
+                    .unwrap_or_else(|| release_boundary(latest.as_ref()));
+                let (since, since_tag) = window_start(since, previous_tag.as_ref());
+                Some(collector.collect(since.as_deref(), since_tag.as_deref())?)
+            };
+            Ok((latest, released, collected))
+        };
+        let collected = collect();
+        let releases = listing
+            .join()
+            .map_err(|_| anyhow!("Listing releases panicked."))?;
+        let (latest, released, collected) = collected?;
+        Ok::<_, anyhow::Error>((releases?, latest, released, collected))
+    })?;
+    let selection = select_draft_releases(&releases, &marker);
+
+    if !keep_duplicates {
+        let delete_unsigned = resolve_flag(
+            "delete-unsigned-drafts",
+            read_input("delete-unsigned-drafts"),
+        )?;
+        prune_extra_drafts(
+            release_client,
+            &selection,
+            &marker,
+            delete_unsigned,
+            &scope_label,
+        )?;
+    }
+
+    let skip_create = released && selection.primary.is_none();
+    if skip_create {
+        let current_sha = current_sha.as_deref().unwrap_or("unknown");
+        println!(
+            "Skipping draft release for {scope_label} because a published release already exists for commit {current_sha}"
+        );
+        return Ok(());
+    }
+
+    let (since, previous_tag) =
+        previous_from_tags.unwrap_or_else(|| release_boundary(latest_published.as_ref()));
+    let collected = match prefetched {
+        Some(collected) => collected,
+        None => {
+            let (since, since_tag) = window_start(since, previous_tag.as_ref());
+            collector.collect(since.as_deref(), since_tag.as_deref())?
+        }
+    };
+    let compare_base = collected.compare_base.as_deref();
+    let direct_commits = collected.direct_commits;
+    let compared_authors = collected.commit_authors;
+    let mut pull_requests = collected.pull_requests;
+    // Search matches milestone titles loosely, so the exact title is checked.
+    if let Some(milestone) = &milestone {
+        let found = pull_requests.len();
+        retain_milestone(&mut pull_requests, milestone);
+        println!(
+            "Keeping {} of {found} pull requests in milestone {milestone}",
+            pull_requests.len()
+        );
+    }
+
+    let previous_version = previous_tag
+        .as_deref()
+        .and_then(|tag| tag.strip_prefix(tag_match_prefix.as_str()));
+    let mut resolved_version = if let Some(resolver) = version_resolver {
+        let previous = previous_version.and_then(SemVer::parse);
+        let resolved = resolver.resolve(previous.as_ref(), &pull_requests);
+        println!("Resolved next version {resolved} from merged pull requests");
+        Some(resolved)
+    } else if let Some(calver) = calver.filter(|_| resolve_calver) {
+        let resolved = calver.next(previous_version, calver::Date::today());
+        println!("Resolved next calendar version {resolved}");
+        Some(resolved)
+    } else {
+        None
+    };
+    let mut version = manifest_version
+        .or_else(|| resolved_version.clone())
+        .context("Unable to determine a version.")?;
+    if let Some(template) = config
+        .as_ref()
+        .and_then(|config| config.build_metadata.as_deref())
+    {
+        let today = calver::Date::today();
+        let metadata = render_build_metadata(
+            template,
+            current_sha.as_deref(),
+            env::var("GITHUB_RUN_NUMBER").ok().as_deref(),
+            &format!("{:04}{:02}{:02}", today.year, today.month, today.day),
+        );
+        if let Some(metadata) = metadata {
+            version = append_build_metadata(&version, &metadata);
+            resolved_version =
+                resolved_version.map(|resolved| append_build_metadata(&resolved, &metadata));
+        }
+    }
     let tag_name = resolve_tag_name(
-        &version_info.version,
+        &version,
+        resolved_version.as_deref(),
         &tag_prefix,
         directory.as_deref(),
         config.as_ref(),
     );
     let release_name = resolve_release_name(
-        &version_info.version,
+        &version,
+        resolved_version.as_deref(),
         &tag_name,
-        &branch,
+        &scope_label,
         directory.as_deref(),
         config.as_ref(),
     );
-    let marker = release_marker(&branch, directory.as_deref());
-    let prerelease = is_prerelease_version(&version_info.version);
-    let scope_label = format_scope_label(&branch, directory.as_deref());
+    let prerelease = calver
+        .and_then(|calver| calver.is_prerelease(&version))
+        .unwrap_or_else(|| is_prerelease_version(&version));
+    let commit_authors = match (config.as_ref(), compare_base) {
+        (Some(config), Some(base_tag))
+            if config.contributors_from == config::ContributorsFrom::Commits =>
+        {
+            match compared_authors {
+                Some(authors) => authors,
+                None => client.fetch_commit_authors(base_tag, &branch, clients.per_page)?,
+            }
+        }
+        (Some(config), None) if config.contributors_from == config::ContributorsFrom::Commits => {
+            println!(
+                "No previous tag to compare against; crediting pull request authors only in $CONTRIBUTORS"
+            );
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    let (shown_previous_tag, repository_url) = previous_tag_for_notes(
+        since_override.as_ref(),
+        previous_tag.as_ref(),
+        &previous_source,
+        target_client.is_some(),
+        release_client.repository_url(),
+    );
+    let history = CommitHistory {
+        authors: commit_authors,
+        direct_commits,
+        tag: tag_name.clone(),
+        resolved_version: resolved_version.clone().unwrap_or_else(|| version.clone()),
+        previous_tag: shown_previous_tag,
+        repository_url,
+    };
+    let release_notes = build_release_notes(&marker, &pull_requests, &history, config.as_ref());
 
-    let (owner, repo) = parse_repository()?;
-    let client = github::GitHubClient::new(&token, &owner, &repo)?;
+    let asset_paths = read_input("assets")
+        .map(|input| assets::resolve_asset_paths(&input, cwd))
+        .transpose()?
+        .unwrap_or_default();
 
-    let releases = client.list_all_releases(MAX_PER_PAGE)?;
-    let selection = select_draft_releases(&releases, &marker);
+    let make_latest = resolve_make_latest(read_input("make-latest"))?;
+    let discussion_category = read_input("discussion-category")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(|| {
+            config
+                .as_ref()
+                .and_then(|config| config.discussion_category.clone())
+        });
+    let fields = ReleaseFields {
+        tag_name: &tag_name,
+        name: &release_name,
+        body: &release_notes,
+        prerelease,
+        target_commitish: branch_filter,
+        make_latest,
+        discussion_category_name: discussion_category.as_deref(),
+    };
+    if tag_already_released(release_client, &tag_name, &scope_label)? {
+        return Ok(());
+    }
+    // Assets carried over from pruned drafts aren't in the listed draft, so
+    // it is only reused as is when nothing was pruned.
+    let release = match selection.primary {
+        Some(primary) if selection.extras.is_empty() && draft_is_current(primary, &fields) => {
+            println!(
+                "Draft release {} for {scope_label} already up to date",
+                primary.id
+            );
+            primary.clone()
+        }
+        primary => save_draft(
+            release_client,
+            primary.map(|release| release.id),
+            &fields,
+            &scope_label,
+        )?,
+    };
 
-    for release_id in selection.extras {
-        client.delete_release(release_id)?;
-        println!("Deleted extra draft release {release_id} for {scope_label}");
+    if !asset_paths.is_empty() {
+        let name_template = read_input("asset-name-template").unwrap_or_default();
+        let target = read_input("asset-target").unwrap_or_default();
+        let context = assets::AssetNameContext {
+            version: &version,
+            tag: &tag_name,
+            target: target.trim(),
+        };
+        let checksums = resolve_flag("checksums", read_input("checksums"))?;
+        let names: Vec<String> = asset_paths
+            .iter()
+            .map(|path| assets::render_asset_name(&name_template, path, &context))
+            .collect();
+        assets::check_asset_names(&names, checksums)?;
+        let mut uploaded = Vec::new();
+        for (path, name) in asset_paths.iter().zip(names) {
+            let content = std::fs::read(path)
+                .with_context(|| format!("Failed to read asset {}", path.display()))?;
+            if checksums {
+                let hex = assets::sha256_hex(&content);
+                let sidecar = assets::checksum_asset_name(&name);
+                replace_release_asset(
+                    release_client,
+                    &release,
+                    &sidecar,
+                    format!("{hex}  {name}\n").into_bytes(),
+                )?;
+                uploaded.push((name.clone(), hex));
+            }
+            replace_release_asset(release_client, &release, &name, content)?;
+            println!("Uploaded asset {name} to draft release {}", release.id);
+        }
+        if checksums {
+            let entries = collect_checksums(release_client, &release, uploaded)?;
+            replace_release_asset(
+                release_client,
+                &release,
+                assets::CHECKSUMS_FILE_NAME,
+                assets::render_checksums(&entries).into_bytes(),
+            )?;
+            println!(
+                "Uploaded {} covering {} assets to draft release {}",
+                assets::CHECKSUMS_FILE_NAME,
+                entries.len(),
+                release.id
+            );
+        }
     }
 
-    let marker_filter = directory.as_deref().map(|_| marker.as_str());
-    let latest_published = select_latest_published_release(&releases, &branch, marker_filter);
-    let current_sha = resolve_current_sha();
-    let skip_create = if selection.primary.is_none() {
-        if let (Some(current_sha), Some(latest_published)) =
-            (current_sha.as_deref(), latest_published)
-        {
-            published_release_matches_commit(&client, latest_published, current_sha)?
-        } else {
-            false
+    if resolve_flag("publish", read_input("publish"))? {
+        // The current commit only exists in the source repository; a target
+        // repository tags its default branch instead.
+        let sha = match target_client {
+            Some(_) => None,
+            None => Some(
+                current_sha
+                    .as_deref()
+                    .context("publish needs GITHUB_SHA to tag the current commit.")?,
+            ),
+        };
+        if resolve_flag("annotated-tag", read_input("annotated-tag"))? {
+            if release_client.tag_exists(&tag_name)? {
+                println!("Tag {tag_name} already exists; publishing against it");
+            } else {
+                let object = match sha {
+                    Some(sha) => sha.to_string(),
+                    None => release_client.resolve_commit_sha("HEAD")?,
+                };
+                create_annotated_tag(
+                    release_client,
+                    &tag_name,
+                    &object,
+                    &release_name,
+                    release_notes
+                        .strip_prefix(release_header(&marker).as_str())
+                        .unwrap_or(&release_notes),
+                )?;
+            }
         }
-    } else {
-        false
+        let published = release_client.publish_release(release.id, sha, &fields)?;
+        println!("Published release {} for {scope_label}", published.tag_name);
+    }
+
+    let degraded = client.degraded_features();
+    if !degraded.is_empty() {
+        println!(
+            "Completed with degraded output due to API rate limit: skipped {}",
+            degraded.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Explains which `permissions:` the workflow is missing, or `None` when the
+/// token can do everything a draft run needs.
+fn missing_permissions_message(repository: &str, access: ReleaseAccess) -> Option<String> {
+    let missing = match access {
+        ReleaseAccess { write: true, .. } => return None,
+        ReleaseAccess { read: false, .. } => "read or write",
+        ReleaseAccess { read: true, .. } => "write",
     };
+    Some(format!(
+        "The token cannot {missing} releases in {repository}, which breezy needs to list releases, \
+         create and update the draft, and delete duplicate drafts. Grant the workflow or job:\n\n\
+         permissions:\n  contents: write\n\n\
+         A GitHub App or fine-grained token needs the Contents repository permission set to Read and write."
+    ))
+}
 
-    if skip_create {
-        let current_sha = current_sha.as_deref().unwrap_or("unknown");
+/// Moves assets from duplicate drafts for the scope onto the surviving draft,
+/// then deletes the duplicates. Drafts without breezy's signature only match
+/// because their body quotes the marker, so they are kept unless
+/// `delete_unsigned` is set.
+fn prune_extra_drafts(
+    release_client: &dyn ReleaseApi,
+    selection: &DraftSelection,
+    marker: &str,
+    delete_unsigned: bool,
+    scope_label: &str,
+) -> Result<()> {
+    let (extras, unsigned): (Vec<&ReleaseInfo>, Vec<&ReleaseInfo>) =
+        selection.extras.iter().partition(|release| {
+            delete_unsigned || has_release_signature(release.body.as_deref().unwrap_or(""), marker)
+        });
+    if !unsigned.is_empty() {
+        let ids: Vec<String> = unsigned
+            .iter()
+            .map(|release| release.id.to_string())
+            .collect();
         println!(
-            "Skipping draft release for {scope_label} because a published release already exists for commit {current_sha}"
+            "::warning::Draft releases {} for {scope_label} contain breezy's marker but not its signature, so they were kept; set delete-unsigned-drafts: true to delete them.",
+            ids.join(", ")
         );
-        return Ok(());
     }
 
-    let since = latest_published
-        .map(|release| {
-            release
-                .published_at
-                .as_deref()
-                .unwrap_or(&release.created_at)
+    if let Some(primary) = selection.primary {
+        for asset in assets::assets_to_carry(&primary.assets, &extras) {
+            let content = release_client.download_release_asset(asset.id)?;
+            release_client.upload_release_asset(primary, &asset.name, content)?;
+            println!(
+                "Carried asset {} over to draft release {}",
+                asset.name, primary.id
+            );
+        }
+    }
+
+    let mut kept = Vec::new();
+    for release in &extras {
+        let release_id = release.id;
+        match release_client.delete_release(release_id) {
+            Ok(()) => println!("Deleted extra draft release {release_id} for {scope_label}"),
+            // A token that may edit releases but not delete them shouldn't
+            // stop the draft from being updated.
+            Err(error) if github::error_status(&error) == Some(StatusCode::FORBIDDEN) => {
+                kept.push(release_id.to_string());
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    if !kept.is_empty() {
+        println!(
+            "::warning::The token may not delete releases, so extra draft releases {} for {scope_label} were kept; delete them by hand.",
+            kept.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// True for values shaped like an ISO 8601 date or timestamp, e.g. `2024-05-01`
+/// or `2024-05-01T12:00:00Z`, rather than a tag name.
+fn is_timestamp(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            })
+        && (bytes.len() == 10 || bytes[10] == b'T')
+}
+
+/// Rewrites an ISO 8601 date or timestamp as UTC `YYYY-MM-DDTHH:MM:SSZ`, the
+/// form search accepts and the other bounds use. A bare date is the start of
+/// that day, or its last second with `end_of_day`.
+fn normalize_timestamp(value: &str, end_of_day: bool) -> Option<String> {
+    let seconds = if value.len() == 10 {
+        app_auth::parse_timestamp(&format!("{value}T00:00:00Z"))?
+            + if end_of_day { 86_399 } else { 0 }
+    } else {
+        app_auth::parse_timestamp(value)?
+    };
+    Some(github::format_timestamp(seconds))
+}
+
+/// Resolves a `since` or `until` input to a UTC timestamp: dates are
+/// normalized, with a bare `until` date covering its whole day, and anything
+/// else names a tag, read as its commit's date.
+fn resolve_window_bound(
+    client: &dyn ReleaseApi,
+    name: &str,
+    value: &str,
+    end_of_day: bool,
+) -> Result<String> {
+    if is_timestamp(value) {
+        return normalize_timestamp(value, end_of_day)
+            .with_context(|| format!("Input {name} is not a valid timestamp: {value}"));
+    }
+    let committed_at = client
+        .fetch_commit(value)
+        .with_context(|| format!("Input {name} is neither a timestamp nor a known tag: {value}"))?
+        .committed_at
+        .with_context(|| format!("Tag {value} given as {name} has no commit date."))?;
+    Ok(normalize_timestamp(&committed_at, false).unwrap_or(committed_at))
+}
+
+/// Lists the PRs merged in `window`, comparing against its starting tag when
+/// there is one the REST API can compare and searching otherwise. A tag the
+/// compare API can't find, such as one deleted since, is searched from too.
+fn fetch_pull_requests(
+    client: &dyn ReleaseApi,
+    branch: &str,
+    window: PullRequestWindow,
+    per_page: u32,
+    limits: FetchLimits,
+    api: PullRequestApi,
+) -> Result<MergedPullRequests> {
+    match window.compare_base {
+        Some(base_tag) if api == PullRequestApi::Rest && window.until.is_none() => {
+            match client.fetch_compared_pull_requests(base_tag, branch, per_page, limits) {
+                Err(error) if github::error_status(&error) == Some(StatusCode::NOT_FOUND) => {
+                    println!(
+                        "::warning::Could not compare {base_tag}...{branch}; searching for pull requests by merge date instead."
+                    );
+                    client.fetch_merged_pull_requests(
+                        branch,
+                        window.since,
+                        window.until,
+                        per_page,
+                        limits,
+                        api,
+                    )
+                }
+                fetched => fetched,
+            }
+        }
+        _ => client.fetch_merged_pull_requests(
+            branch,
+            window.since,
+            window.until,
+            per_page,
+            limits,
+            api,
+        ),
+    }
+}
+
+/// The PRs and direct commits found for a draft, and the tag they were
+/// compared from, if any.
+struct CollectedPullRequests {
+    pull_requests: Vec<PullRequestInfo>,
+    direct_commits: Vec<DirectCommit>,
+    compare_base: Option<String>,
+    /// Authors of the commits since `compare_base`, when the whole range was
+    /// walked while collecting.
+    commit_authors: Option<Vec<String>>,
+}
+
+/// Gathers a draft's PRs from the source and any extra `repositories`, once
+/// the start of the window is known.
+struct PullRequestCollector<'a> {
+    clients: &'a Clients,
+    config: Option<&'a ReleaseConfig>,
+    branch: &'a str,
+    marker: &'a str,
+    until: Option<&'a str>,
+    /// Title of the milestone whose PRs are collected instead of the window's.
+    milestone: Option<&'a str>,
+    cache_path: Option<PathBuf>,
+}
+
+impl PullRequestCollector<'_> {
+    /// Collects the PRs merged since `since`, comparing from `since_tag` when
+    /// the REST API can.
+    fn collect(
+        &self,
+        since: Option<&str>,
+        since_tag: Option<&str>,
+    ) -> Result<CollectedPullRequests> {
+        let clients = self.clients;
+        let config = self.config;
+        // The compare API only runs to the branch head, so a window with an
+        // end is searched. A milestone is searched for whenever its PRs were
+        // merged.
+        let compare_base = since_tag.filter(|_| self.until.is_none() && self.milestone.is_none());
+        let limits = read_fetch_limits()?;
+        let mut api = resolve_pull_request_api(read_input("pull-request-api"))?;
+        if self.milestone.is_some() {
+            api = PullRequestApi::Rest;
+        }
+        let lists_direct_commits = config.is_some_and(|config| config.direct_commits);
+        // Direct commits are only seen by walking every commit in the window,
+        // and a milestone isn't a window to resume.
+        let mut pr_cache = self
+            .cache_path
+            .as_ref()
+            .filter(|_| !lists_direct_commits && self.milestone.is_none())
+            .map(|path| pr_cache::PullRequestCache::load(path));
+        let cached = pr_cache
+            .as_ref()
+            .and_then(|cache| cache.resume(self.marker, since))
+            .cloned();
+        // A resumed window only needs what's new since the last run: a
+        // compare from the commit it reached, or else a search from a little
+        // before its cursor.
+        let (window_since, compared_from) = match &cached {
+            Some(window) => {
+                println!(
+                    "Resuming pull request discovery from {} cached pull requests",
+                    window.pull_requests.len()
+                );
+                (
+                    window.search_since().or_else(|| since.map(str::to_string)),
+                    window.head.as_deref().filter(|_| compare_base.is_some()),
+                )
+            }
+            None => (since.map(str::to_string), compare_base),
+        };
+        let window_since = window_since.as_deref();
+        // The previous tag only exists in the source repository, so the other
+        // `repositories` are always searched by merge date.
+        let mut sources = vec![(
+            clients.source_repository.as_str(),
+            clients.source.as_ref(),
+            compared_from,
+        )];
+        sources.extend(
+            clients
+                .extra_sources
+                .iter()
+                .map(|(repository, extra)| (repository.as_str(), extra.as_ref(), None)),
+        );
+        let aggregated = sources.len() > 1;
+        let mut author_names = HashMap::new();
+        if lists_direct_commits && (compare_base.is_none() || api != PullRequestApi::Rest) {
+            println!(
+                "Direct commits are only found by comparing against a previous tag with pull-request-api: rest; skipping them"
+            );
+        }
+        let mut pull_requests = Vec::new();
+        let mut direct_commits = Vec::new();
+        let mut fetched_truncated = None;
+        let mut head = None;
+        let mut commit_authors = None;
+        for (repository, source, compared_from) in sources {
+            let fetched = match self.milestone {
+                Some(milestone) => source.fetch_milestone_pull_requests(
+                    self.branch,
+                    milestone,
+                    clients.per_page,
+                    limits,
+                )?,
+                None => fetch_pull_requests(
+                    source,
+                    self.branch,
+                    PullRequestWindow {
+                        since: window_since,
+                        until: self.until,
+                        compare_base: compared_from,
+                    },
+                    clients.per_page,
+                    limits,
+                    api,
+                )?,
+            };
+            if let Some(reason) = &fetched.truncated {
+                println!(
+                    "::warning::Stopped fetching pull requests from {repository} after {} because breezy {reason}; release notes are incomplete.",
+                    fetched.pull_requests.len()
+                );
+            }
+            fetched_truncated = fetched_truncated.or(fetched.truncated);
+            head = head.or(fetched.head);
+            // A resumed compare only walks the commits since the cached head.
+            if compared_from.is_some() && compared_from == compare_base {
+                commit_authors = commit_authors.or(fetched.commit_authors);
+            }
+            direct_commits.extend(fetched.direct_commits);
+            let mut found = fetched.pull_requests;
+            if aggregated {
+                for pull_request in &mut found {
+                    pull_request.repository = Some(repository.to_string());
+                }
+            }
+            // Logins are global, so one lookup serves every repository.
+            fill_in_details(source, &mut found, config, api, &mut author_names)?;
+            pull_requests.extend(found);
+        }
+        set_output("truncated", &fetched_truncated.is_some().to_string())?;
+        if let Some(window) = &cached {
+            pull_requests = pr_cache::merge_pull_requests(pull_requests, &window.pull_requests);
+        }
+        // A truncated fetch would leave a gap the cursor skips past next run.
+        if let Some(cache) = &mut pr_cache
+            && fetched_truncated.is_none()
+        {
+            cache.store(self.marker, since, head.as_deref(), &pull_requests)?;
+        }
+        Ok(CollectedPullRequests {
+            pull_requests,
+            direct_commits,
+            compare_base: compare_base.map(str::to_string),
+            commit_authors,
         })
-        .map(|value| value.to_string());
-
-    let pull_requests =
-        client.fetch_merged_pull_requests(&branch, since.as_deref(), MAX_PER_PAGE)?;
-    let release_notes = build_release_notes(&marker, &pull_requests, config.as_ref());
-
-    if let Some(release_id) = selection.primary {
-        client.update_release(
-            release_id,
-            &tag_name,
-            &release_name,
-            &release_notes,
-            prerelease,
-            &branch,
-        )?;
+    }
+}
+
+/// The highest tag below the manifest's that matches the tag prefix, with its
+/// commit date, for `previous-from: tags`.
+fn previous_tag_boundary(
+    client: &dyn ReleaseApi,
+    per_page: u32,
+    tag_match_prefix: &str,
+    manifest_tag: Option<&str>,
+) -> Result<(Option<String>, Option<String>)> {
+    let tags = client.list_all_tags(per_page)?;
+    match select_previous_tag(
+        &tags,
+        |tag| tag.name.as_str(),
+        tag_match_prefix,
+        manifest_tag.unwrap_or(""),
+    ) {
+        Some(tag) => {
+            println!("Using tag {} as the previous release boundary", tag.name);
+            Ok((
+                client.fetch_commit(&tag.commit.sha)?.committed_at,
+                Some(tag.name.clone()),
+            ))
+        }
+        None => Ok((None, None)),
+    }
+}
+
+/// Looks up whatever the config's templates show beyond the PR itself:
+/// reviewers, closing issues, author names, and new contributors.
+fn fill_in_details(
+    client: &dyn ReleaseApi,
+    pull_requests: &mut [PullRequestInfo],
+    config: Option<&ReleaseConfig>,
+    api: PullRequestApi,
+    author_names: &mut HashMap<String, Option<String>>,
+) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    // GraphQL returns reviews and closing issues with each PR; REST needs a
+    // request per PR, so those are only made when a template uses them.
+    if config.change_template.contains("$REVIEWERS") && api != PullRequestApi::GraphQl {
+        fetch_reviewers(client, pull_requests)?;
+    }
+    if release_notes::uses_closing_issues(config) && api != PullRequestApi::GraphQl {
+        fetch_closing_issues(client, pull_requests)?;
+    }
+    if config.change_template.contains("$AUTHOR_NAME") {
+        fetch_author_names(client, pull_requests, author_names)?;
+    }
+    if release_notes::uses_new_contributors(config) {
+        mark_first_contributions(client, pull_requests, config)?;
+    }
+    Ok(())
+}
+
+/// Marks each PR that is its author's first merged PR in its repository,
+/// asking once per author and repository, until the rate limit says to stop.
+/// A failed search leaves the rest unmarked rather than failing the run.
+fn mark_first_contributions(
+    client: &dyn ReleaseApi,
+    pull_requests: &mut [PullRequestInfo],
+    config: &ReleaseConfig,
+) -> Result<()> {
+    let mut firsts: HashMap<(Option<String>, String), Option<u64>> = HashMap::new();
+    for pull_request in pull_requests {
+        if pull_request.author == "unknown"
+            || release_notes::is_excluded_contributor(config, &pull_request.author)
+        {
+            continue;
+        }
+        let key = (pull_request.repository.clone(), pull_request.author.clone());
+        if !firsts.contains_key(&key) {
+            if !client.allow_optional("new contributors") {
+                break;
+            }
+            match client.fetch_first_merged_pull_request(key.0.as_deref(), &key.1) {
+                Ok(first) => {
+                    firsts.insert(key.clone(), first);
+                }
+                Err(error) => {
+                    println!(
+                        "::warning::Skipping the remaining new contributors because a search failed: {error:#}"
+                    );
+                    break;
+                }
+            }
+        }
+        pull_request.first_contribution = firsts[&key] == Some(pull_request.number);
+    }
+    Ok(())
+}
+
+/// Fills in each PR's reviewers until the rate limit says to stop.
+fn fetch_reviewers(client: &dyn ReleaseApi, pull_requests: &mut [PullRequestInfo]) -> Result<()> {
+    for pull_request in pull_requests {
+        if !client.allow_optional("reviewers") {
+            break;
+        }
+        pull_request.reviewers =
+            client.fetch_reviewers(pull_request.number, &pull_request.author)?;
+    }
+    Ok(())
+}
+
+/// Drops the PRs not assigned to `milestone`, compared case-insensitively.
+fn retain_milestone(pull_requests: &mut Vec<PullRequestInfo>, milestone: &str) {
+    pull_requests.retain(|pull_request| {
+        pull_request
+            .milestone
+            .as_deref()
+            .is_some_and(|title| title.trim().eq_ignore_ascii_case(milestone))
+    });
+}
+
+/// Fills in each PR author's profile name, asking once per login, until the
+/// rate limit says to stop or a lookup fails. Authors without a name keep
+/// their login.
+fn fetch_author_names(
+    client: &dyn ReleaseApi,
+    pull_requests: &mut [PullRequestInfo],
+    names: &mut HashMap<String, Option<String>>,
+) -> Result<()> {
+    let mut looking_up = true;
+    for pull_request in pull_requests {
+        if pull_request.author == "unknown" {
+            continue;
+        }
+        if looking_up && !names.contains_key(&pull_request.author) {
+            if !client.allow_optional("author names") {
+                looking_up = false;
+            } else {
+                match client.fetch_user_name(&pull_request.author) {
+                    Ok(name) => {
+                        names.insert(pull_request.author.clone(), name);
+                    }
+                    Err(error) => {
+                        println!(
+                            "::warning::Showing logins for the remaining authors because a user lookup failed: {error:#}"
+                        );
+                        looking_up = false;
+                    }
+                }
+            }
+        }
+        pull_request.author_name = names.get(&pull_request.author).cloned().flatten();
+    }
+    Ok(())
+}
+
+/// Fills in the issues each PR closes until the rate limit says to stop.
+fn fetch_closing_issues(
+    client: &dyn ReleaseApi,
+    pull_requests: &mut [PullRequestInfo],
+) -> Result<()> {
+    for pull_request in pull_requests {
+        if !client.allow_optional("closing issues") {
+            break;
+        }
+        pull_request.closing_issues = client.fetch_closing_issues(pull_request.number)?;
+    }
+    Ok(())
+}
+
+/// Whether `draft` already has the notes, tag, name, and prerelease flag
+/// about to be saved, so updating it would change nothing.
+fn draft_is_current(draft: &ReleaseInfo, fields: &ReleaseFields) -> bool {
+    draft.body.as_deref() == Some(fields.body)
+        && draft.tag_name == fields.tag_name
+        && draft.name.as_deref() == Some(fields.name)
+        && draft.prerelease == fields.prerelease
+}
+
+/// Updates the scope's existing draft, or creates one when there is none.
+fn save_draft(
+    release_client: &dyn ReleaseApi,
+    primary: Option<u64>,
+    fields: &ReleaseFields,
+    scope_label: &str,
+) -> Result<ReleaseInfo> {
+    if let Some(release_id) = primary {
+        let release = release_client.update_release(release_id, fields)?;
         println!("Updated draft release {release_id} for {scope_label}");
+        Ok(release)
     } else {
-        client.create_release(
-            &tag_name,
-            &release_name,
-            &release_notes,
-            prerelease,
-            &branch,
-        )?;
+        let release = release_client.create_release(fields)?;
         println!("Created draft release for {scope_label}");
+        Ok(release)
     }
+}
+
+/// Whether a published release already holds `tag_name`, in which case
+/// GitHub rejects a draft for it. Its notes have shipped and the PR window now
+/// starts after it, so they are left alone and the version needs a bump.
+fn tag_already_released(
+    release_client: &dyn ReleaseApi,
+    tag_name: &str,
+    scope_label: &str,
+) -> Result<bool> {
+    // Looked up directly, since the release listing may stop before reaching
+    // an older release that holds the tag.
+    let Some(published) = release_client.fetch_release_by_tag(tag_name)? else {
+        return Ok(false);
+    };
+    println!(
+        "::warning::Release {} is already published, so the draft for {scope_label} was left as is; bump the version to draft the next release.",
+        published.tag_name
+    );
+    Ok(true)
+}
+
+fn run_archive(output: Option<String>, audit: &Arc<AuditLog>) -> Result<()> {
+    let directory = resolve_directory(read_input("directory"))?;
+    let cwd = env::current_dir().context("Unable to resolve current working directory.")?;
+    let (clients, config) = connect(&cwd, audit)?;
+
+    let output = output
+        .or_else(|| read_input("archive-dir"))
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| archive::DEFAULT_ARCHIVE_DIR.to_string());
+    let output_dir = cwd.join(output.trim());
+
+    let releases = clients.releases().list_all_releases(clients.per_page)?;
+    let fallback_branch = resolve_branch().ok();
+    let tag_prefix = resolve_tag_match_prefix(
+        &read_input("tag-prefix").unwrap_or_else(|| "v".to_string()),
+        directory.as_deref(),
+        config.as_ref(),
+    );
+    let scope = archive::ArchiveScope {
+        directory: directory.as_deref(),
+        tag_prefix: &tag_prefix,
+        repository: clients.marker_repository(),
+        fallback_branch: fallback_branch.as_deref(),
+        pull_request_api: resolve_pull_request_api(read_input("pull-request-api"))?,
+        per_page: clients.per_page,
+    };
+    let count = archive::write_archive(
+        clients.source.as_ref(),
+        &releases,
+        config.as_ref(),
+        &scope,
+        &output_dir,
+    )?;
+    println!("Archived {count} releases to {}", output_dir.display());
 
     Ok(())
 }
 
+/// The existing release a backfill rewrites, and the tags bounding its PRs.
+struct Backfill<'a> {
+    from_tag: &'a str,
+    to_tag: &'a str,
+    /// Prefix stripped from `to_tag` for `$RESOLVED_VERSION`.
+    tag_prefix: &'a str,
+    branch: &'a str,
+    per_page: u32,
+    limits: FetchLimits,
+    api: PullRequestApi,
+}
+
+fn run_backfill(audit: &Arc<AuditLog>) -> Result<()> {
+    let read_tag = |name: &str| {
+        read_input(name)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .with_context(|| format!("The backfill command needs the {name} input."))
+    };
+    let from_tag = read_tag("from-tag")?;
+    let to_tag = read_tag("to-tag")?;
+    let branch = resolve_branch()?;
+    let directory = resolve_directory(read_input("directory"))?;
+    let cwd = env::current_dir().context("Unable to resolve current working directory.")?;
+    let (clients, config) = connect(&cwd, audit)?;
+
+    let releases = clients
+        .releases()
+        .list_releases(clients.per_page, &mut |releases| {
+            releases.iter().any(|release| release.tag_name == to_tag)
+        })?;
+    let marker = release_marker(
+        &branch,
+        directory.as_deref(),
+        None,
+        clients.marker_repository(),
+    );
+    let tag_prefix = resolve_tag_match_prefix(
+        &read_input("tag-prefix").unwrap_or_else(|| "v".to_string()),
+        directory.as_deref(),
+        config.as_ref(),
+    );
+    let backfill = Backfill {
+        from_tag: &from_tag,
+        to_tag: &to_tag,
+        tag_prefix: &tag_prefix,
+        branch: &branch,
+        per_page: clients.per_page,
+        limits: read_fetch_limits()?,
+        api: resolve_pull_request_api(read_input("pull-request-api"))?,
+    };
+    backfill_release(
+        clients.source.as_ref(),
+        clients.releases(),
+        &releases,
+        &backfill,
+        config.as_ref(),
+        &marker,
+    )?;
+    Ok(())
+}
+
+/// Regenerates the notes of the release tagged `to_tag` from the PRs merged
+/// between the two tags' commits, leaving the release's other fields alone.
+fn backfill_release(
+    client: &dyn ReleaseApi,
+    release_client: &dyn ReleaseApi,
+    releases: &[ReleaseInfo],
+    backfill: &Backfill,
+    config: Option<&ReleaseConfig>,
+    marker: &str,
+) -> Result<ReleaseInfo> {
+    let release = releases
+        .iter()
+        .find(|release| release.tag_name == backfill.to_tag)
+        .with_context(|| format!("No release is tagged {} to backfill.", backfill.to_tag))?;
+    let since = resolve_window_bound(client, "from-tag", backfill.from_tag, false)?;
+    let until = resolve_window_bound(client, "to-tag", backfill.to_tag, true)?;
+    let fetched = client.fetch_merged_pull_requests(
+        backfill.branch,
+        Some(&since),
+        Some(&until),
+        backfill.per_page,
+        backfill.limits,
+        backfill.api,
+    )?;
+    if let Some(reason) = &fetched.truncated {
+        println!(
+            "::warning::Stopped fetching pull requests after {} because breezy {reason}; release notes are incomplete.",
+            fetched.pull_requests.len()
+        );
+    }
+    let mut pull_requests = fetched.pull_requests;
+    fill_in_details(
+        client,
+        &mut pull_requests,
+        config,
+        backfill.api,
+        &mut HashMap::new(),
+    )?;
+    let history = CommitHistory {
+        tag: backfill.to_tag.to_string(),
+        resolved_version: backfill
+            .to_tag
+            .strip_prefix(backfill.tag_prefix)
+            .unwrap_or(backfill.to_tag)
+            .to_string(),
+        previous_tag: Some(backfill.from_tag.to_string()),
+        repository_url: Some(release_client.repository_url()),
+        ..CommitHistory::default()
+    };
+    let notes = build_release_notes(marker, &pull_requests, &history, config);
+    let name = release.name.as_deref().unwrap_or(&release.tag_name);
+    let updated = release_client.update_release_notes(release.id, name, &notes)?;
+    println!(
+        "Backfilled release {} with {} pull requests merged since {}",
+        backfill.to_tag,
+        pull_requests.len(),
+        backfill.from_tag
+    );
+    Ok(updated)
+}
+
+/// Tags `object` with the release name and notes as the message, signed when
+/// a `tag-signing-key` is given.
+fn create_annotated_tag(
+    client: &dyn ReleaseApi,
+    tag_name: &str,
+    object: &str,
+    release_name: &str,
+    notes: &str,
+) -> Result<()> {
+    let notes = notes.trim();
+    let mut message = format!("{release_name}\n");
+    if !notes.is_empty() {
+        message.push_str(&format!("\n{notes}\n"));
+    }
+    let tagger_name = read_input("tagger-name")
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TAGGER_NAME.to_string());
+    let tagger_email = read_input("tagger-email")
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TAGGER_EMAIL.to_string());
+    let tagger = signing::Tagger {
+        name: tagger_name.trim(),
+        email: tagger_email.trim(),
+    };
+    let timestamp = github::unix_now();
+
+    let signed = match read_input("tag-signing-key").filter(|value| !value.trim().is_empty()) {
+        Some(key) => {
+            let key = signing::SshSigningKey::from_openssh(&key)?;
+            let payload = signing::tag_payload(object, tag_name, &tagger, timestamp, &message);
+            message.push_str(&key.sign(payload.as_bytes()));
+            true
+        }
+        None => false,
+    };
+    client.create_annotated_tag(tag_name, object, &message, &tagger, timestamp)?;
+    println!(
+        "Created {}annotated tag {tag_name} at {object}",
+        if signed { "signed " } else { "" }
+    );
+    Ok(())
+}
+
+/// Uploads `content` as `name`, replacing an asset of that name on the release.
+fn replace_release_asset(
+    client: &dyn ReleaseApi,
+    release: &ReleaseInfo,
+    name: &str,
+    content: Vec<u8>,
+) -> Result<()> {
+    if let Some(existing) = release.assets.iter().find(|asset| asset.name == name) {
+        client.delete_release_asset(existing.id)?;
+    }
+    client.upload_release_asset(release, name, content)?;
+    Ok(())
+}
+
+/// Pairs every asset on the release with its SHA-256: fresh hashes for this
+/// run's uploads, GitHub's digest for the rest, or a download when there is none.
+fn collect_checksums(
+    client: &dyn ReleaseApi,
+    release: &ReleaseInfo,
+    mut entries: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>> {
+    for asset in &release.assets {
+        if assets::is_checksum_asset(&asset.name)
+            || entries.iter().any(|(name, _)| *name == asset.name)
+        {
+            continue;
+        }
+        let hex = match asset
+            .digest
+            .as_deref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+        {
+            Some(hex) => hex.to_string(),
+            None => assets::sha256_hex(&client.download_release_asset(asset.id)?),
+        };
+        entries.push((asset.name.clone(), hex));
+    }
+    Ok(entries)
+}
+
 fn input_key(name: &str) -> String {
     format!("INPUT_{}", name.replace(' ', "_").to_uppercase())
 }
@@ -160,27 +1643,247 @@ fn read_input(name: &str) -> Option<String> {
     None
 }
 
-fn resolve_language(input: &str, config: Option<&ReleaseConfig>) -> Result<String> {
-    if !input.trim().is_empty() {
-        return Ok(input.trim().to_string());
+fn parse_u64_input(name: &str, default: u64) -> Result<u64> {
+    let Some(raw) = read_input(name).filter(|value| !value.trim().is_empty()) else {
+        return Ok(default);
+    };
+    raw.trim()
+        .parse()
+        .with_context(|| format!("Input {name} must be a non-negative integer."))
+}
+
+/// Reads a timeout in seconds; `0` disables it.
+fn parse_timeout_input(name: &str, default: Duration) -> Result<Option<Duration>> {
+    let seconds = parse_u64_input(name, default.as_secs())?;
+    Ok((seconds > 0).then(|| Duration::from_secs(seconds)))
+}
+
+/// GitHub serves at most 100 items a page.
+fn resolve_per_page(per_page: u64) -> Result<u32> {
+    match u32::try_from(per_page) {
+        Ok(per_page @ 1..=MAX_PER_PAGE) => Ok(per_page),
+        _ => bail!("Input per-page must be between 1 and {MAX_PER_PAGE}."),
     }
-    if let Some(config) = config
-        && let Some(language) = &config.language
-        && !language.trim().is_empty()
-    {
-        return Ok(language.trim().to_string());
+}
+
+/// `max-prs` and `fetch-timeout` (seconds) default to 0, meaning unlimited.
+fn read_fetch_limits() -> Result<FetchLimits> {
+    let max_prs = parse_u64_input("max-prs", 0)?;
+    let timeout = parse_u64_input("fetch-timeout", 0)?;
+    Ok(FetchLimits {
+        max_prs: (max_prs > 0).then_some(max_prs as usize),
+        time_budget: (timeout > 0).then(|| Duration::from_secs(timeout)),
+    })
+}
+
+/// Publishes the run's changes as the `audit` output, and to `audit-file`
+/// when one is given.
+fn write_audit(audit: &AuditLog) -> Result<()> {
+    let json = audit.to_json()?;
+    set_output("audit", &json)?;
+    if let Some(path) = read_input("audit-file").filter(|value| !value.trim().is_empty()) {
+        let path = Path::new(path.trim());
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, format!("{json}\n"))
+            .with_context(|| format!("Failed to write audit log {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn set_output(name: &str, value: &str) -> Result<()> {
+    let Some(path) = env::var_os("GITHUB_OUTPUT") else {
+        return Ok(());
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open GITHUB_OUTPUT.")?;
+    writeln!(file, "{name}={value}").context("Failed to write GITHUB_OUTPUT.")
+}
+
+/// Returns true when the Helm archetype should read `appVersion`.
+fn resolve_helm_version_field(input: Option<String>) -> Result<bool> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "version" => Ok(false),
+        "appversion" | "app-version" => Ok(true),
+        other => bail!("Unknown helm-version-field: {other}. Expected version or appVersion."),
+    }
+}
+
+/// Renders the `build-metadata` template. Characters SemVer doesn't allow in build
+/// metadata become `-`, and empty identifiers are dropped.
+fn render_build_metadata(
+    template: &str,
+    sha: Option<&str>,
+    run_number: Option<&str>,
+    date: &str,
+) -> Option<String> {
+    let sha_short: String = sha.unwrap_or("").chars().take(7).collect();
+    let rendered = fill_variables(
+        template,
+        &[
+            ("$SHA_SHORT", &sha_short),
+            ("$RUN_NUMBER", run_number.unwrap_or("")),
+            ("$DATE", date),
+        ],
+    );
+    let identifiers: Vec<String> = rendered
+        .split('.')
+        .map(|identifier| {
+            identifier
+                .trim()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect::<String>()
+        })
+        .filter(|identifier| !identifier.is_empty())
+        .collect();
+    Some(identifiers.join(".")).filter(|metadata| !metadata.is_empty())
+}
+
+fn append_build_metadata(version: &str, metadata: &str) -> String {
+    let separator = if version.contains('+') { '.' } else { '+' };
+    format!("{version}{separator}{metadata}")
+}
+
+/// Reads the `version` input, dropping a leading `v` so `v1.2.0` and `1.2.0` both work
+/// with the tag prefix.
+fn parse_version_override(input: Option<String>) -> Option<String> {
+    let value = input?.trim().to_string();
+    let version = match value.strip_prefix(['v', 'V']) {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest.to_string(),
+        _ => value,
+    };
+    Some(version).filter(|version| !version.is_empty())
+}
+
+fn resolve_version_check(input: Option<String>) -> Result<bool> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "first" => Ok(false),
+        "strict" => Ok(true),
+        other => bail!("Unknown version-check value: {other}. Expected first or strict."),
+    }
+}
+
+fn resolve_content_source(name: &str, input: Option<String>) -> Result<ContentSource> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "local" => Ok(ContentSource::Local),
+        "api" => Ok(ContentSource::Api),
+        other => bail!("Unknown {name} value: {other}. Expected local or api."),
+    }
+}
+
+fn load_config_from_api(
+    client: &dyn ReleaseApi,
+    input: Option<String>,
+) -> Result<Option<ReleaseConfig>> {
+    let explicit = input.filter(|value| !value.trim().is_empty());
+    let path = explicit
+        .as_deref()
+        .map(|value| value.trim().trim_start_matches("./"))
+        .unwrap_or(config::DEFAULT_CONFIG_PATH);
+    match client.fetch_file_contents(path, None)? {
+        Some(content) => Ok(Some(config::parse_config(&content).with_context(|| {
+            format!("Failed to parse config file {path} from the API")
+        })?)),
+        None if explicit.is_some() => bail!("Config file not found in repository: {path}"),
+        None => Ok(None),
+    }
+}
+
+fn resolve_flag(name: &str, input: Option<String>) -> Result<bool> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "false" => Ok(false),
+        "true" => Ok(true),
+        other => bail!("Unknown {name} value: {other}. Expected true or false."),
+    }
+}
+
+fn resolve_make_latest(input: Option<String>) -> Result<Option<MakeLatest>> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" => Ok(None),
+        "true" => Ok(Some(MakeLatest::True)),
+        "false" => Ok(Some(MakeLatest::False)),
+        "legacy" => Ok(Some(MakeLatest::Legacy)),
+        other => bail!("Unknown make-latest value: {other}. Expected true, false, or legacy."),
+    }
+}
+
+fn resolve_previous_source(input: Option<String>) -> Result<PreviousSource> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "releases" => Ok(PreviousSource::Releases),
+        "tags" => Ok(PreviousSource::Tags),
+        other => bail!("Unknown previous-from value: {other}. Expected releases or tags."),
+    }
+}
+
+fn resolve_provider(input: Option<String>) -> Result<Provider> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "github" => Ok(Provider::GitHub),
+        "gitea" | "forgejo" => Ok(Provider::Gitea),
+        other => bail!("Unknown provider value: {other}. Expected github or gitea."),
     }
-    bail!("Missing required input: language");
 }
 
-fn apply_template(template: &str, version: &str, directory: Option<&str>) -> String {
-    let mut rendered = template.replace("$VERSION", version);
-    rendered = rendered.replace("$DIRECTORY", directory.unwrap_or(""));
-    rendered
+fn resolve_pull_request_api(input: Option<String>) -> Result<PullRequestApi> {
+    let value = input.unwrap_or_default().trim().to_lowercase();
+    match value.as_str() {
+        "" | "rest" => Ok(PullRequestApi::Rest),
+        "graphql" => Ok(PullRequestApi::GraphQl),
+        other => bail!("Unknown pull-request-api value: {other}. Expected rest or graphql."),
+    }
+}
+
+fn configured_language(input: &str, config: Option<&ReleaseConfig>) -> Option<String> {
+    if !input.trim().is_empty() {
+        return Some(input.trim().to_string());
+    }
+    config
+        .and_then(|config| config.language.as_deref())
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(str::to_string)
+}
+
+/// `$RESOLVED_VERSION` falls back to `$VERSION` when no version-resolver is configured.
+fn apply_template(
+    template: &str,
+    version: &str,
+    resolved_version: Option<&str>,
+    directory: Option<&str>,
+) -> String {
+    fill_variables(
+        template,
+        &[
+            ("$RESOLVED_VERSION", resolved_version.unwrap_or(version)),
+            ("$VERSION", version),
+            ("$DIRECTORY", directory.unwrap_or("")),
+        ],
+    )
 }
 
 fn resolve_tag_name(
     version: &str,
+    resolved_version: Option<&str>,
     tag_prefix: &str,
     directory: Option<&str>,
     config: Option<&ReleaseConfig>,
@@ -188,42 +1891,96 @@ fn resolve_tag_name(
     if let Some(config) = config
         && let Some(template) = &config.tag_template
     {
-        return apply_template(template, version, directory);
+        return apply_template(template, version, resolved_version, directory);
     }
     format!("{}{}", tag_prefix.trim(), version)
 }
 
+/// Returns the literal tag text that precedes the version, so tags of other
+/// sub-projects or naming schemes are ignored when looking for a previous tag.
+fn resolve_tag_match_prefix(
+    tag_prefix: &str,
+    directory: Option<&str>,
+    config: Option<&ReleaseConfig>,
+) -> String {
+    if let Some(config) = config
+        && let Some(template) = &config.tag_template
+    {
+        let prefix = template
+            .split("$RESOLVED_VERSION")
+            .next()
+            .and_then(|prefix| prefix.split("$VERSION").next())
+            .unwrap_or("");
+        return apply_template(prefix, "", None, directory);
+    }
+    tag_prefix.trim().to_string()
+}
+
+/// The `$PREVIOUS_TAG` for the notes and the repository address for its
+/// compare link. The tag comes from the window actually collected, so `since`
+/// replaces the previous release's. A `since` or `previous-from: tags` tag is
+/// in the source repository, so with `target-repo` the link is left out.
+fn previous_tag_for_notes(
+    since_override: Option<&(String, Option<String>)>,
+    previous_tag: Option<&String>,
+    previous_source: &PreviousSource,
+    has_target: bool,
+    release_repository_url: String,
+) -> (Option<String>, Option<String>) {
+    let (tag, from_source) = match since_override {
+        Some((_, tag)) => (tag.clone(), true),
+        None => (
+            previous_tag.cloned(),
+            matches!(previous_source, PreviousSource::Tags),
+        ),
+    };
+    let repository_url = Some(release_repository_url).filter(|_| !(has_target && from_source));
+    (tag, repository_url)
+}
+
 fn resolve_release_name(
     version: &str,
+    resolved_version: Option<&str>,
     tag_name: &str,
-    branch: &str,
+    scope_label: &str,
     directory: Option<&str>,
     config: Option<&ReleaseConfig>,
 ) -> String {
     if let Some(config) = config
         && let Some(template) = &config.name_template
     {
-        return apply_template(template, version, directory);
+        return apply_template(template, version, resolved_version, directory);
     }
-    let scope = format_scope_label(branch, directory);
-    format!("{tag_name} ({scope})")
+    format!("{tag_name} ({scope_label})")
 }
 
 fn parse_repository() -> Result<(String, String)> {
     let repository =
         env::var("GITHUB_REPOSITORY").context("Missing GITHUB_REPOSITORY environment variable.")?;
+    parse_repository_slug(&repository, "GITHUB_REPOSITORY")
+}
+
+fn parse_repository_slug(repository: &str, name: &str) -> Result<(String, String)> {
     let mut parts = repository.splitn(2, '/');
     let owner = parts.next().unwrap_or_default();
     let repo = parts.next().unwrap_or_default();
     if owner.is_empty() || repo.is_empty() {
-        return Err(anyhow!(
-            "Invalid GITHUB_REPOSITORY value; expected owner/repo."
-        ));
+        return Err(anyhow!("Invalid {name} value; expected owner/repo."));
     }
 
     Ok((owner.to_string(), repo.to_string()))
 }
 
+/// The branch a merge queue ref such as `gh-readonly-queue/main/pr-12-<sha>`
+/// merges into, so queue runs update the base branch's draft. Other names are
+/// returned as is.
+fn merge_queue_base(branch: &str) -> &str {
+    branch
+        .strip_prefix("gh-readonly-queue/")
+        .and_then(|rest| rest.rsplit_once("/pr-"))
+        .map_or(branch, |(base, _)| base)
+}
+
 fn resolve_branch() -> Result<String> {
     if let Ok(value) = env::var("GITHUB_HEAD_REF") {
         let trimmed = value.trim();
@@ -235,14 +1992,14 @@ fn resolve_branch() -> Result<String> {
     if let Ok(value) = env::var("GITHUB_REF_NAME") {
         let trimmed = value.trim();
         if !trimmed.is_empty() {
-            return Ok(trimmed.to_string());
+            return Ok(merge_queue_base(trimmed).to_string());
         }
     }
 
     if let Ok(value) = env::var("GITHUB_REF") {
         let trimmed = value.trim();
         if let Some(stripped) = trimmed.strip_prefix("refs/heads/") {
-            return Ok(stripped.to_string());
+            return Ok(merge_queue_base(stripped).to_string());
         }
         if trimmed.starts_with("refs/pull/")
             && let Ok(head) = env::var("GITHUB_HEAD_REF")
@@ -294,6 +2051,28 @@ fn resolve_directory(input: Option<String>) -> Result<Option<String>> {
     Ok(Some(value.to_string()))
 }
 
+/// `cache-dir` defaults to `$RUNNER_TEMP/breezy-cache`, which lasts for the
+/// job; `none` turns the cache off.
+fn resolve_cache_dir(
+    input: Option<String>,
+    runner_temp: Option<String>,
+    cwd: &Path,
+) -> Option<PathBuf> {
+    let Some(raw) = input.filter(|value| !value.trim().is_empty()) else {
+        let temp = runner_temp.filter(|value| !value.trim().is_empty())?;
+        return Some(Path::new(temp.trim()).join(DEFAULT_CACHE_DIR_NAME));
+    };
+    let trimmed = raw.trim();
+    if trimmed.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    let path = PathBuf::from(trimmed);
+    if path.is_absolute() {
+        return Some(path);
+    }
+    Some(cwd.join(path))
+}
+
 fn format_scope_label(branch: &str, directory: Option<&str>) -> String {
     if let Some(directory) = directory.filter(|value| !value.trim().is_empty()) {
         return format!("{branch}/{directory}");
@@ -301,7 +2080,7 @@ fn format_scope_label(branch: &str, directory: Option<&str>) -> String {
     branch.to_string()
 }
 
-fn select_draft_releases(releases: &[ReleaseInfo], marker: &str) -> DraftSelection {
+fn select_draft_releases<'a>(releases: &'a [ReleaseInfo], marker: &str) -> DraftSelection<'a> {
     let mut drafts: Vec<&ReleaseInfo> = releases
         .iter()
         .filter(|release| release.draft && release.body.as_deref().unwrap_or("").contains(marker))
@@ -309,21 +2088,70 @@ fn select_draft_releases(releases: &[ReleaseInfo], marker: &str) -> DraftSelecti
 
     drafts.sort_by(|left, right| right.created_at.cmp(&left.created_at));
 
-    let primary = drafts.first().map(|release| release.id);
-    let extras = drafts.iter().skip(1).map(|release| release.id).collect();
+    let primary = drafts.first().copied();
+    let extras = drafts.iter().skip(1).copied().collect();
 
     DraftSelection { primary, extras }
 }
 
+/// Lists the releases a draft run looks at. Older duplicate drafts can be on
+/// any page, so every page is read unless `keep_duplicates` says they're left
+/// alone; then the listing stops at the scope's draft and its latest
+/// published release, which on a long history is a page or two since releases
+/// come newest first. The latest published release goes to
+/// `on_latest_published` on the page it turns up, before any further pages
+/// are listed.
+fn list_scope_releases(
+    client: &dyn ReleaseApi,
+    per_page: u32,
+    marker: &str,
+    branch: Option<&str>,
+    marker_filter: Option<&str>,
+    keep_duplicates: bool,
+    on_latest_published: &mut dyn FnMut(&ReleaseInfo),
+) -> Result<Vec<ReleaseInfo>> {
+    let mut reported = false;
+    client.list_releases(per_page, &mut |releases| {
+        let latest = select_latest_published_release(releases, branch, marker_filter);
+        if let Some(latest) = latest
+            && !reported
+        {
+            on_latest_published(latest);
+            reported = true;
+        }
+        keep_duplicates
+            && select_draft_releases(releases, marker).primary.is_some()
+            && latest.is_some()
+    })
+}
+
+/// Where a window starting at `release` opens, and its tag.
+fn release_boundary(release: Option<&ReleaseInfo>) -> (Option<String>, Option<String>) {
+    (
+        release.map(|release| {
+            release
+                .published_at
+                .clone()
+                .unwrap_or_else(|| release.created_at.clone())
+        }),
+        release.map(|release| release.tag_name.clone()),
+    )
+}
+
 fn select_latest_published_release<'a>(
     releases: &'a [ReleaseInfo],
-    branch: &str,
+    branch: Option<&str>,
     marker: Option<&str>,
 ) -> Option<&'a ReleaseInfo> {
     let mut published: Vec<&ReleaseInfo> = releases
         .iter()
         .filter(|release| {
-            if release.draft || release.target_commitish != branch {
+            if release.draft {
+                return false;
+            }
+            if let Some(branch) = branch
+                && release.target_commitish != branch
+            {
                 return false;
             }
             if let Some(marker) = marker {
@@ -347,7 +2175,7 @@ fn select_latest_published_release<'a>(
 }
 
 fn published_release_matches_commit(
-    client: &github::GitHubClient,
+    client: &dyn ReleaseApi,
     release: &ReleaseInfo,
     current_sha: &str,
 ) -> Result<bool> {
@@ -361,3 +2189,1242 @@ fn published_release_matches_commit(
     let release_sha = client.resolve_commit_sha(tag_name)?;
     Ok(release_sha == current_sha)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use github::{AssetInfo, CommitInfo, TagCommit, TagInfo};
+    use release_notes::IssueReference;
+    use signing::Tagger;
+    use std::sync::Mutex;
+    use version::DirEntry;
+
+    /// An in-memory repository standing in for the GitHub API. Every mutating
+    /// or pull request call is appended to `calls`.
+    #[derive(Default)]
+    struct FakeApi {
+        // Shared so a test can still look at them once the fake is boxed
+        // into `Clients`.
+        releases: Arc<Mutex<Vec<ReleaseInfo>>>,
+        pull_requests: Vec<PullRequestInfo>,
+        calls: Arc<Mutex<Vec<String>>>,
+        forbid_deletes: bool,
+    }
+
+    impl FakeApi {
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn fetched(&self) -> MergedPullRequests {
+            MergedPullRequests {
+                pull_requests: self.pull_requests.clone(),
+                truncated: None,
+                direct_commits: Vec::new(),
+                head: None,
+                commit_authors: None,
+            }
+        }
+
+        fn apply(release: &mut ReleaseInfo, fields: &ReleaseFields) {
+            release.tag_name = fields.tag_name.to_string();
+            release.name = Some(fields.name.to_string());
+            release.body = Some(fields.body.to_string());
+            release.prerelease = fields.prerelease;
+        }
+    }
+
+    impl version::TagSource for FakeApi {
+        fn tag_names(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+    }
+
+    impl ReleaseApi for FakeApi {
+        fn allow_optional(&self, _feature: &str) -> bool {
+            true
+        }
+
+        fn degraded_features(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn repository_url(&self) -> String {
+            "https://github.com/o/r".to_string()
+        }
+
+        fn list_releases(
+            &self,
+            per_page: u32,
+            enough: &mut dyn FnMut(&[ReleaseInfo]) -> bool,
+        ) -> Result<Vec<ReleaseInfo>> {
+            let mut listed = Vec::new();
+            for page in self.releases.lock().unwrap().chunks(per_page as usize) {
+                listed.extend_from_slice(page);
+                if enough(&listed) {
+                    break;
+                }
+            }
+            Ok(listed)
+        }
+
+        fn delete_release(&self, release_id: u64) -> Result<()> {
+            self.record(format!("delete release {release_id}"));
+            if self.forbid_deletes {
+                return Err(github::HttpStatusError::new(
+                    StatusCode::FORBIDDEN,
+                    "HTTP 403 Forbidden".to_string(),
+                )
+                .into());
+            }
+            self.releases
+                .lock()
+                .unwrap()
+                .retain(|release| release.id != release_id);
+            Ok(())
+        }
+
+        fn upload_release_asset(
+            &self,
+            release: &ReleaseInfo,
+            name: &str,
+            content: Vec<u8>,
+        ) -> Result<AssetInfo> {
+            let release_id = release.id;
+            self.record(format!(
+                "upload {name} ({} bytes) to {release_id}",
+                content.len()
+            ));
+            let asset = AssetInfo {
+                id: 1_000 + release_id,
+                name: name.to_string(),
+                digest: None,
+            };
+            let mut releases = self.releases.lock().unwrap();
+            let release = releases
+                .iter_mut()
+                .find(|release| release.id == release_id)
+                .context("no such release")?;
+            release.assets.push(asset.clone());
+            Ok(asset)
+        }
+
+        fn download_release_asset(&self, asset_id: u64) -> Result<Vec<u8>> {
+            self.record(format!("download asset {asset_id}"));
+            Ok(b"asset".to_vec())
+        }
+
+        fn delete_release_asset(&self, asset_id: u64) -> Result<()> {
+            self.record(format!("delete asset {asset_id}"));
+            for release in self.releases.lock().unwrap().iter_mut() {
+                release.assets.retain(|asset| asset.id != asset_id);
+            }
+            Ok(())
+        }
+
+        fn update_release(&self, release_id: u64, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+            self.record(format!(
+                "update release {release_id} as {}",
+                fields.tag_name
+            ));
+            let mut releases = self.releases.lock().unwrap();
+            let release = releases
+                .iter_mut()
+                .find(|release| release.id == release_id)
+                .context("no such release")?;
+            Self::apply(release, fields);
+            Ok(release.clone())
+        }
+
+        fn create_release(&self, fields: &ReleaseFields) -> Result<ReleaseInfo> {
+            self.record(format!("create release {}", fields.tag_name));
+            let mut releases = self.releases.lock().unwrap();
+            let mut release = draft(
+                releases.iter().map(|release| release.id).max().unwrap_or(0) + 1,
+                "",
+                "2024-06-01T00:00:00Z",
+            );
+            Self::apply(&mut release, fields);
+            releases.push(release.clone());
+            Ok(release)
+        }
+
+        fn update_release_notes(
+            &self,
+            release_id: u64,
+            name: &str,
+            body: &str,
+        ) -> Result<ReleaseInfo> {
+            self.record(format!("update notes of release {release_id}"));
+            let mut releases = self.releases.lock().unwrap();
+            let release = releases
+                .iter_mut()
+                .find(|release| release.id == release_id)
+                .context("no such release")?;
+            release.name = Some(name.to_string());
+            release.body = Some(body.to_string());
+            Ok(release.clone())
+        }
+
+        fn publish_release(
+            &self,
+            release_id: u64,
+            _target_commitish: Option<&str>,
+            fields: &ReleaseFields,
+        ) -> Result<ReleaseInfo> {
+            let mut release = self.update_release(release_id, fields)?;
+            release.draft = false;
+            Ok(release)
+        }
+
+        fn check_release_access(&self) -> Result<ReleaseAccess> {
+            Ok(ReleaseAccess {
+                read: true,
+                write: true,
+            })
+        }
+
+        fn fetch_commit_authors(
+            &self,
+            base_tag: &str,
+            branch: &str,
+            _per_page: u32,
+        ) -> Result<Vec<String>> {
+            self.record(format!("commit authors in {base_tag}...{branch}"));
+            Ok(Vec::new())
+        }
+
+        fn fetch_closing_issues(&self, number: u64) -> Result<Vec<IssueReference>> {
+            self.record(format!("closing issues of {number}"));
+            Ok(Vec::new())
+        }
+
+        fn fetch_reviewers(&self, number: u64, _author: &str) -> Result<Vec<String>> {
+            self.record(format!("reviewers of {number}"));
+            Ok(vec!["reviewer".to_string()])
+        }
+
+        fn fetch_user_name(&self, login: &str) -> Result<Option<String>> {
+            self.record(format!("user {login}"));
+            if login == "broken" {
+                bail!("user lookup failed");
+            }
+            Ok(Some(format!("{login} name")))
+        }
+
+        fn fetch_first_merged_pull_request(
+            &self,
+            repository: Option<&str>,
+            login: &str,
+        ) -> Result<Option<u64>> {
+            match repository {
+                Some(repository) => {
+                    self.record(format!("first pull request by {login} in {repository}"))
+                }
+                None => self.record(format!("first pull request by {login}")),
+            }
+            if login == "failing" {
+                bail!("search failed");
+            }
+            Ok(self
+                .pull_requests
+                .iter()
+                .filter(|pull_request| {
+                    pull_request.author == login && pull_request.repository.as_deref() == repository
+                })
+                .map(|pull_request| pull_request.number)
+                .min())
+        }
+
+        fn tag_exists(&self, _tag: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn fetch_release_by_tag(&self, tag: &str) -> Result<Option<ReleaseInfo>> {
+            self.record(format!("release tagged {tag}"));
+            Ok(self
+                .releases
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|release| !release.draft && release.tag_name == tag)
+                .cloned())
+        }
+
+        fn create_annotated_tag(
+            &self,
+            tag: &str,
+            object: &str,
+            _message: &str,
+            _tagger: &Tagger,
+            _timestamp: u64,
+        ) -> Result<()> {
+            self.record(format!("tag {tag} at {object}"));
+            Ok(())
+        }
+
+        fn list_all_tags(&self, _per_page: u32) -> Result<Vec<TagInfo>> {
+            Ok(Vec::new())
+        }
+
+        fn resolve_commit_sha(&self, reference: &str) -> Result<String> {
+            Ok(format!("sha-{reference}"))
+        }
+
+        fn fetch_commit(&self, reference: &str) -> Result<CommitInfo> {
+            Ok(CommitInfo {
+                sha: format!("sha-{reference}"),
+                committed_at: Some(format!("date of {reference}")),
+            })
+        }
+
+        fn fetch_file_contents(
+            &self,
+            _path: &str,
+            _reference: Option<&str>,
+        ) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        fn list_directory(&self, _path: &str, _reference: &str) -> Result<Vec<DirEntry>> {
+            Ok(Vec::new())
+        }
+
+        fn fetch_merged_pull_requests(
+            &self,
+            branch: &str,
+            since: Option<&str>,
+            _until: Option<&str>,
+            _per_page: u32,
+            _limits: FetchLimits,
+            api: PullRequestApi,
+        ) -> Result<MergedPullRequests> {
+            self.record(format!(
+                "list {api:?} pull requests into {branch} since {}",
+                since.unwrap_or("the start")
+            ));
+            Ok(self.fetched())
+        }
+
+        fn fetch_milestone_pull_requests(
+            &self,
+            branch: &str,
+            milestone: &str,
+            _per_page: u32,
+            _limits: FetchLimits,
+        ) -> Result<MergedPullRequests> {
+            self.record(format!(
+                "list pull requests into {branch} in milestone {milestone}"
+            ));
+            Ok(self.fetched())
+        }
+
+        fn fetch_compared_pull_requests(
+            &self,
+            base_tag: &str,
+            branch: &str,
+            _per_page: u32,
+            _limits: FetchLimits,
+        ) -> Result<MergedPullRequests> {
+            self.record(format!("compare {base_tag}...{branch}"));
+            if base_tag == "deleted" {
+                return Err(github::HttpStatusError::new(
+                    StatusCode::NOT_FOUND,
+                    "HTTP 404 Not Found".to_string(),
+                )
+                .into());
+            }
+            Ok(MergedPullRequests {
+                commit_authors: Some(vec!["grace".to_string()]),
+                ..self.fetched()
+            })
+        }
+    }
+
+    fn shared(releases: Vec<ReleaseInfo>) -> Arc<Mutex<Vec<ReleaseInfo>>> {
+        Arc::new(Mutex::new(releases))
+    }
+
+    fn draft(id: u64, body: &str, created_at: &str) -> ReleaseInfo {
+        ReleaseInfo {
+            id,
+            tag_name: String::new(),
+            name: None,
+            body: Some(body.to_string()),
+            draft: true,
+            prerelease: false,
+            target_commitish: "main".to_string(),
+            created_at: created_at.to_string(),
+            published_at: None,
+            assets: Vec::new(),
+            upload_url: None,
+        }
+    }
+
+    fn fields<'a>(tag_name: &'a str, body: &'a str) -> ReleaseFields<'a> {
+        ReleaseFields {
+            tag_name,
+            name: tag_name,
+            body,
+            prerelease: false,
+            target_commitish: Some("main"),
+            make_latest: None,
+            discussion_category_name: None,
+        }
+    }
+
+    fn tag(name: &str) -> TagInfo {
+        TagInfo {
+            name: name.to_string(),
+            commit: TagCommit {
+                sha: format!("sha-{name}"),
+            },
+        }
+    }
+
+    #[test]
+    fn selects_highest_matching_previous_tag() {
+        let tags = vec![
+            tag("v1.2.0"),
+            tag("v1.10.0"),
+            tag("v1.11.0"),
+            tag("v1.12.0"),
+            tag("v2.0.0-rc.1"),
+            tag("worker-v9.0.0"),
+            tag("nightly"),
+        ];
+        fn name(tag: &TagInfo) -> &str {
+            &tag.name
+        }
+
+        let previous = select_previous_tag(&tags, name, "v", "v1.11.0").unwrap();
+        assert_eq!(previous.name, "v1.10.0");
+
+        let unreleased = select_previous_tag(&tags, name, "v", "v1.10.5").unwrap();
+        assert_eq!(unreleased.name, "v1.10.0");
+
+        let without_manifest = select_previous_tag(&tags, name, "v", "").unwrap();
+        assert_eq!(without_manifest.name, "v2.0.0-rc.1");
+    }
+
+    #[test]
+    fn previous_tag_for_notes_follows_the_collected_window() {
+        let url = || "https://github.com/o/target".to_string();
+        let previous = "v1.2.0".to_string();
+        let since_tag = (
+            "2024-05-01T00:00:00Z".to_string(),
+            Some("v1.0.0".to_string()),
+        );
+        let since_timestamp = ("2024-05-01T00:00:00Z".to_string(), None);
+
+        assert_eq!(
+            previous_tag_for_notes(
+                None,
+                Some(&previous),
+                &PreviousSource::Releases,
+                true,
+                url()
+            ),
+            (Some("v1.2.0".to_string()), Some(url()))
+        );
+        assert_eq!(
+            previous_tag_for_notes(
+                Some(&since_tag),
+                Some(&previous),
+                &PreviousSource::Releases,
+                false,
+                url()
+            ),
+            (Some("v1.0.0".to_string()), Some(url()))
+        );
+        assert_eq!(
+            previous_tag_for_notes(
+                Some(&since_timestamp),
+                Some(&previous),
+                &PreviousSource::Releases,
+                false,
+                url()
+            ),
+            (None, Some(url()))
+        );
+        assert_eq!(
+            previous_tag_for_notes(
+                Some(&since_tag),
+                Some(&previous),
+                &PreviousSource::Releases,
+                true,
+                url()
+            ),
+            (Some("v1.0.0".to_string()), None)
+        );
+        assert_eq!(
+            previous_tag_for_notes(None, Some(&previous), &PreviousSource::Tags, true, url()),
+            (Some("v1.2.0".to_string()), None)
+        );
+        assert_eq!(
+            previous_tag_for_notes(None, Some(&previous), &PreviousSource::Tags, false, url()),
+            (Some("v1.2.0".to_string()), Some(url()))
+        );
+    }
+
+    #[test]
+    fn tag_match_prefix_uses_template_prefix() {
+        let config = config::parse_config("tag-template: $DIRECTORY-v$VERSION").unwrap();
+
+        let prefix = resolve_tag_match_prefix("v", Some("crates/app"), Some(&config));
+
+        assert_eq!(prefix, "crates/app-v");
+    }
+
+    #[test]
+    fn renders_build_metadata() {
+        let metadata = render_build_metadata(
+            "build.$RUN_NUMBER.$SHA_SHORT.$DATE",
+            Some("abc1234def5678"),
+            Some("123"),
+            "20240517",
+        )
+        .unwrap();
+        let missing = render_build_metadata("build.$RUN_NUMBER", None, None, "20240517");
+
+        assert_eq!(metadata, "build.123.abc1234.20240517");
+        assert_eq!(
+            append_build_metadata("1.4.0", &metadata),
+            "1.4.0+build.123.abc1234.20240517"
+        );
+        assert_eq!(append_build_metadata("1.4.0+1", "sha"), "1.4.0+1.sha");
+        assert_eq!(missing, Some("build".to_string()));
+        assert_eq!(render_build_metadata("$RUN_NUMBER", None, None, ""), None);
+    }
+
+    #[test]
+    fn parses_version_override() {
+        assert_eq!(
+            parse_version_override(Some(" v1.2.0-rc.1 ".to_string())),
+            Some("1.2.0-rc.1".to_string())
+        );
+        assert_eq!(
+            parse_version_override(Some("2024.05".to_string())),
+            Some("2024.05".to_string())
+        );
+        assert_eq!(parse_version_override(Some("  ".to_string())), None);
+        assert_eq!(parse_version_override(None), None);
+    }
+
+    #[test]
+    fn templates_expose_resolved_version() {
+        let config = config::parse_config("tag-template: v$RESOLVED_VERSION").unwrap();
+
+        let resolved = resolve_tag_name("1.0.0", Some("1.1.0"), "v", None, Some(&config));
+        let fallback = resolve_tag_name("1.0.0", None, "v", None, Some(&config));
+
+        assert_eq!(resolved, "v1.1.0");
+        assert_eq!(fallback, "v1.0.0");
+        assert_eq!(resolve_tag_match_prefix("v", None, Some(&config)), "v");
+    }
+
+    #[test]
+    fn defaults_cache_dir_to_runner_temp() {
+        let cwd = Path::new("/work");
+        let temp = Some("/tmp/runner".to_string());
+
+        assert_eq!(
+            resolve_cache_dir(None, temp.clone(), cwd),
+            Some(PathBuf::from("/tmp/runner/breezy-cache"))
+        );
+        assert_eq!(
+            resolve_cache_dir(Some(".cache".to_string()), temp.clone(), cwd),
+            Some(PathBuf::from("/work/.cache"))
+        );
+        assert_eq!(resolve_cache_dir(Some("none".to_string()), temp, cwd), None);
+        assert_eq!(resolve_cache_dir(None, None, cwd), None);
+    }
+
+    #[test]
+    fn resolves_flags() {
+        assert!(!resolve_flag("publish", None).unwrap());
+        assert!(!resolve_flag("publish", Some(" false ".to_string())).unwrap());
+        assert!(resolve_flag("publish", Some("TRUE".to_string())).unwrap());
+        assert!(resolve_flag("publish", Some("yes".to_string())).is_err());
+    }
+
+    #[test]
+    fn resolves_merge_queue_refs_to_their_base() {
+        assert_eq!(
+            merge_queue_base("gh-readonly-queue/main/pr-12-abc123"),
+            "main"
+        );
+        assert_eq!(
+            merge_queue_base("gh-readonly-queue/release/1.x/pr-7-def456"),
+            "release/1.x"
+        );
+        assert_eq!(merge_queue_base("main"), "main");
+        assert_eq!(merge_queue_base("feature/pr-queue"), "feature/pr-queue");
+    }
+
+    #[test]
+    fn tells_timestamps_from_tags() {
+        assert!(is_timestamp("2024-05-01"));
+        assert!(is_timestamp("2024-05-01T12:00:00Z"));
+        assert!(!is_timestamp("v1.2.0"));
+        assert!(!is_timestamp("2024.05.01"));
+        assert!(!is_timestamp("2024-05-01-hotfix"));
+    }
+
+    #[test]
+    fn normalizes_window_bounds_to_utc() {
+        assert_eq!(
+            normalize_timestamp("2024-05-01", false).as_deref(),
+            Some("2024-05-01T00:00:00Z")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-05-01", true).as_deref(),
+            Some("2024-05-01T23:59:59Z")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-05-01T02:30:00+04:00", true).as_deref(),
+            Some("2024-04-30T22:30:00Z")
+        );
+        assert_eq!(normalize_timestamp("2024-13-01", false), None);
+
+        let api = FakeApi::default();
+        assert_eq!(
+            resolve_window_bound(&api, "until", "2024-05-01", true).unwrap(),
+            "2024-05-01T23:59:59Z"
+        );
+        assert!(resolve_window_bound(&api, "until", "2024-05-01Tnoon", true).is_err());
+    }
+
+    #[test]
+    fn resolves_per_page() {
+        assert_eq!(resolve_per_page(100).unwrap(), 100);
+        assert_eq!(resolve_per_page(25).unwrap(), 25);
+        assert!(resolve_per_page(0).is_err());
+        assert!(resolve_per_page(101).is_err());
+    }
+
+    #[test]
+    fn prunes_duplicate_drafts_onto_the_newest() {
+        let marker = release_marker("main", None, None, None);
+        let header = release_header(&marker);
+        let mut older = draft(1, &header, "2024-05-01T00:00:00Z");
+        older.assets.push(AssetInfo {
+            id: 11,
+            name: "app.tar.gz".to_string(),
+            digest: None,
+        });
+        let api = FakeApi {
+            releases: shared(vec![
+                older,
+                draft(2, &header, "2024-05-02T00:00:00Z"),
+                draft(3, "unrelated", "2024-05-03T00:00:00Z"),
+            ]),
+            ..FakeApi::default()
+        };
+
+        let releases = api.list_all_releases(MAX_PER_PAGE).unwrap();
+        let selection = select_draft_releases(&releases, &marker);
+        prune_extra_drafts(&api, &selection, &marker, false, "main").unwrap();
+        let release = save_draft(
+            &api,
+            selection.primary.map(|release| release.id),
+            &fields("v1.1.0", &marker),
+            "main",
+        )
+        .unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec![
+                "download asset 11",
+                "upload app.tar.gz (5 bytes) to 2",
+                "delete release 1",
+                "update release 2 as v1.1.0",
+            ]
+        );
+        assert_eq!(release.assets.len(), 1);
+        let remaining: Vec<u64> = api
+            .releases
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|release| release.id)
+            .collect();
+        assert_eq!(remaining, vec![2, 3]);
+    }
+
+    #[test]
+    fn drafts_notes_from_compared_or_searched_pull_requests() {
+        let api = FakeApi {
+            pull_requests: vec![PullRequestInfo {
+                number: 7,
+                title: "Add widgets".to_string(),
+                author: "octo".to_string(),
+                url: "https://github.com/o/r/pull/7".to_string(),
+                ..PullRequestInfo::default()
+            }],
+            ..FakeApi::default()
+        };
+        let since = Some("2024-05-01T00:00:00Z");
+
+        let compared = fetch_pull_requests(
+            &api,
+            "main",
+            PullRequestWindow {
+                since,
+                compare_base: Some("v1.0.0"),
+                ..PullRequestWindow::default()
+            },
+            MAX_PER_PAGE,
+            FetchLimits::default(),
+            PullRequestApi::Rest,
+        )
+        .unwrap();
+        fetch_pull_requests(
+            &api,
+            "main",
+            PullRequestWindow {
+                since,
+                compare_base: Some("v1.0.0"),
+                ..PullRequestWindow::default()
+            },
+            MAX_PER_PAGE,
+            FetchLimits::default(),
+            PullRequestApi::GraphQl,
+        )
+        .unwrap();
+        fetch_pull_requests(
+            &api,
+            "main",
+            PullRequestWindow::default(),
+            MAX_PER_PAGE,
+            FetchLimits::default(),
+            PullRequestApi::Rest,
+        )
+        .unwrap();
+
+        let marker = release_marker("main", None, None, None);
+        let notes = build_release_notes(
+            &marker,
+            &compared.pull_requests,
+            &CommitHistory::default(),
+            None,
+        );
+        save_draft(&api, None, &fields("v1.1.0", &notes), "main").unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec![
+                "compare v1.0.0...main",
+                "list GraphQl pull requests into main since 2024-05-01T00:00:00Z",
+                "list Rest pull requests into main since the start",
+                "create release v1.1.0",
+            ]
+        );
+        let releases = api.releases.lock().unwrap();
+        let body = releases[0].body.as_deref().unwrap();
+        assert!(body.contains("Add widgets"));
+        assert!(select_draft_releases(&releases, &marker).primary.is_some());
+    }
+
+    #[test]
+    fn audits_each_change_with_body_hashes() {
+        let marker = release_marker("main", None, None, None);
+        let header = release_header(&marker);
+        let log = Arc::new(AuditLog::default());
+        let api = AuditedApi::new(
+            Box::new(FakeApi {
+                releases: shared(vec![
+                    draft(1, &header, "2024-05-01T00:00:00Z"),
+                    draft(2, &header, "2024-05-02T00:00:00Z"),
+                ]),
+                ..FakeApi::default()
+            }),
+            "o/r",
+            Arc::clone(&log),
+        );
+
+        let releases = api.list_all_releases(MAX_PER_PAGE).unwrap();
+        let selection = select_draft_releases(&releases, &marker);
+        prune_extra_drafts(&api, &selection, &marker, false, "main").unwrap();
+        save_draft(&api, Some(2), &fields("v1.1.0", "new notes"), "main").unwrap();
+
+        let entries = log.entries();
+        let actions: Vec<&str> = entries.iter().map(|entry| entry.action).collect();
+        assert_eq!(actions, vec!["delete-release", "update-release"]);
+        assert_eq!(entries[0].release_id, Some(1));
+        assert_eq!(entries[1].tag.as_deref(), Some("v1.1.0"));
+        assert_eq!(
+            entries[1].body_before,
+            Some(assets::sha256_hex(header.as_bytes()))
+        );
+        assert_eq!(
+            entries[1].body_after,
+            Some(assets::sha256_hex(b"new notes"))
+        );
+        assert!(
+            log.to_json()
+                .unwrap()
+                .starts_with("[{\"action\":\"delete-release\"")
+        );
+    }
+
+    #[test]
+    fn keeps_extra_drafts_without_a_signature() {
+        let marker = release_marker("main", None, None, None);
+        let api = FakeApi {
+            releases: shared(vec![
+                draft(1, &format!("Pasted notes {marker}"), "2024-05-01T00:00:00Z"),
+                draft(2, &release_header(&marker), "2024-05-02T00:00:00Z"),
+            ]),
+            ..FakeApi::default()
+        };
+        let releases = api.list_all_releases(MAX_PER_PAGE).unwrap();
+        let selection = select_draft_releases(&releases, &marker);
+
+        prune_extra_drafts(&api, &selection, &marker, false, "main").unwrap();
+        assert!(api.calls().is_empty());
+
+        prune_extra_drafts(&api, &selection, &marker, true, "main").unwrap();
+        assert_eq!(api.calls(), vec!["delete release 1"]);
+    }
+
+    #[test]
+    fn keeps_extra_drafts_the_token_may_not_delete() {
+        let marker = release_marker("main", None, None, None);
+        let header = release_header(&marker);
+        let api = FakeApi {
+            releases: shared(vec![
+                draft(1, &header, "2024-05-01T00:00:00Z"),
+                draft(2, &header, "2024-05-02T00:00:00Z"),
+            ]),
+            forbid_deletes: true,
+            ..FakeApi::default()
+        };
+        let releases = api.list_all_releases(MAX_PER_PAGE).unwrap();
+        let selection = select_draft_releases(&releases, &marker);
+
+        prune_extra_drafts(&api, &selection, &marker, false, "main").unwrap();
+
+        assert_eq!(api.calls(), vec!["delete release 1"]);
+        assert_eq!(api.releases.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn recognizes_a_draft_that_is_already_current() {
+        let mut existing = draft(1, "notes", "2024-05-01T00:00:00Z");
+        existing.tag_name = "v1.1.0".to_string();
+        existing.name = Some("v1.1.0".to_string());
+
+        assert!(draft_is_current(&existing, &fields("v1.1.0", "notes")));
+        assert!(!draft_is_current(&existing, &fields("v1.1.0", "new notes")));
+        assert!(!draft_is_current(&existing, &fields("v1.2.0", "notes")));
+        existing.prerelease = true;
+        assert!(!draft_is_current(&existing, &fields("v1.1.0", "notes")));
+    }
+
+    #[test]
+    fn stops_listing_releases_early_only_when_duplicates_are_kept() {
+        let marker = release_marker("main", None, None, None);
+        let mut latest = draft(2, "", "2024-05-02T00:00:00Z");
+        latest.draft = false;
+        latest.published_at = Some("2024-05-02T00:00:00Z".to_string());
+        let mut older = latest.clone();
+        older.id = 1;
+        let api = FakeApi {
+            releases: shared(vec![
+                draft(3, &marker, "2024-05-03T00:00:00Z"),
+                latest,
+                older,
+            ]),
+            ..FakeApi::default()
+        };
+
+        let mut reported = Vec::new();
+        let listed =
+            list_scope_releases(&api, 1, &marker, Some("main"), None, true, &mut |latest| {
+                reported.push(latest.id)
+            })
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(reported, vec![2]);
+        let listed = list_scope_releases(
+            &api,
+            1,
+            "other marker",
+            Some("main"),
+            None,
+            true,
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(listed.len(), 3);
+        let mut reported = Vec::new();
+        let listed =
+            list_scope_releases(&api, 1, &marker, Some("main"), None, false, &mut |latest| {
+                reported.push(latest.id)
+            })
+            .unwrap();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(reported, vec![2]);
+    }
+
+    #[test]
+    fn prunes_a_duplicate_draft_on_a_later_page() {
+        let marker = release_marker("main", None, None, None);
+        let body = release_header(&marker);
+        let mut latest = draft(3, "", "2024-05-03T00:00:00Z");
+        latest.draft = false;
+        latest.published_at = Some("2024-05-03T00:00:00Z".to_string());
+        let api = FakeApi {
+            releases: shared(vec![
+                draft(4, &body, "2024-05-04T00:00:00Z"),
+                latest,
+                draft(1, &body, "2024-05-01T00:00:00Z"),
+            ]),
+            ..FakeApi::default()
+        };
+
+        let listed =
+            list_scope_releases(&api, 2, &marker, Some("main"), None, false, &mut |_| {}).unwrap();
+        let selection = select_draft_releases(&listed, &marker);
+        prune_extra_drafts(&api, &selection, &marker, false, "main").unwrap();
+
+        assert_eq!(selection.primary.map(|release| release.id), Some(4));
+        assert_eq!(api.calls(), vec!["delete release 1"]);
+    }
+
+    #[test]
+    fn backfills_an_existing_release_between_two_tags() {
+        let mut published = draft(1, "", "2024-05-01T00:00:00Z");
+        published.tag_name = "v1.1.0".to_string();
+        published.draft = false;
+        let api = FakeApi {
+            releases: shared(vec![published]),
+            pull_requests: vec![PullRequestInfo {
+                number: 4,
+                title: "Add widgets".to_string(),
+                ..PullRequestInfo::default()
+            }],
+            ..FakeApi::default()
+        };
+        let releases = api.list_all_releases(MAX_PER_PAGE).unwrap();
+        let mut backfill = Backfill {
+            from_tag: "v1.0.0",
+            to_tag: "v1.1.0",
+            tag_prefix: "v",
+            branch: "main",
+            per_page: MAX_PER_PAGE,
+            limits: FetchLimits::default(),
+            api: PullRequestApi::Rest,
+        };
+
+        let release = backfill_release(&api, &api, &releases, &backfill, None, "marker").unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec![
+                "list Rest pull requests into main since date of v1.0.0",
+                "update notes of release 1",
+            ]
+        );
+        let expected = format!("{}\n\nAdd widgets", release_header("marker"));
+        assert_eq!(release.body.as_deref(), Some(expected.as_str()));
+        assert!(!release.draft);
+        backfill.to_tag = "v9.9.9";
+        assert!(backfill_release(&api, &api, &releases, &backfill, None, "marker").is_err());
+    }
+
+    #[test]
+    fn leaves_a_published_release_holding_the_tag_alone() {
+        // The manifest wasn't bumped after v1.1.0 shipped, so the next push
+        // resolves the same tag with only the PRs merged since.
+        let marker = release_marker("main", None, None, None);
+        let published = |id: u64, tag: &str, created_at: &str| {
+            let mut release = draft(id, "shipped notes", created_at);
+            release.tag_name = tag.to_string();
+            release.draft = false;
+            release
+        };
+        let api = FakeApi {
+            releases: shared(vec![
+                draft(4, &release_header(&marker), "2024-05-04T00:00:00Z"),
+                published(3, "v1.2.0", "2024-05-03T00:00:00Z"),
+                published(1, "v1.1.0", "2024-05-01T00:00:00Z"),
+            ]),
+            ..FakeApi::default()
+        };
+
+        // The listing stops once the draft and the latest release turn up,
+        // before the older release holding the tag.
+        let listed =
+            list_scope_releases(&api, 1, &marker, Some("main"), None, true, &mut |_| {}).unwrap();
+        assert!(listed.iter().all(|release| release.tag_name != "v1.1.0"));
+
+        assert!(!tag_already_released(&api, "v1.3.0", "main").unwrap());
+        assert!(tag_already_released(&api, "v1.1.0", "main").unwrap());
+        assert_eq!(
+            api.calls(),
+            vec!["release tagged v1.3.0", "release tagged v1.1.0"]
+        );
+        let releases = api.releases.lock().unwrap();
+        assert_eq!(releases[2].body.as_deref(), Some("shipped notes"));
+    }
+
+    #[test]
+    fn keeps_pull_requests_in_the_milestone() {
+        let assigned = |number: u64, milestone: Option<&str>| PullRequestInfo {
+            number,
+            milestone: milestone.map(str::to_string),
+            ..PullRequestInfo::default()
+        };
+        let mut pull_requests = vec![
+            assigned(1, Some("v2.0")),
+            assigned(2, None),
+            assigned(3, Some("V2.0 ")),
+            assigned(4, Some("v2.1")),
+        ];
+
+        retain_milestone(&mut pull_requests, "v2.0");
+
+        let numbers: Vec<u64> = pull_requests
+            .iter()
+            .map(|pull_request| pull_request.number)
+            .collect();
+        assert_eq!(numbers, vec![1, 3]);
+    }
+
+    #[test]
+    fn looks_up_each_author_name_once() {
+        let api = FakeApi::default();
+        let author = |number: u64, login: &str| PullRequestInfo {
+            number,
+            author: login.to_string(),
+            ..PullRequestInfo::default()
+        };
+        let mut pull_requests = vec![
+            author(1, "ada"),
+            author(2, "unknown"),
+            author(3, "broken"),
+            author(4, "bob"),
+            author(5, "ada"),
+        ];
+        let mut names = HashMap::new();
+
+        fetch_author_names(&api, &mut pull_requests, &mut names).unwrap();
+
+        assert_eq!(api.calls(), vec!["user ada", "user broken"]);
+        assert_eq!(pull_requests[0].author_name.as_deref(), Some("ada name"));
+        assert_eq!(pull_requests[1].author_name, None);
+        assert_eq!(pull_requests[2].author_name, None);
+        assert_eq!(pull_requests[3].author_name, None);
+        assert_eq!(pull_requests[4].author_name.as_deref(), Some("ada name"));
+    }
+
+    #[test]
+    fn marks_authors_first_merged_pull_requests() {
+        let author = |number: u64, login: &str| PullRequestInfo {
+            number,
+            author: login.to_string(),
+            ..PullRequestInfo::default()
+        };
+        let api = FakeApi {
+            pull_requests: vec![author(1, "ada"), author(5, "ada"), author(6, "bob")],
+            ..FakeApi::default()
+        };
+        let mut pull_requests = vec![
+            author(5, "ada"),
+            author(6, "bob"),
+            author(7, "bob"),
+            author(8, "renovate[bot]"),
+        ];
+        let config = ReleaseConfig {
+            exclude_contributors: vec!["renovate[bot]".to_string()],
+            ..ReleaseConfig::default()
+        };
+
+        mark_first_contributions(&api, &mut pull_requests, &config).unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec!["first pull request by ada", "first pull request by bob"]
+        );
+        let firsts: Vec<bool> = pull_requests
+            .iter()
+            .map(|pull_request| pull_request.first_contribution)
+            .collect();
+        assert_eq!(firsts, vec![false, true, false, false]);
+    }
+
+    #[test]
+    fn marks_first_contributions_per_repository_and_degrades_on_errors() {
+        let author = |number: u64, login: &str, repository: &str| PullRequestInfo {
+            number,
+            author: login.to_string(),
+            repository: Some(repository.to_string()),
+            ..PullRequestInfo::default()
+        };
+        let api = FakeApi {
+            pull_requests: vec![author(1, "ada", "acme/api"), author(4, "ada", "acme/web")],
+            ..FakeApi::default()
+        };
+        let mut pull_requests = vec![
+            author(4, "ada", "acme/web"),
+            author(6, "ada", "acme/api"),
+            author(7, "failing", "acme/api"),
+            author(8, "bob", "acme/api"),
+        ];
+
+        mark_first_contributions(&api, &mut pull_requests, &ReleaseConfig::default()).unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec![
+                "first pull request by ada in acme/web",
+                "first pull request by ada in acme/api",
+                "first pull request by failing in acme/api",
+            ]
+        );
+        let firsts: Vec<bool> = pull_requests
+            .iter()
+            .map(|pull_request| pull_request.first_contribution)
+            .collect();
+        assert_eq!(firsts, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn explains_missing_release_permissions() {
+        let full = ReleaseAccess {
+            read: true,
+            write: true,
+        };
+        assert!(missing_permissions_message("o/r", full).is_none());
+
+        let read_only = missing_permissions_message(
+            "o/r",
+            ReleaseAccess {
+                read: true,
+                write: false,
+            },
+        )
+        .unwrap();
+        assert!(read_only.starts_with("The token cannot write releases in o/r"));
+        assert!(read_only.contains("permissions:\n  contents: write\n"));
+
+        let none = missing_permissions_message(
+            "o/r",
+            ReleaseAccess {
+                read: false,
+                write: false,
+            },
+        )
+        .unwrap();
+        assert!(none.starts_with("The token cannot read or write releases in o/r"));
+    }
+
+    #[test]
+    fn writes_a_draft_from_the_action_inputs() {
+        let dir = env::temp_dir().join(format!("breezy-draft-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("breezy.yml");
+        fs::write(
+            &config_path,
+            "change-template: '* $TITLE @$AUTHOR ($NUMBER)'\ncontributors-from: commits\n",
+        )
+        .unwrap();
+        // SAFETY: no other test reads or writes these variables, and std's
+        // environment accessors are serialised with each other.
+        unsafe {
+            for name in ["GITHUB_HEAD_REF", "GITHUB_SHA", "GITHUB_OUTPUT"] {
+                env::remove_var(name);
+            }
+            env::set_var("GITHUB_REF_NAME", "main");
+            env::set_var("INPUT_VERSION", "1.2.0");
+            env::set_var("INPUT_CONFIG_FILE", &config_path);
+        }
+        let mut published = draft(1, "shipped notes", "2024-05-01T00:00:00Z");
+        published.tag_name = "v1.1.0".to_string();
+        published.draft = false;
+        published.published_at = Some("2024-05-01T12:00:00Z".to_string());
+        let api = FakeApi {
+            releases: shared(vec![published]),
+            pull_requests: vec![PullRequestInfo {
+                number: 4,
+                title: "Add widgets".to_string(),
+                author: "ada".to_string(),
+                url: "https://github.com/o/r/pull/4".to_string(),
+                ..PullRequestInfo::default()
+            }],
+            ..FakeApi::default()
+        };
+        let calls = Arc::clone(&api.calls);
+        let releases = Arc::clone(&api.releases);
+        let clients = Clients {
+            source: Box::new(api),
+            target: None,
+            extra_sources: Vec::new(),
+            source_repository: "o/r".to_string(),
+            release_repository: "o/r".to_string(),
+            per_page: MAX_PER_PAGE,
+        };
+        let config = config::load_config(Some(config_path.display().to_string()), &dir).unwrap();
+
+        write_draft(&clients, config, &dir).unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "compare v1.1.0...main",
+                "release tagged v1.2.0",
+                "create release v1.2.0",
+            ]
+        );
+        let releases = releases.lock().unwrap();
+        let created = releases.last().unwrap();
+        let marker = release_marker("main", None, None, None);
+        let expected = format!(
+            "{}\n\n* Add widgets @ada (https://github.com/o/r/pull/4)",
+            release_header(&marker)
+        );
+        assert_eq!(created.body.as_deref(), Some(expected.as_str()));
+        assert_eq!(created.name.as_deref(), Some("v1.2.0 (main)"));
+        assert!(created.draft);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn searches_when_the_compare_base_is_gone() {
+        let api = FakeApi::default();
+        let window = |compare_base| PullRequestWindow {
+            since: Some("2024-05-01T00:00:00Z"),
+            until: None,
+            compare_base,
+        };
+        let fetch = |compare_base| {
+            fetch_pull_requests(
+                &api,
+                "main",
+                window(compare_base),
+                MAX_PER_PAGE,
+                FetchLimits::default(),
+                PullRequestApi::Rest,
+            )
+        };
+
+        fetch(Some("v1.0.0")).unwrap();
+        fetch(Some("deleted")).unwrap();
+
+        assert_eq!(
+            api.calls(),
+            vec![
+                "compare v1.0.0...main",
+                "compare deleted...main",
+                "list Rest pull requests into main since 2024-05-01T00:00:00Z",
+            ]
+        );
+    }
+}
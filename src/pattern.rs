@@ -0,0 +1,756 @@
+use anyhow::{Result, bail};
+use std::cell::Cell;
+
+const MAX_PROGRAM_SIZE: usize = 5_000;
+const MAX_REPEAT: u32 = 1_000;
+
+thread_local! {
+    /// Visited bitset reused across matches, since replacers run once per entry.
+    static VISITED: Cell<Vec<u64>> = const { Cell::new(Vec::new()) };
+}
+
+/// A small backtracking regular expression engine for config-supplied patterns.
+///
+/// Supports literals, `.`, classes (`[a-z]`, `[^"]`), `\d \w \s` (and negations),
+/// `\b`, groups (`(...)`, `(?:...)`), alternation, and greedy or lazy `* + ? {n,m}`.
+/// `^` and `$` match at line boundaries, which suits scanning manifests for a line.
+/// Each (instruction, position) pair is explored at most once, so matching is
+/// linear in the text length for a given pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    program: Vec<Inst>,
+    groups: usize,
+}
+
+pub struct Captures<'t> {
+    text: &'t str,
+    slots: Vec<Option<usize>>,
+}
+
+impl<'t> Captures<'t> {
+    /// Returns the text of group `index`; group 0 is the whole match.
+    pub fn get(&self, index: usize) -> Option<&'t str> {
+        let (start, end) = self.range(index)?;
+        Some(&self.text[start..end])
+    }
+
+    pub fn range(&self, index: usize) -> Option<(usize, usize)> {
+        match (self.slots.get(index * 2)?, self.slots.get(index * 2 + 1)?) {
+            (Some(start), Some(end)) => Some((*start, *end)),
+            _ => None,
+        }
+    }
+}
+
+fn expand_replacement(captures: &Captures, replacement: &str, output: &mut String) {
+    let mut rest = replacement;
+    while let Some(position) = rest.find('$') {
+        output.push_str(&rest[..position]);
+        rest = &rest[position + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('&') {
+            output.push_str(captures.get(0).unwrap_or(""));
+            rest = after;
+        } else if let Some((digits, after)) = rest
+            .strip_prefix('{')
+            .and_then(|braced| braced.split_once('}'))
+            .filter(|(digits, _)| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+        {
+            output.push_str(
+                digits
+                    .parse()
+                    .ok()
+                    .and_then(|index| captures.get(index))
+                    .unwrap_or(""),
+            );
+            rest = after;
+        } else if let Some(digit) = rest.chars().next().and_then(|c| c.to_digit(10)) {
+            output.push_str(captures.get(digit as usize).unwrap_or(""));
+            rest = &rest[1..];
+        } else {
+            output.push('$');
+        }
+    }
+    output.push_str(rest);
+}
+
+#[derive(Debug, Clone)]
+struct CharClass {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl CharClass {
+    fn new(ranges: &[(char, char)], negated: bool) -> Self {
+        Self {
+            ranges: ranges.to_vec(),
+            negated,
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|(low, high)| *low <= c && c <= *high)
+            != self.negated
+    }
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(CharClass),
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(CharClass),
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            position: 0,
+            groups: 0,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.position < parser.chars.len() {
+            bail!("Unmatched ) in pattern {pattern}");
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.push(Inst::Save(0));
+        compiler.emit(&node)?;
+        compiler.push(Inst::Save(1));
+        compiler.push(Inst::Match);
+        if compiler.program.len() > MAX_PROGRAM_SIZE {
+            bail!("Pattern {pattern} is too large.");
+        }
+
+        Ok(Self {
+            program: compiler.program,
+            groups: parser.groups,
+        })
+    }
+
+    /// Number of capturing groups, not counting the whole match.
+    pub fn group_count(&self) -> usize {
+        self.groups
+    }
+
+    /// Returns the leftmost match in `text`.
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
+        self.with_visited(text, |visited| self.captures_from(text, 0, visited))
+    }
+
+    /// Replaces every non-overlapping match in `text`. In `replacement`, `$1` or
+    /// `${1}` inserts a group, `$&` the whole match, and `$$` a literal `$`.
+    /// Only one digit is read after `$`, so `$10` is group 1 followed by `0`;
+    /// write `${10}` for group 10.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        self.with_visited(text, |visited| {
+            self.replace_from(text, replacement, visited)
+        })
+    }
+
+    fn replace_from(&self, text: &str, replacement: &str, visited: &mut [u64]) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut from = 0;
+        while let Some(captures) = self.captures_from(text, from, visited) {
+            let Some((start, end)) = captures.range(0) else {
+                break;
+            };
+            output.push_str(&text[copied..start]);
+            expand_replacement(&captures, replacement, &mut output);
+            copied = end;
+            from = if end > start {
+                end
+            } else {
+                // Step past an empty match so it isn't found again.
+                match text[end..].chars().next() {
+                    Some(c) => end + c.len_utf8(),
+                    None => break,
+                }
+            };
+        }
+        output.push_str(&text[copied..]);
+        output
+    }
+
+    /// Lends `search` this thread's visited bitset, sized for every instruction
+    /// at every position in `text`. Searches clear the states they use.
+    fn with_visited<T>(&self, text: &str, search: impl FnOnce(&mut [u64]) -> T) -> T {
+        VISITED.with(|cell| {
+            let mut visited = cell.take();
+            visited.resize((self.program.len() * (text.len() + 1)).div_ceil(64), 0);
+            let result = search(&mut visited);
+            cell.set(visited);
+            result
+        })
+    }
+
+    /// Returns the leftmost match starting at or after `from`, marking the
+    /// states it explores in `visited`. States are laid out by position, so
+    /// only those from `from` on are cleared: a search never goes back.
+    fn captures_from<'t>(
+        &self,
+        text: &'t str,
+        from: usize,
+        visited: &mut [u64],
+    ) -> Option<Captures<'t>> {
+        let first = from * self.program.len();
+        if !first.is_multiple_of(64) {
+            visited[first / 64] &= (1 << (first % 64)) - 1;
+        }
+        visited[first.div_ceil(64)..].fill(0);
+        let mut slots = vec![None; (self.groups + 1) * 2];
+        let starts = text[from..]
+            .char_indices()
+            .map(|(index, _)| from + index)
+            .chain(std::iter::once(text.len()));
+        for start in starts {
+            if self.run(text, start, &mut slots, visited) {
+                return Some(Captures { text, slots });
+            }
+        }
+        None
+    }
+
+    fn run(
+        &self,
+        text: &str,
+        start: usize,
+        slots: &mut [Option<usize>],
+        visited: &mut [u64],
+    ) -> bool {
+        enum Job {
+            Explore(usize, usize),
+            Restore(usize, Option<usize>),
+        }
+
+        let width = self.program.len();
+        let mut jobs = vec![Job::Explore(0, start)];
+        while let Some(job) = jobs.pop() {
+            let (mut pc, mut position) = match job {
+                Job::Restore(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                }
+                Job::Explore(pc, position) => (pc, position),
+            };
+            loop {
+                let key = position * width + pc;
+                if visited[key / 64] & (1 << (key % 64)) != 0 {
+                    break;
+                }
+                visited[key / 64] |= 1 << (key % 64);
+
+                let next = text[position..].chars().next();
+                match &self.program[pc] {
+                    Inst::Char(expected) => match next {
+                        Some(c) if c == *expected => {
+                            position += c.len_utf8();
+                            pc += 1;
+                        }
+                        _ => break,
+                    },
+                    Inst::Any => match next {
+                        Some(c) if c != '\n' => {
+                            position += c.len_utf8();
+                            pc += 1;
+                        }
+                        _ => break,
+                    },
+                    Inst::Class(class) => match next {
+                        Some(c) if class.matches(c) => {
+                            position += c.len_utf8();
+                            pc += 1;
+                        }
+                        _ => break,
+                    },
+                    Inst::LineStart => {
+                        if position == 0 || text[..position].ends_with('\n') {
+                            pc += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    Inst::LineEnd => {
+                        let rest = &text[position..];
+                        if rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n") {
+                            pc += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    Inst::WordBoundary => {
+                        let is_word =
+                            |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                        if is_word(text[..position].chars().next_back()) != is_word(next) {
+                            pc += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    Inst::Split(first, second) => {
+                        jobs.push(Job::Explore(*second, position));
+                        pc = *first;
+                    }
+                    Inst::Jump(target) => pc = *target,
+                    Inst::Save(slot) => {
+                        jobs.push(Job::Restore(*slot, slots[*slot]));
+                        slots[*slot] = Some(position);
+                        pc += 1;
+                    }
+                    Inst::Match => return true,
+                }
+            }
+        }
+        false
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Node::Alternate(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.remove(0),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node> {
+        let Some(c) = self.next() else {
+            bail!("Unexpected end of pattern.");
+        };
+        Ok(match c {
+            '(' => {
+                let index = if self.chars[self.position..].starts_with(&['?', ':']) {
+                    self.position += 2;
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let inner = self.parse_alternation()?;
+                if self.next() != Some(')') {
+                    bail!("Unclosed ( in pattern.");
+                }
+                Node::Group(Box::new(inner), index)
+            }
+            '[' => Node::Class(self.parse_class()?),
+            '.' => Node::Any,
+            '^' => Node::LineStart,
+            '$' => Node::LineEnd,
+            '*' | '+' | '?' => bail!("Nothing to repeat before {c} in pattern."),
+            '\\' => self.parse_escape()?,
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_escape(&mut self) -> Result<Node> {
+        let Some(c) = self.next() else {
+            bail!("Pattern ends with a trailing backslash.");
+        };
+        Ok(match c {
+            'd' => Node::Class(CharClass::new(DIGIT, false)),
+            'D' => Node::Class(CharClass::new(DIGIT, true)),
+            'w' => Node::Class(CharClass::new(WORD, false)),
+            'W' => Node::Class(CharClass::new(WORD, true)),
+            's' => Node::Class(CharClass::new(SPACE, false)),
+            'S' => Node::Class(CharClass::new(SPACE, true)),
+            'b' => Node::WordBoundary,
+            c => Node::Char(escaped_char(c)),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<CharClass> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(c) = self.next() else {
+                bail!("Unclosed [ in pattern.");
+            };
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let Some(escaped) = self.next() else {
+                    bail!("Unclosed [ in pattern.");
+                };
+                match escaped {
+                    'd' => {
+                        ranges.extend_from_slice(DIGIT);
+                        continue;
+                    }
+                    'w' => {
+                        ranges.extend_from_slice(WORD);
+                        continue;
+                    }
+                    's' => {
+                        ranges.extend_from_slice(SPACE);
+                        continue;
+                    }
+                    'D' | 'W' | 'S' => bail!("\\{escaped} is not supported inside [...]."),
+                    other => escaped_char(other),
+                }
+            } else {
+                c
+            };
+            let is_range = self.peek() == Some('-')
+                && self.chars.get(self.position + 1).is_some_and(|c| *c != ']');
+            if is_range {
+                self.position += 1;
+                let high = match self.next() {
+                    Some('\\') => self.next().map(escaped_char),
+                    other => other,
+                };
+                let Some(high) = high.filter(|high| *high >= low) else {
+                    bail!("Invalid range in [...].");
+                };
+                ranges.push((low, high));
+            } else {
+                ranges.push((low, low));
+            }
+        }
+        Ok(CharClass { ranges, negated })
+    }
+
+    fn parse_quantifier(&mut self, node: Node) -> Result<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_braces() {
+                Some((min, max, width)) => {
+                    self.position += width - 1;
+                    (min, max)
+                }
+                None => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+        self.position += 1;
+        if max.is_some_and(|max| max < min) || min > MAX_REPEAT || max.unwrap_or(0) > MAX_REPEAT {
+            bail!("Invalid repetition count in pattern.");
+        }
+        let greedy = self.peek() != Some('?');
+        if !greedy {
+            self.position += 1;
+        }
+        if matches!(node, Node::LineStart | Node::LineEnd | Node::WordBoundary) {
+            bail!("Nothing to repeat in pattern.");
+        }
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` at the current position, returning the
+    /// bounds and the number of characters consumed. Anything else is a literal `{`.
+    fn parse_braces(&self) -> Option<(u32, Option<u32>, usize)> {
+        let rest: String = self.chars[self.position..].iter().collect();
+        let end = rest.find('}')?;
+        let inner = &rest[1..end];
+        let width = rest[..=end].chars().count();
+        let (min, max) = match inner.split_once(',') {
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+            None => {
+                let count = inner.parse().ok()?;
+                (count, Some(count))
+            }
+        };
+        Some((min, max, width))
+    }
+}
+
+fn escaped_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        other => other,
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> usize {
+        self.program.push(inst);
+        self.program.len() - 1
+    }
+
+    fn emit(&mut self, node: &Node) -> Result<()> {
+        if self.program.len() > MAX_PROGRAM_SIZE {
+            bail!("Pattern is too large.");
+        }
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => {
+                self.push(Inst::Char(*c));
+            }
+            Node::Any => {
+                self.push(Inst::Any);
+            }
+            Node::Class(class) => {
+                self.push(Inst::Class(class.clone()));
+            }
+            Node::LineStart => {
+                self.push(Inst::LineStart);
+            }
+            Node::LineEnd => {
+                self.push(Inst::LineEnd);
+            }
+            Node::WordBoundary => {
+                self.push(Inst::WordBoundary);
+            }
+            Node::Group(inner, index) => {
+                if let Some(index) = index {
+                    self.push(Inst::Save(index * 2));
+                }
+                self.emit(inner)?;
+                if let Some(index) = index {
+                    self.push(Inst::Save(index * 2 + 1));
+                }
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.emit(node)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                let mut jumps = Vec::new();
+                for (index, branch) in branches.iter().enumerate() {
+                    if index + 1 == branches.len() {
+                        self.emit(branch)?;
+                        break;
+                    }
+                    let split = self.push(Inst::Split(0, 0));
+                    self.emit(branch)?;
+                    jumps.push(self.push(Inst::Jump(0)));
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0));
+                        self.emit(node)?;
+                        self.push(Inst::Jump(split));
+                        let end = self.program.len();
+                        self.program[split] = self.split(*greedy, split + 1, end);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0)));
+                            self.emit(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = self.split(*greedy, split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn split(&self, greedy: bool, body: usize, end: usize) -> Inst {
+        if greedy {
+            Inst::Split(body, end)
+        } else {
+            Inst::Split(end, body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(pattern: &str, text: &str, index: usize) -> Option<String> {
+        Pattern::new(pattern)
+            .unwrap()
+            .captures(text)
+            .and_then(|captures| captures.get(index).map(str::to_string))
+    }
+
+    #[test]
+    fn captures_version_strings() {
+        let init = "\"\"\"Package.\"\"\"\n\n__version__ = \"1.4.0\"\n";
+        let makefile = "NAME := app\nVERSION ?= 0.8.2-rc.1\n";
+
+        assert_eq!(
+            group(r#"__version__\s*=\s*["']([^"']+)["']"#, init, 1).as_deref(),
+            Some("1.4.0")
+        );
+        assert_eq!(
+            group(r"^VERSION\s*\??=\s*(\S+)$", makefile, 1).as_deref(),
+            Some("0.8.2-rc.1")
+        );
+        assert_eq!(
+            group(r"(\d+)\.(\d+)\.(\d+)", "v10.2.33", 3).as_deref(),
+            Some("33")
+        );
+    }
+
+    #[test]
+    fn supports_alternation_and_repetition() {
+        assert_eq!(group("a(b|cd)+e", "xacdbe", 1).as_deref(), Some("b"));
+        assert_eq!(group("x{2,3}", "xxxx", 0).as_deref(), Some("xxx"));
+        assert_eq!(group("x{2,3}?", "xxxx", 0).as_deref(), Some("xx"));
+        assert_eq!(group("<.+?>", "<a><b>", 0).as_deref(), Some("<a>"));
+        assert_eq!(group(r"\bcat\b", "concat cat", 0).as_deref(), Some("cat"));
+        assert_eq!(group("a{,", "a{,", 0).as_deref(), Some("a{,"));
+        assert_eq!(group("^abc$", "xabc", 0), None);
+    }
+
+    #[test]
+    fn counts_capturing_groups() {
+        assert_eq!(Pattern::new(r"(?:v)?(\d+)").unwrap().group_count(), 1);
+        assert_eq!(Pattern::new(r"(a)(b(c))").unwrap().group_count(), 3);
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(Pattern::new("(abc").is_err());
+        assert!(Pattern::new("abc)").is_err());
+        assert!(Pattern::new("[abc").is_err());
+        assert!(Pattern::new("*a").is_err());
+        assert!(Pattern::new("a{3,1}").is_err());
+    }
+
+    #[test]
+    fn avoids_exponential_backtracking() {
+        let text = "a".repeat(5_000);
+        assert!(Pattern::new("(a*)*b").unwrap().captures(&text).is_none());
+    }
+
+    #[test]
+    fn replaces_every_match() {
+        let ticket = Pattern::new(r"\b(JIRA)-(\d+)").unwrap();
+        assert_eq!(
+            ticket.replace_all(
+                "Fix JIRA-12 and JIRA-7",
+                "[$1-$2](https://jira.example.com/browse/${1}-${2})"
+            ),
+            "Fix [JIRA-12](https://jira.example.com/browse/JIRA-12) and [JIRA-7](https://jira.example.com/browse/JIRA-7)"
+        );
+        assert_eq!(
+            Pattern::new(r"^\[\w+\] ")
+                .unwrap()
+                .replace_all("[ci] One\n[deps] Two", ""),
+            "One\nTwo"
+        );
+        assert_eq!(Pattern::new("x*").unwrap().replace_all("ab", "-"), "-a-b-");
+        assert_eq!(
+            Pattern::new(r"\d+").unwrap().replace_all("v12", "$$$&$9"),
+            "v$12"
+        );
+        assert_eq!(
+            Pattern::new("(a)").unwrap().replace_all("a", "$10 ${1}0"),
+            "a0 a0"
+        );
+        // Later matches overlap states the earlier ones explored.
+        assert_eq!(
+            Pattern::new("a+b|a").unwrap().replace_all("aab aa ab", "X"),
+            "X XX X"
+        );
+    }
+}
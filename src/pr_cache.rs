@@ -0,0 +1,290 @@
+use crate::app_auth::parse_timestamp;
+use crate::github::format_timestamp;
+use crate::release_notes::PullRequestInfo;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the stored shape changes, so an old file is ignored rather
+/// than half-read.
+const CACHE_VERSION: u32 = 2;
+/// How far before the cursor a resumed search starts again. Search can take
+/// a while to index a merge, so a PR merged just before the last run may only
+/// turn up now.
+const RESUME_OVERLAP_SECS: u64 = 3_600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedWindow {
+    /// Start of the window the PRs were collected for; a new previous release
+    /// starts a new window.
+    pub since: Option<String>,
+    /// Latest merge time seen, from which the next run resumes.
+    pub cursor: Option<String>,
+    /// Newest commit compared, from which the next run compares onwards. Only
+    /// set for windows that started at a tag.
+    pub head: Option<String>,
+    pub pull_requests: Vec<PullRequestInfo>,
+}
+
+impl CachedWindow {
+    /// Where a resumed search starts: the cursor, less an overlap for PRs the
+    /// search index hadn't caught up with yet. The overlap's PRs come back
+    /// again and replace their cached copies.
+    pub fn search_since(&self) -> Option<String> {
+        let cursor = self.cursor.as_deref()?;
+        Some(match parse_timestamp(cursor) {
+            Some(seconds) => format_timestamp(seconds.saturating_sub(RESUME_OVERLAP_SECS)),
+            None => cursor.to_string(),
+        })
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    /// Keyed by draft marker, which names the branch and scope.
+    windows: BTreeMap<String, CachedWindow>,
+}
+
+/// Merged PRs found by earlier runs, kept in one JSON file (for the
+/// `pr-cache-file` input) so each run only asks for PRs merged since the last.
+pub struct PullRequestCache {
+    path: PathBuf,
+    file: CacheFile,
+}
+
+impl PullRequestCache {
+    /// Opens the cache at `path`. A missing, unreadable or corrupt file starts
+    /// empty.
+    pub fn load(path: &Path) -> Self {
+        let file = match fs::read_to_string(path) {
+            Ok(content) => match serde_json::from_str::<CacheFile>(&content) {
+                Ok(file) if file.version == CACHE_VERSION => file,
+                Ok(_) => {
+                    println!(
+                        "Ignoring pull request cache {} written by another breezy version",
+                        path.display()
+                    );
+                    CacheFile::default()
+                }
+                // Older versions may not parse as this one's shape at all.
+                Err(error) => {
+                    match serde_json::from_str::<serde_json::Value>(&content)
+                        .ok()
+                        .and_then(|value| value.get("version")?.as_u64())
+                    {
+                        Some(version) if version != u64::from(CACHE_VERSION) => println!(
+                            "Ignoring pull request cache {} written by another breezy version",
+                            path.display()
+                        ),
+                        _ => println!(
+                            "Ignoring unreadable pull request cache {}: {error}",
+                            path.display()
+                        ),
+                    }
+                    CacheFile::default()
+                }
+            },
+            Err(_) => CacheFile::default(),
+        };
+        Self {
+            path: path.to_path_buf(),
+            file,
+        }
+    }
+
+    /// The window cached for `marker`, if it starts at `since`.
+    pub fn resume(&self, marker: &str, since: Option<&str>) -> Option<&CachedWindow> {
+        self.file
+            .windows
+            .get(marker)
+            .filter(|window| window.since.as_deref() == since)
+    }
+
+    /// Records every PR in the window starting at `since`, compared up to
+    /// `head` if it was, replacing whatever was cached for `marker`.
+    pub fn store(
+        &mut self,
+        marker: &str,
+        since: Option<&str>,
+        head: Option<&str>,
+        pull_requests: &[PullRequestInfo],
+    ) -> Result<()> {
+        let cursor = pull_requests
+            .iter()
+            .filter_map(|pull_request| pull_request.merged_at.clone())
+            .max()
+            .or_else(|| since.map(str::to_string));
+        self.file.version = CACHE_VERSION;
+        self.file.windows.insert(
+            marker.to_string(),
+            CachedWindow {
+                since: since.map(str::to_string),
+                cursor,
+                head: head.map(str::to_string),
+                pull_requests: pull_requests.to_vec(),
+            },
+        );
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory {}", parent.display())
+            })?;
+        }
+        let content = serde_json::to_string(&self.file)?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write pull request cache {}", self.path.display()))
+    }
+}
+
+/// Returns `fresh` followed by the `cached` PRs it doesn't repeat. A resumed
+/// search overlaps the time before the cursor, so the same PR can come back. PRs are
+/// matched by repository as well as number, since aggregated `repositories`
+/// reuse numbers.
+pub fn merge_pull_requests(
+    fresh: Vec<PullRequestInfo>,
+    cached: &[PullRequestInfo],
+) -> Vec<PullRequestInfo> {
+    let key =
+        |pull_request: &PullRequestInfo| (pull_request.repository.clone(), pull_request.number);
+    let seen: HashSet<(Option<String>, u64)> = fresh.iter().map(key).collect();
+    let mut merged = fresh;
+    merged.extend(
+        cached
+            .iter()
+            .filter(|pull_request| !seen.contains(&key(pull_request)))
+            .cloned(),
+    );
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull_request(number: u64, merged_at: &str) -> PullRequestInfo {
+        PullRequestInfo {
+            number,
+            title: format!("PR {number}"),
+            merged_at: Some(merged_at.to_string()),
+            ..PullRequestInfo::default()
+        }
+    }
+
+    #[test]
+    fn resumes_only_the_same_window() {
+        let dir = std::env::temp_dir().join(format!("breezy-pr-cache-{}", std::process::id()));
+        let path = dir.join("prs.json");
+        let mut cache = PullRequestCache::load(&path);
+        assert!(cache.resume("marker", None).is_none());
+
+        cache
+            .store(
+                "marker",
+                Some("2024-05-01T00:00:00Z"),
+                Some("abc123"),
+                &[
+                    pull_request(1, "2024-05-02T00:00:00Z"),
+                    pull_request(2, "2024-05-03T00:00:00Z"),
+                ],
+            )
+            .unwrap();
+
+        let reloaded = PullRequestCache::load(&path);
+        let window = reloaded
+            .resume("marker", Some("2024-05-01T00:00:00Z"))
+            .unwrap();
+        assert_eq!(window.cursor.as_deref(), Some("2024-05-03T00:00:00Z"));
+        assert_eq!(window.head.as_deref(), Some("abc123"));
+        assert_eq!(
+            window.search_since().as_deref(),
+            Some("2024-05-02T23:00:00Z")
+        );
+        assert_eq!(window.pull_requests.len(), 2);
+        assert!(
+            reloaded
+                .resume("marker", Some("2024-06-01T00:00:00Z"))
+                .is_none()
+        );
+        assert!(
+            reloaded
+                .resume("other", Some("2024-05-01T00:00:00Z"))
+                .is_none()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn merges_fresh_pull_requests_over_cached_ones() {
+        let mut updated = pull_request(2, "2024-05-03T00:00:00Z");
+        updated.title = "Renamed".to_string();
+        let merged = merge_pull_requests(
+            vec![pull_request(3, "2024-05-04T00:00:00Z"), updated],
+            &[
+                pull_request(1, "2024-05-02T00:00:00Z"),
+                pull_request(2, "2024-05-03T00:00:00Z"),
+            ],
+        );
+
+        let numbers: Vec<u64> = merged
+            .iter()
+            .map(|pull_request| pull_request.number)
+            .collect();
+        assert_eq!(numbers, vec![3, 2, 1]);
+        assert_eq!(merged[1].title, "Renamed");
+    }
+
+    #[test]
+    fn keeps_same_numbered_pull_requests_from_other_repositories() {
+        let in_repository = |repository: &str, merged_at: &str| PullRequestInfo {
+            repository: Some(repository.to_string()),
+            ..pull_request(7, merged_at)
+        };
+        let merged = merge_pull_requests(
+            vec![in_repository("acme/api", "2024-05-04T00:00:00Z")],
+            &[
+                in_repository("acme/api", "2024-05-03T00:00:00Z"),
+                in_repository("acme/web", "2024-05-02T00:00:00Z"),
+            ],
+        );
+
+        let keys: Vec<(Option<&str>, &str)> = merged
+            .iter()
+            .map(|pull_request| {
+                (
+                    pull_request.repository.as_deref(),
+                    pull_request.merged_at.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (Some("acme/api"), "2024-05-04T00:00:00Z"),
+                (Some("acme/web"), "2024-05-02T00:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn starts_empty_from_corrupt_or_outdated_files() {
+        let dir = std::env::temp_dir().join(format!("breezy-pr-cache-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("prs.json");
+        for content in [
+            "{not json",
+            r#"{"version": 1, "windows": {"marker": {"since": null}}}"#,
+        ] {
+            fs::write(&path, content).unwrap();
+            assert!(
+                PullRequestCache::load(&path)
+                    .resume("marker", None)
+                    .is_none()
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
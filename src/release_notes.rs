@@ -1,67 +1,759 @@
-use crate::config::ReleaseConfig;
+use crate::config::{
+    CategorizeBy, ReleaseCategory, ReleaseConfig, SortBy, SortDirection, strip_variation_selectors,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashSet;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PullRequestInfo {
     pub number: u64,
     pub title: String,
+    pub body: Option<String>,
     pub author: String,
     pub labels: Vec<String>,
     pub url: String,
     pub merged_at: Option<String>,
+    /// Logins of everyone who reviewed, excluding the author.
+    pub reviewers: Vec<String>,
+    /// Names from `Co-authored-by:` trailers in the description.
+    pub co_authors: Vec<String>,
+    /// Issues GitHub will close, or closed, when the PR merged.
+    pub closing_issues: Vec<IssueReference>,
+    /// `owner/repo` the PR was merged in, set when notes span several
+    /// `repositories`.
+    pub repository: Option<String>,
+    /// The author's profile name, looked up when a template uses `$AUTHOR_NAME`.
+    pub author_name: Option<String>,
+    /// Title of the milestone the PR is assigned to.
+    pub milestone: Option<String>,
+    /// Paths the PR changed, up to the first hundred. Only read by
+    /// `pull-request-api: graphql`.
+    pub files: Vec<String>,
+    /// Whether this is the author's first merged PR in the repository, looked
+    /// up when the notes show new contributors.
+    pub first_contribution: bool,
 }
 
-pub fn release_marker(branch: &str, directory: Option<&str>) -> String {
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueReference {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+}
+
+/// A commit on the branch that no pull request is associated with, e.g. a
+/// hotfix pushed straight to the branch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirectCommit {
+    pub sha: String,
+    pub message: String,
+    /// Login of the GitHub account the author email belongs to, if any.
+    pub author: Option<String>,
+    pub url: String,
+}
+
+/// What the notes show beyond the pull requests: the git history since the
+/// previous release and the tags and version bounding it.
+#[derive(Clone, Debug, Default)]
+pub struct CommitHistory {
+    /// Commit authors credited in `$CONTRIBUTORS` alongside PR authors.
+    pub authors: Vec<String>,
+    /// Listed under "Direct Commits" with `direct-commits`.
+    pub direct_commits: Vec<DirectCommit>,
+    /// Tag of the release the notes are for, shown as `$TAG`.
+    pub tag: String,
+    /// Version being released, shown as `$RESOLVED_VERSION`.
+    pub resolved_version: String,
+    /// Tag of the previous release, shown as `$PREVIOUS_TAG`.
+    pub previous_tag: Option<String>,
+    /// Web address of the repository, for the Full Changelog compare link.
+    pub repository_url: Option<String>,
+}
+
+/// Whether the config renders closing issues, which cost a lookup per PR
+/// outside the GraphQL API.
+pub fn uses_closing_issues(config: &ReleaseConfig) -> bool {
+    config.change_template.contains("$ISSUES")
+        || config
+            .template
+            .as_deref()
+            .is_some_and(|template| template.contains("$CLOSED_ISSUES"))
+}
+
+/// Whether the notes show new contributors, so their first PRs must be looked up.
+pub fn uses_new_contributors(config: &ReleaseConfig) -> bool {
+    config.new_contributors
+        || config
+            .template
+            .as_deref()
+            .is_some_and(|template| template.contains("$NEW_CONTRIBUTORS"))
+}
+
+/// Whether `login` is listed in `exclude-contributors`, compared
+/// case-insensitively.
+pub fn is_excluded_contributor(config: &ReleaseConfig, login: &str) -> bool {
+    config
+        .exclude_contributors
+        .iter()
+        .any(|excluded| excluded.eq_ignore_ascii_case(login))
+}
+
+/// The `New Contributors` section, one line per author whose first merged PR
+/// is among `pull_requests`, like GitHub's generated notes. Empty when there
+/// are none.
+fn new_contributors_section(pull_requests: &[PullRequestInfo], config: &ReleaseConfig) -> String {
+    let mut firsts: Vec<&PullRequestInfo> = pull_requests
+        .iter()
+        .filter(|pull_request| {
+            pull_request.first_contribution
+                && !is_excluded_contributor(config, &pull_request.author)
+        })
+        .collect();
+    if firsts.is_empty() {
+        return String::new();
+    }
+    firsts.sort_by_key(|pull_request| pull_request.number);
+    let mut notes = NotesWriter::default();
+    notes.line("## New Contributors");
+    for pull_request in firsts {
+        notes.line(&format!(
+            "* @{} made their first contribution in {}",
+            pull_request.author, pull_request.url
+        ));
+    }
+    notes.finish()
+}
+
+/// `@login` for every PR and commit author, alphabetically and without repeats.
+fn contributors_list(
+    pull_requests: &[PullRequestInfo],
+    commit_authors: &[String],
+    config: &ReleaseConfig,
+) -> String {
+    let mut logins: Vec<&str> = pull_requests
+        .iter()
+        .map(|pull_request| pull_request.author.as_str())
+        .chain(commit_authors.iter().map(String::as_str))
+        .filter(|login| {
+            !login.is_empty() && *login != "unknown" && !is_excluded_contributor(config, login)
+        })
+        .collect();
+    logins.sort_by_key(|login| login.to_lowercase());
+    logins.dedup_by(|left, right| left.eq_ignore_ascii_case(right));
+    let mentions: Vec<String> = logins.iter().map(|login| format!("@{login}")).collect();
+    mentions.join(", ")
+}
+
+/// One `- title (url)` line per issue closed by any of the PRs, by number.
+fn closed_issues_list(pull_requests: &[PullRequestInfo]) -> String {
+    let mut issues: Vec<&IssueReference> = pull_requests
+        .iter()
+        .flat_map(|pull_request| &pull_request.closing_issues)
+        .collect();
+    issues.sort_by_key(|issue| issue.number);
+    issues.dedup_by_key(|issue| issue.number);
+    let mut notes = NotesWriter::default();
+    for issue in issues {
+        notes.line(&format!("- {} ({})", issue.title, issue.url));
+    }
+    notes.finish()
+}
+
+/// Reads the names from `Co-authored-by: Name <email>` trailers, in order and
+/// without repeats.
+pub fn parse_co_authors(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in body.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case("co-authored-by") {
+            continue;
+        }
+        let name = value.split('<').next().unwrap_or("").trim();
+        if !name.is_empty() && !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Builds the hidden marker identifying a draft's scope. The source repository is
+/// only included when drafts are kept in a different repository.
+pub fn release_marker(
+    branch: &str,
+    directory: Option<&str>,
+    member: Option<&str>,
+    repository: Option<&str>,
+) -> String {
+    let mut scope = String::new();
+    if let Some(repository) = repository.filter(|value| !value.trim().is_empty()) {
+        scope.push_str(&format!("repo={repository} "));
+    }
+    scope.push_str(&format!("branch={branch}"));
     if let Some(directory) = directory.filter(|value| !value.trim().is_empty()) {
-        return format!("<!-- breezy:branch={branch} directory={directory} -->");
+        scope.push_str(&format!(" directory={directory}"));
+    }
+    if let Some(member) = member.filter(|value| !value.trim().is_empty()) {
+        scope.push_str(&format!(" member={member}"));
     }
-    format!("<!-- breezy:branch={branch} -->")
+    format!("<!-- breezy:{scope} -->")
 }
 
-fn sort_by_merge_date(pull_requests: &[PullRequestInfo]) -> Vec<PullRequestInfo> {
-    let mut ordered = pull_requests.to_vec();
+/// Version of the signature format written by [`release_signature`].
+pub const SIGNATURE_SCHEMA: u32 = 1;
+
+const SIGNATURE_PREFIX: &str = "<!-- breezy-signature ";
+
+/// The scope part of a marker made by [`release_marker`].
+fn marker_scope(marker: &str) -> &str {
+    marker
+        .strip_prefix("<!-- breezy:")
+        .and_then(|rest| rest.strip_suffix(" -->"))
+        .unwrap_or(marker)
+}
+
+/// Builds the signature breezy writes under its marker, naming the tool, the
+/// schema version, and the draft's scope. Only drafts carrying it are deleted,
+/// so a hand-written draft that quotes a marker is left alone.
+pub fn release_signature(marker: &str) -> String {
+    format!(
+        "{SIGNATURE_PREFIX}tool=breezy schema={SIGNATURE_SCHEMA} scope={} -->",
+        marker_scope(marker)
+    )
+}
+
+/// The marker and signature that open every body breezy writes.
+pub fn release_header(marker: &str) -> String {
+    format!("{marker}\n{}", release_signature(marker))
+}
+
+/// True when `body` carries a breezy signature for the scope of `marker`
+/// with a schema version this build understands.
+pub fn has_release_signature(body: &str, marker: &str) -> bool {
+    body.lines().any(|line| {
+        let Some(rest) = line
+            .trim()
+            .strip_prefix(SIGNATURE_PREFIX)
+            .and_then(|rest| rest.strip_suffix(" -->"))
+        else {
+            return false;
+        };
+        let Some((fields, scope)) = rest.split_once(" scope=") else {
+            return false;
+        };
+        let mut tool = None;
+        let mut schema = None;
+        for field in fields.split_whitespace() {
+            match field.split_once('=') {
+                Some(("tool", value)) => tool = Some(value),
+                Some(("schema", value)) => schema = value.parse::<u32>().ok(),
+                _ => {}
+            }
+        }
+        tool == Some("breezy")
+            && schema.is_some_and(|schema| (1..=SIGNATURE_SCHEMA).contains(&schema))
+            && scope == marker_scope(marker)
+    })
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MarkerScope {
+    pub repository: Option<String>,
+    pub branch: String,
+    pub directory: Option<String>,
+    /// Workspace member (a `crate` or npm `package`) the draft tracks.
+    pub member: Option<String>,
+}
+
+/// Reads the scope back out of a breezy marker embedded in a release body.
+pub fn parse_release_marker(body: &str) -> Option<MarkerScope> {
+    let start = body.find("<!-- breezy:")? + "<!-- breezy:".len();
+    let end = body[start..].find("-->")? + start;
+    let mut scope = MarkerScope::default();
+    for part in body[start..end].split_whitespace() {
+        match part.split_once('=') {
+            Some(("repo", value)) => scope.repository = Some(value.to_string()),
+            Some(("branch", value)) => scope.branch = value.to_string(),
+            Some(("directory", value)) => scope.directory = Some(value.to_string()),
+            Some(("member", value)) => scope.member = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(scope)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConventionalTitle<'a> {
+    pub kind: String,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+/// Parses a Conventional Commits style title such as `feat(cli)!: add flag`.
+pub fn parse_conventional_title(title: &str) -> Option<ConventionalTitle<'_>> {
+    let title = title.trim_start();
+    let (header, description) = title.split_once(':')?;
+    let (header, breaking) = match header.strip_suffix('!') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+    let (kind, scope) = match header.split_once('(') {
+        Some((kind, rest)) => {
+            let scope = rest.strip_suffix(')')?.trim();
+            (kind, Some(scope).filter(|scope| !scope.is_empty()))
+        }
+        None => (header, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(ConventionalTitle {
+        kind: kind.to_lowercase(),
+        scope,
+        breaking,
+        description: description.trim(),
+    })
+}
+
+/// Orders PRs by merge date, keeping the first occurrence of each PR number.
+fn unique_by_merge_date(pull_requests: &[PullRequestInfo]) -> Vec<&PullRequestInfo> {
+    let mut ordered: Vec<&PullRequestInfo> = pull_requests.iter().collect();
     ordered.sort_by(|left, right| left.merged_at.cmp(&right.merged_at));
+    let mut seen = HashSet::with_capacity(ordered.len());
+    ordered.retain(|pull_request| seen.insert(pull_request.number));
     ordered
 }
 
+/// The PRs without repeats, in the config's `sort-by` and `sort-direction`
+/// order. Ties keep their merge order in either direction.
+fn sorted_changes<'a>(
+    pull_requests: &'a [PullRequestInfo],
+    config: &ReleaseConfig,
+) -> Vec<&'a PullRequestInfo> {
+    let mut ordered = unique_by_merge_date(pull_requests);
+    let descending = config.sort_direction == SortDirection::Descending;
+    // A reversed comparator, not a reversed list, so ties stay in merge order.
+    let direction = |ordering: Ordering| {
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    ordered.sort_by(|left, right| {
+        direction(match config.sort_by {
+            SortBy::MergedAt => left.merged_at.cmp(&right.merged_at),
+            SortBy::Title => left.title.to_lowercase().cmp(&right.title.to_lowercase()),
+            SortBy::Number => left.number.cmp(&right.number),
+        })
+    });
+    ordered
+}
+
+enum RevertTarget<'a> {
+    Number(u64),
+    Title(&'a str),
+}
+
+/// What a revert PR backs out: the PR its body links with `Reverts #123` (or
+/// `Reverts owner/repo#123`, as GitHub's revert button writes), or else the
+/// title quoted in `Revert "…"` or following `revert:`.
+fn revert_target(pull_request: &PullRequestInfo) -> Option<RevertTarget<'_>> {
+    let linked = pull_request.body.as_deref().and_then(|body| {
+        body.lines().find_map(|line| {
+            let line = line.trim();
+            let rest = line
+                .get(..8)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("reverts "))
+                .map(|_| &line[8..])?;
+            let (_, number) = rest.split_whitespace().next()?.rsplit_once('#')?;
+            number.trim_end_matches(['.', ',']).parse().ok()
+        })
+    });
+    if let Some(number) = linked {
+        return Some(RevertTarget::Number(number));
+    }
+    let title = pull_request.title.trim();
+    if let Some(quoted) = title
+        .strip_prefix("Revert \"")
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return Some(RevertTarget::Title(quoted));
+    }
+    parse_conventional_title(title)
+        .filter(|conventional| conventional.kind == "revert")
+        .map(|conventional| RevertTarget::Title(conventional.description))
+}
+
+fn is_revert_of(target: &RevertTarget<'_>, pull_request: &PullRequestInfo) -> bool {
+    match target {
+        RevertTarget::Number(number) => pull_request.number == *number,
+        RevertTarget::Title(title) => {
+            let own = pull_request.title.trim();
+            own.eq_ignore_ascii_case(title)
+                || parse_conventional_title(own).is_some_and(|conventional| {
+                    conventional.description.eq_ignore_ascii_case(title)
+                })
+        }
+    }
+}
+
+/// Leaves out each PR reverted later in the same window together with its
+/// revert, so the notes don't announce changes that were backed out. A revert
+/// whose original shipped in an earlier release is kept.
+fn drop_reverted(pull_requests: &[PullRequestInfo]) -> Vec<PullRequestInfo> {
+    let mut order: Vec<usize> = (0..pull_requests.len()).collect();
+    order.sort_by(|left, right| {
+        pull_requests[*left]
+            .merged_at
+            .cmp(&pull_requests[*right].merged_at)
+    });
+    let mut dropped = HashSet::new();
+    for (position, &index) in order.iter().enumerate() {
+        let revert = &pull_requests[index];
+        let Some(target) = revert_target(revert) else {
+            continue;
+        };
+        let original = order[..position].iter().rev().copied().find(|earlier| {
+            let candidate = &pull_requests[*earlier];
+            !dropped.contains(earlier)
+                && candidate.number != revert.number
+                && candidate.repository == revert.repository
+                && is_revert_of(&target, candidate)
+        });
+        if let Some(original) = original {
+            dropped.insert(original);
+            dropped.insert(index);
+        }
+    }
+    pull_requests
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !dropped.contains(index))
+        .map(|(_, pull_request)| pull_request.clone())
+        .collect()
+}
+
 pub fn build_release_notes(
     marker: &str,
     pull_requests: &[PullRequestInfo],
+    history: &CommitHistory,
+    config: Option<&ReleaseConfig>,
+) -> String {
+    let header = release_header(marker);
+    let body = render_release_body(pull_requests, history, config);
+    if body.trim().is_empty() {
+        return header;
+    }
+    format!("{header}\n\n{body}")
+}
+
+/// `**Full Changelog**: <compare link>` from the previous tag to this one, or
+/// nothing when either tag or the repository's address is unknown.
+fn full_changelog_line(history: &CommitHistory) -> String {
+    match (&history.repository_url, &history.previous_tag) {
+        (Some(url), Some(previous)) if !history.tag.is_empty() => format!(
+            "**Full Changelog**: {url}/compare/{previous}...{}",
+            history.tag
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Substitutes `variables` into `template` in one pass, so a value that
+/// contains a variable name, like an issue titled "Document $CHANGES", comes
+/// through verbatim. Where names share a prefix the longest one wins.
+pub fn fill_variables(template: &str, variables: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(position) = rest.find('$') {
+        output.push_str(&rest[..position]);
+        rest = &rest[position..];
+        let variable = variables
+            .iter()
+            .filter(|(name, _)| rest.starts_with(name))
+            .max_by_key(|(name, _)| name.len());
+        match variable {
+            Some((name, value)) => {
+                output.push_str(value);
+                rest = &rest[name.len()..];
+            }
+            None => {
+                output.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Fills in the release's `$RESOLVED_VERSION` and `$TAG` for the change
+/// template, leaving its PR variables in place.
+fn apply_release_variables(template: &str, history: &CommitHistory) -> String {
+    fill_variables(
+        template,
+        &[
+            ("$RESOLVED_VERSION", &history.resolved_version),
+            ("$TAG", &history.tag),
+        ],
+    )
+}
+
+/// Adds each non-empty section after `body`, separated by blank lines.
+fn append_sections(body: String, sections: &[&str]) -> String {
+    sections
+        .iter()
+        .filter(|section| !section.is_empty())
+        .fold(body, |body, section| {
+            if body.is_empty() {
+                section.to_string()
+            } else {
+                format!("{body}\n\n{section}")
+            }
+        })
+}
+
+/// Renders the release notes body without the breezy marker and signature.
+pub fn render_release_body(
+    pull_requests: &[PullRequestInfo],
+    history: &CommitHistory,
     config: Option<&ReleaseConfig>,
 ) -> String {
+    let pull_requests = &drop_reverted(pull_requests)[..];
     if let Some(config) = config {
-        let changes = build_changes(pull_requests, config);
-        let body = if let Some(template) = &config.template {
-            template.replace("$CHANGES", &changes)
+        let with_release_variables;
+        let config = if config.change_template.contains("$RESOLVED_VERSION")
+            || config.change_template.contains("$TAG")
+        {
+            with_release_variables = ReleaseConfig {
+                change_template: apply_release_variables(&config.change_template, history),
+                ..config.clone()
+            };
+            &with_release_variables
         } else {
-            changes
+            config
         };
-        if body.trim().is_empty() {
-            return marker.to_string();
+        let mut changes = build_repository_changes(pull_requests, config);
+        if config.direct_commits && !history.direct_commits.is_empty() {
+            let direct = build_direct_commits(&history.direct_commits, config);
+            changes = if changes.is_empty() {
+                direct
+            } else {
+                format!("{changes}\n\n{direct}")
+            };
+        }
+        let new_contributors = new_contributors_section(pull_requests, config);
+        let full_changelog = full_changelog_line(history);
+        let template = config.template.as_deref().unwrap_or("$CHANGES");
+        // Sections the template doesn't place itself are appended when enabled.
+        let mut trailing = Vec::new();
+        if config.new_contributors && !template.contains("$NEW_CONTRIBUTORS") {
+            trailing.push(new_contributors.as_str());
+        }
+        if config.full_changelog && !template.contains("$FULL_CHANGELOG") {
+            trailing.push(full_changelog.as_str());
         }
-        return format!("{marker}\n\n{body}");
+        let rendered = fill_variables(
+            template,
+            &[
+                ("$RESOLVED_VERSION", &history.resolved_version),
+                ("$TAG", &history.tag),
+                (
+                    "$CONTRIBUTORS",
+                    &contributors_list(pull_requests, &history.authors, config),
+                ),
+                ("$CLOSED_ISSUES", &closed_issues_list(pull_requests)),
+                ("$NEW_CONTRIBUTORS", &new_contributors),
+                ("$FULL_CHANGELOG", &full_changelog),
+                (
+                    "$PREVIOUS_TAG",
+                    history.previous_tag.as_deref().unwrap_or(""),
+                ),
+                ("$CHANGES", &changes),
+            ],
+        );
+        let body = append_sections(rendered, &trailing);
+        return config.replacers.iter().fold(body, |body, replacer| {
+            replacer.pattern.replace_all(&body, &replacer.replace)
+        });
     }
 
-    let mut lines = vec![marker.to_string()];
-    let mut seen = HashSet::new();
+    let mut notes = NotesWriter::default();
+    for pull_request in unique_by_merge_date(pull_requests) {
+        notes.line(&pull_request.title);
+    }
+    notes.finish()
+}
 
-    for pull_request in sort_by_merge_date(pull_requests) {
-        if seen.contains(&pull_request.number) {
-            continue;
+#[derive(Clone, Copy)]
+enum ChangeVariable {
+    Title,
+    Author,
+    AuthorName,
+    Number,
+    Body,
+    Reviewers,
+    CoAuthors,
+    Issues,
+}
+
+const CHANGE_VARIABLES: &[(&str, ChangeVariable)] = &[
+    ("$TITLE", ChangeVariable::Title),
+    ("$AUTHOR", ChangeVariable::Author),
+    ("$AUTHOR_NAME", ChangeVariable::AuthorName),
+    ("$NUMBER", ChangeVariable::Number),
+    ("$BODY", ChangeVariable::Body),
+    ("$REVIEWERS", ChangeVariable::Reviewers),
+    ("$CO_AUTHORS", ChangeVariable::CoAuthors),
+    ("$ISSUES", ChangeVariable::Issues),
+];
+
+enum TemplateSegment {
+    Literal(String),
+    Variable(ChangeVariable),
+}
+
+/// A change template split into literal text and variables once, so each PR
+/// is rendered in a single pass without rescanning the template.
+struct CompiledTemplate {
+    segments: Vec<TemplateSegment>,
+}
+
+impl CompiledTemplate {
+    fn compile(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(position) = rest.find('$') {
+            literal.push_str(&rest[..position]);
+            rest = &rest[position..];
+            // Prefer the longest name so variables sharing a prefix don't shadow each other.
+            let variable = CHANGE_VARIABLES
+                .iter()
+                .filter(|(name, _)| rest.starts_with(name))
+                .max_by_key(|(name, _)| name.len());
+            match variable {
+                Some((name, variable)) => {
+                    if !literal.is_empty() {
+                        segments.push(TemplateSegment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(TemplateSegment::Variable(*variable));
+                    rest = &rest[name.len()..];
+                }
+                None => {
+                    literal.push('$');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(TemplateSegment::Literal(literal));
         }
-        seen.insert(pull_request.number);
-        lines.push(pull_request.title.clone());
+        Self { segments }
     }
 
-    if lines.len() == 1 {
-        return lines.remove(0);
+    fn render(&self, pull_request: &PullRequestInfo) -> String {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Literal(value) => output.push_str(value),
+                TemplateSegment::Variable(ChangeVariable::Title) => {
+                    output.push_str(&pull_request.title)
+                }
+                TemplateSegment::Variable(ChangeVariable::Author) => {
+                    output.push_str(&pull_request.author)
+                }
+                TemplateSegment::Variable(ChangeVariable::AuthorName) => output.push_str(
+                    pull_request
+                        .author_name
+                        .as_deref()
+                        .unwrap_or(&pull_request.author),
+                ),
+                TemplateSegment::Variable(ChangeVariable::Number) => {
+                    output.push_str(&pull_request.url)
+                }
+                TemplateSegment::Variable(ChangeVariable::Body) => {
+                    output.push_str(pull_request.body.as_deref().unwrap_or("").trim())
+                }
+                TemplateSegment::Variable(ChangeVariable::Reviewers) => {
+                    let mentions: Vec<String> = pull_request
+                        .reviewers
+                        .iter()
+                        .map(|login| format!("@{login}"))
+                        .collect();
+                    output.push_str(&mentions.join(", "))
+                }
+                TemplateSegment::Variable(ChangeVariable::CoAuthors) => {
+                    output.push_str(&pull_request.co_authors.join(", "))
+                }
+                TemplateSegment::Variable(ChangeVariable::Issues) => {
+                    let urls: Vec<&str> = pull_request
+                        .closing_issues
+                        .iter()
+                        .map(|issue| issue.url.as_str())
+                        .collect();
+                    output.push_str(&urls.join(", "))
+                }
+            }
+        }
+        output
     }
+}
 
-    let mut body = Vec::with_capacity(lines.len() + 1);
-    body.push(lines.remove(0));
-    body.push(String::new());
-    body.extend(lines);
-    body.join("\n")
+/// Builds newline-joined notes directly into one buffer. Blank lines are
+/// deferred so trailing blanks never reach the output.
+#[derive(Default)]
+struct NotesWriter {
+    output: String,
+    lines: usize,
+    pending_blanks: usize,
+}
+
+impl NotesWriter {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            output: String::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    fn line(&mut self, value: &str) {
+        for _ in 0..self.pending_blanks {
+            self.push_raw("");
+        }
+        self.pending_blanks = 0;
+        self.push_raw(value);
+    }
+
+    fn heading(&mut self, level: u8, title: &str) {
+        self.line(&format_heading(level, title));
+    }
+
+    fn blank(&mut self) {
+        self.pending_blanks += 1;
+    }
+
+    fn ends_with_blank(&self) -> bool {
+        self.pending_blanks > 0
+    }
+
+    fn push_raw(&mut self, value: &str) {
+        if self.lines > 0 {
+            self.output.push('\n');
+        }
+        self.output.push_str(value);
+        self.lines += 1;
+    }
+
+    fn finish(self) -> String {
+        self.output
+    }
 }
 
 fn format_heading(level: u8, title: &str) -> String {
@@ -69,92 +761,289 @@ fn format_heading(level: u8, title: &str) -> String {
     format!("{hashes} {title}")
 }
 
-fn build_changes(pull_requests: &[PullRequestInfo], config: &ReleaseConfig) -> String {
-    let mut seen = HashSet::new();
-    let mut ordered = Vec::new();
+struct RenderedChange<'a> {
+    pull_request: &'a PullRequestInfo,
+    line: String,
+    scope: Option<String>,
+}
 
-    for pull_request in sort_by_merge_date(pull_requests) {
-        if seen.insert(pull_request.number) {
-            ordered.push(pull_request);
+/// Renders each repository's changes under its own heading, one level above
+/// the categories, when PRs were collected from several repositories.
+fn build_repository_changes(pull_requests: &[PullRequestInfo], config: &ReleaseConfig) -> String {
+    let mut repositories: Vec<&str> = Vec::new();
+    for repository in pull_requests
+        .iter()
+        .filter_map(|pull_request| pull_request.repository.as_deref())
+    {
+        if !repositories.contains(&repository) {
+            repositories.push(repository);
         }
     }
+    if repositories.is_empty() {
+        return build_changes(pull_requests, config);
+    }
 
-    let mut lines = Vec::new();
-    let mut categorized = HashSet::new();
-
-    if !config.categories.is_empty() {
-        for category in &config.categories {
-            let mut category_lines = Vec::new();
-            for pull_request in &ordered {
-                if is_excluded(pull_request, config) {
-                    continue;
-                }
-                if !has_matching_label(pull_request, &category.labels) {
-                    continue;
-                }
-                categorized.insert(pull_request.number);
-                category_lines.push(apply_change_template(&config.change_template, pull_request));
-            }
-            if !category_lines.is_empty() {
-                lines.push(format_heading(category.heading_level, &category.title));
-                lines.extend(category_lines);
-                lines.push(String::new());
-            }
+    let level = config.categories.first().map_or(2, |category| {
+        category.heading_level.saturating_sub(1).max(1)
+    });
+    let mut notes = NotesWriter::default();
+    for repository in repositories {
+        let members: Vec<PullRequestInfo> = pull_requests
+            .iter()
+            .filter(|pull_request| pull_request.repository.as_deref() == Some(repository))
+            .cloned()
+            .collect();
+        let changes = build_changes(&members, config);
+        if changes.is_empty() {
+            continue;
         }
+        notes.heading(level, repository);
+        notes.blank();
+        notes.line(&changes);
+        notes.blank();
+    }
+    notes.finish()
+}
+
+/// Renders each direct commit through the change template, with its subject
+/// as `$TITLE`, the rest of its message as `$BODY`, and its URL for the PR's.
+fn build_direct_commits(commits: &[DirectCommit], config: &ReleaseConfig) -> String {
+    let template = CompiledTemplate::compile(&config.change_template);
+    let level = config
+        .categories
+        .first()
+        .map_or(2, |category| category.heading_level);
+    let mut notes = NotesWriter::default();
+    notes.heading(level, "Direct Commits");
+    for commit in commits {
+        let (subject, body) = commit
+            .message
+            .split_once('\n')
+            .unwrap_or((commit.message.as_str(), ""));
+        let body = body.trim();
+        notes.line(
+            &template.render(&PullRequestInfo {
+                title: subject.trim().to_string(),
+                body: (!body.is_empty()).then(|| body.to_string()),
+                author: commit
+                    .author
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                url: commit.url.clone(),
+                ..PullRequestInfo::default()
+            }),
+        );
     }
+    notes.finish()
+}
+
+fn build_changes(pull_requests: &[PullRequestInfo], config: &ReleaseConfig) -> String {
+    let template = CompiledTemplate::compile(&config.change_template);
+    let exclude_labels: HashSet<&str> = config.exclude_labels.iter().map(String::as_str).collect();
+    let ordered = sorted_changes(pull_requests, config);
 
+    let mut changes: Vec<RenderedChange> = Vec::with_capacity(ordered.len());
+    let mut category_changes: Vec<Vec<usize>> = vec![Vec::new(); config.categories.len()];
     let mut uncategorized = Vec::new();
-    for pull_request in &ordered {
-        if categorized.contains(&pull_request.number) {
+    let mut highlights = Vec::new();
+
+    for pull_request in ordered {
+        let labels = normalized_labels(&pull_request.labels);
+        if labels
+            .iter()
+            .any(|label| exclude_labels.contains(label.as_str()))
+        {
             continue;
         }
-        if is_excluded(pull_request, config) {
+        let conventional = match config.categorize_by {
+            CategorizeBy::Conventional => parse_conventional_title(&pull_request.title),
+            _ if config.group_by_scope => parse_conventional_title(&pull_request.title),
+            _ => None,
+        };
+
+        let index = changes.len();
+        let mut categorized = false;
+        for (category, members) in config.categories.iter().zip(&mut category_changes) {
+            if matches_category(
+                pull_request,
+                &labels,
+                conventional.as_ref(),
+                category,
+                config.categorize_by,
+            ) {
+                members.push(index);
+                categorized = true;
+            }
+        }
+        if !categorized {
+            uncategorized.push(index);
+        }
+        if labels.contains(&config.highlight_label) {
+            highlights.push(index);
+        }
+
+        let scope = conventional
+            .as_ref()
+            .filter(|_| config.group_by_scope)
+            .and_then(|title| title.scope)
+            .map(|scope| scope.to_lowercase());
+        changes.push(RenderedChange {
+            pull_request,
+            line: template.render(pull_request),
+            scope,
+        });
+    }
+
+    let capacity = changes.iter().map(|change| change.line.len() + 1).sum();
+    let mut notes = NotesWriter::with_capacity(capacity);
+    let section_level = config
+        .categories
+        .first()
+        .map_or(2, |category| category.heading_level);
+
+    write_highlights(&mut notes, &changes, &highlights, config, section_level);
+
+    for (category, members) in config.categories.iter().zip(&category_changes) {
+        if members.is_empty() {
             continue;
         }
-        uncategorized.push(apply_change_template(&config.change_template, pull_request));
+        notes.heading(category.heading_level, &category.title);
+        write_scoped_lines(
+            &mut notes,
+            category.heading_level,
+            &changes,
+            members,
+            config,
+        );
+        notes.blank();
     }
 
     if !uncategorized.is_empty() {
         if let Some(category) = config.categories.first() {
-            lines.push(format_heading(category.heading_level, "Other Changes"));
+            notes.heading(category.heading_level, "Other Changes");
+        }
+        for index in &uncategorized {
+            notes.line(&changes[*index].line);
         }
-        lines.extend(uncategorized);
-        lines.push(String::new());
+        notes.blank();
     }
 
-    while matches!(lines.last(), Some(value) if value.is_empty()) {
-        lines.pop();
+    notes.finish()
+}
+
+/// Writes a section for PRs carrying the highlight label, each with the first
+/// paragraph of its description as a blurb. Highlighted PRs still appear in their category.
+fn write_highlights(
+    notes: &mut NotesWriter,
+    changes: &[RenderedChange],
+    highlights: &[usize],
+    config: &ReleaseConfig,
+    section_level: u8,
+) {
+    if highlights.is_empty() {
+        return;
     }
 
-    lines.join("\n")
+    notes.heading(section_level, &config.highlight_title);
+    for index in highlights {
+        let pull_request = changes[*index].pull_request;
+        notes.heading(
+            (section_level + 1).min(6),
+            &format!("{} ({})", pull_request.title, pull_request.url),
+        );
+        if let Some(blurb) = pull_request.body.as_deref().and_then(first_paragraph) {
+            notes.line(&blurb);
+        }
+        notes.blank();
+    }
 }
 
-fn has_matching_label(pull_request: &PullRequestInfo, category_labels: &[String]) -> bool {
-    if category_labels.is_empty() {
-        return false;
+/// Returns the first prose paragraph of a PR description, skipping HTML
+/// comments and headings left over from PR templates.
+fn first_paragraph(body: &str) -> Option<String> {
+    let mut text = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("<!--") {
+        text.push_str(&rest[..start]);
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
     }
-    let labels = normalized_labels(&pull_request.labels);
-    category_labels
-        .iter()
-        .any(|label| labels.contains(&label.to_lowercase()))
+    text.push_str(rest);
+
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|paragraph| {
+            paragraph
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .find(|paragraph| !paragraph.is_empty())
 }
 
-fn is_excluded(pull_request: &PullRequestInfo, config: &ReleaseConfig) -> bool {
-    if config.exclude_labels.is_empty() {
-        return false;
+/// Writes unscoped lines first, then one subsection per scope ordered by display name.
+fn write_scoped_lines(
+    notes: &mut NotesWriter,
+    heading_level: u8,
+    changes: &[RenderedChange],
+    members: &[usize],
+    config: &ReleaseConfig,
+) {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for index in members {
+        let change = &changes[*index];
+        let Some(scope) = change.scope.as_deref() else {
+            notes.line(&change.line);
+            continue;
+        };
+        let display = config
+            .scopes
+            .iter()
+            .find(|(key, _)| key == scope)
+            .map_or(scope, |(_, display)| display.as_str());
+        match groups.iter_mut().find(|(name, _)| *name == display) {
+            Some((_, group)) => group.push(&change.line),
+            None => groups.push((display, vec![&change.line])),
+        }
+    }
+
+    groups.sort_by(|left, right| left.0.cmp(right.0));
+    let subheading_level = (heading_level + 1).min(6);
+    for (display, group) in groups {
+        if !notes.ends_with_blank() {
+            notes.blank();
+        }
+        notes.heading(subheading_level, display);
+        for line in group {
+            notes.line(line);
+        }
+    }
+}
+
+fn matches_category(
+    pull_request: &PullRequestInfo,
+    labels: &HashSet<String>,
+    conventional: Option<&ConventionalTitle>,
+    category: &ReleaseCategory,
+    categorize_by: CategorizeBy,
+) -> bool {
+    match categorize_by {
+        CategorizeBy::Labels => category.labels.iter().any(|label| labels.contains(label)),
+        CategorizeBy::Gitmoji => has_leading_gitmoji(&pull_request.title, &category.gitmoji),
+        CategorizeBy::Conventional => conventional.is_some_and(|title| {
+            category.types.contains(&title.kind)
+                || (title.breaking && category.types.iter().any(|kind| kind == "breaking"))
+        }),
     }
-    let labels = normalized_labels(&pull_request.labels);
-    config
-        .exclude_labels
-        .iter()
-        .any(|label| labels.contains(&label.to_lowercase()))
 }
 
-fn apply_change_template(template: &str, pull_request: &PullRequestInfo) -> String {
-    template
-        .replace("$TITLE", &pull_request.title)
-        .replace("$AUTHOR", &pull_request.author)
-        .replace("$NUMBER", &pull_request.url)
+fn has_leading_gitmoji(title: &str, codes: &[String]) -> bool {
+    let title = strip_variation_selectors(title.trim_start());
+    codes.iter().any(|code| title.starts_with(code.as_str()))
 }
 
 fn normalized_labels(labels: &[String]) -> HashSet<String> {
@@ -172,13 +1061,12 @@ mod tests {
 
     fn base_config(with_template: bool) -> ReleaseConfig {
         ReleaseConfig {
-            language: None,
-            tag_template: None,
-            name_template: None,
             categories: vec![ReleaseCategory {
                 title: "Features".to_string(),
                 heading_level: 2,
                 labels: vec!["feature".to_string()],
+                gitmoji: Vec::new(),
+                types: Vec::new(),
             }],
             exclude_labels: vec!["skip-log".to_string()],
             change_template: "* $TITLE @$AUTHOR ($NUMBER)".to_string(),
@@ -187,13 +1075,26 @@ mod tests {
             } else {
                 None
             },
+            ..ReleaseConfig::default()
+        }
+    }
+
+    fn pull_request(number: u64, title: &str, labels: &[&str]) -> PullRequestInfo {
+        PullRequestInfo {
+            number,
+            title: title.to_string(),
+            author: "alice".to_string(),
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            url: format!("https://github.com/o/r/pull/{number}"),
+            merged_at: Some(format!("2024-01-{number:02}T00:00:00Z")),
+            ..PullRequestInfo::default()
         }
     }
 
     #[test]
     fn renders_categories_and_urls() {
         let config = base_config(true);
-        let marker = release_marker("main", None);
+        let marker = release_marker("main", None, None, None);
         let pull_requests = vec![
             PullRequestInfo {
                 number: 1,
@@ -202,6 +1103,7 @@ mod tests {
                 labels: vec!["feature".to_string()],
                 url: "https://github.com/o/r/pull/1".to_string(),
                 merged_at: Some("2024-01-01T00:00:00Z".to_string()),
+                ..PullRequestInfo::default()
             },
             PullRequestInfo {
                 number: 2,
@@ -210,6 +1112,7 @@ mod tests {
                 labels: vec!["bug".to_string()],
                 url: "https://github.com/o/r/pull/2".to_string(),
                 merged_at: Some("2024-01-02T00:00:00Z".to_string()),
+                ..PullRequestInfo::default()
             },
             PullRequestInfo {
                 number: 3,
@@ -218,13 +1121,20 @@ mod tests {
                 labels: vec!["skip-log".to_string()],
                 url: "https://github.com/o/r/pull/3".to_string(),
                 merged_at: Some("2024-01-03T00:00:00Z".to_string()),
+                ..PullRequestInfo::default()
             },
         ];
 
-        let notes = build_release_notes(&marker, &pull_requests, Some(&config));
+        let notes = build_release_notes(
+            &marker,
+            &pull_requests,
+            &CommitHistory::default(),
+            Some(&config),
+        );
 
+        let header = release_header(&marker);
         let expected = [
-            marker.as_str(),
+            header.as_str(),
             "",
             "## Changes",
             "",
@@ -239,19 +1149,581 @@ mod tests {
         assert_eq!(notes, expected);
     }
 
+    #[test]
+    fn categorizes_by_leading_gitmoji() {
+        let config = crate::config::parse_config(
+            r#"
+categorize-by: gitmoji
+change-template: "* $TITLE"
+"#,
+        )
+        .unwrap();
+        let pull_requests = vec![
+            pull_request(1, "✨ Add login", &[]),
+            pull_request(2, "⚡️ Speed up search", &[]),
+            pull_request(3, ":bug: Fix crash", &["feature"]),
+            pull_request(4, "Update readme", &[]),
+        ];
+
+        let notes = render_release_body(&pull_requests, &CommitHistory::default(), Some(&config));
+
+        let expected = [
+            "## Features",
+            "* ✨ Add login",
+            "",
+            "## Bug Fixes",
+            "* :bug: Fix crash",
+            "",
+            "## Performance",
+            "* ⚡️ Speed up search",
+            "",
+            "## Other Changes",
+            "* Update readme",
+        ]
+        .join("\n");
+        assert_eq!(notes, expected);
+    }
+
+    #[test]
+    fn groups_conventional_changes_by_scope() {
+        let config = crate::config::parse_config(
+            r#"
+categorize-by: conventional
+change-template: "* $TITLE"
+scopes:
+  cli: Command line
+"#,
+        )
+        .unwrap();
+        let pull_requests = vec![
+            pull_request(1, "feat(cli): add --dry-run", &[]),
+            pull_request(2, "feat: support gitea", &[]),
+            pull_request(3, "feat(api)!: drop v1 endpoints", &[]),
+            pull_request(4, "fix(cli): handle empty input", &[]),
+        ];
+
+        let notes = render_release_body(&pull_requests, &CommitHistory::default(), Some(&config));
+
+        let expected = [
+            "## Breaking Changes",
+            "",
+            "### api",
+            "* feat(api)!: drop v1 endpoints",
+            "",
+            "## Features",
+            "* feat: support gitea",
+            "",
+            "### Command line",
+            "* feat(cli): add --dry-run",
+            "",
+            "### api",
+            "* feat(api)!: drop v1 endpoints",
+            "",
+            "## Bug Fixes",
+            "",
+            "### Command line",
+            "* fix(cli): handle empty input",
+        ]
+        .join("\n");
+        assert_eq!(notes, expected);
+    }
+
+    #[test]
+    fn parses_conventional_titles() {
+        let title = parse_conventional_title("feat(cli)!: add flag").unwrap();
+
+        assert_eq!(title.kind, "feat");
+        assert_eq!(title.scope, Some("cli"));
+        assert!(title.breaking);
+        assert_eq!(title.description, "add flag");
+        assert!(parse_conventional_title("Update readme").is_none());
+        assert!(parse_conventional_title("Fix: the thing").is_some());
+        assert!(parse_conventional_title("Release 1.0: notes").is_none());
+    }
+
+    #[test]
+    fn renders_highlights_first() {
+        let config = base_config(false);
+        let mut highlighted = pull_request(1, "Add login", &["feature", "highlight"]);
+        highlighted.body = Some(
+            "<!-- PR template -->\n## Summary\n\nLog in with\nyour GitHub account.\n\nDetails here."
+                .to_string(),
+        );
+        let pull_requests = vec![highlighted, pull_request(2, "Fix bug", &[])];
+
+        let notes = render_release_body(&pull_requests, &CommitHistory::default(), Some(&config));
+
+        let expected = [
+            "## Highlights",
+            "### Add login (https://github.com/o/r/pull/1)",
+            "Log in with your GitHub account.",
+            "",
+            "## Features",
+            "* Add login @alice (https://github.com/o/r/pull/1)",
+            "",
+            "## Other Changes",
+            "* Fix bug @alice (https://github.com/o/r/pull/2)",
+        ]
+        .join("\n");
+        assert_eq!(notes, expected);
+    }
+
+    #[test]
+    fn compiled_template_substitutes_once() {
+        let template = CompiledTemplate::compile("* $TITLE by $AUTHOR ($NUMBER) $UNKNOWN $");
+        let mut pull_request = pull_request(7, "Mention $AUTHOR literally", &[]);
+        pull_request.author = "bob".to_string();
+
+        assert_eq!(
+            template.render(&pull_request),
+            "* Mention $AUTHOR literally by bob (https://github.com/o/r/pull/7) $UNKNOWN $"
+        );
+    }
+
+    #[test]
+    fn renders_author_names_falling_back_to_logins() {
+        let template = CompiledTemplate::compile("* $TITLE by $AUTHOR_NAME (@$AUTHOR)");
+        let mut pull_request = pull_request(7, "Add widgets", &[]);
+        pull_request.author = "ada".to_string();
+
+        assert_eq!(
+            template.render(&pull_request),
+            "* Add widgets by ada (@ada)"
+        );
+        pull_request.author_name = Some("Ada Lovelace".to_string());
+        assert_eq!(
+            template.render(&pull_request),
+            "* Add widgets by Ada Lovelace (@ada)"
+        );
+    }
+
+    #[test]
+    fn compiled_template_renders_pull_request_body() {
+        let template = CompiledTemplate::compile("* $TITLE\n\n  $BODY");
+        let mut pull_request = pull_request(7, "Add widgets", &[]);
+        pull_request.body = Some("\r\nWidgets can now spin.\r\n".to_string());
+
+        assert_eq!(
+            template.render(&pull_request),
+            "* Add widgets\n\n  Widgets can now spin."
+        );
+        pull_request.body = None;
+        assert_eq!(template.render(&pull_request), "* Add widgets\n\n  ");
+    }
+
+    #[test]
+    fn credits_reviewers_and_co_authors() {
+        assert_eq!(
+            parse_co_authors(
+                "Pairing session.\n\nCo-authored-by: Ada Lovelace <ada@example.com>\n\
+                 co-authored-by:Grace Hopper <grace@example.com>\n\
+                 Co-authored-by: Ada Lovelace <ada@example.org>"
+            ),
+            vec!["Ada Lovelace".to_string(), "Grace Hopper".to_string()]
+        );
+
+        let template =
+            CompiledTemplate::compile("* $TITLE (reviewed by $REVIEWERS; with $CO_AUTHORS)");
+        let mut pull_request = pull_request(7, "Add widgets", &[]);
+        pull_request.reviewers = vec!["bob".to_string(), "carol".to_string()];
+        pull_request.co_authors = vec!["Ada Lovelace".to_string()];
+
+        assert_eq!(
+            template.render(&pull_request),
+            "* Add widgets (reviewed by @bob, @carol; with Ada Lovelace)"
+        );
+    }
+
+    #[test]
+    fn renders_closing_issues() {
+        let config = crate::config::parse_config(
+            "change-template: \"* $TITLE $ISSUES\"\ntemplate: |\n  $CHANGES\n\n  Closed:\n  $CLOSED_ISSUES\n",
+        )
+        .unwrap();
+        let issue = |number: u64| IssueReference {
+            number,
+            title: format!("Issue {number}"),
+            url: format!("https://github.com/o/r/issues/{number}"),
+        };
+        let mut first = pull_request(1, "Fix crash", &[]);
+        first.closing_issues = vec![issue(12), issue(3)];
+        let mut second = pull_request(2, "Fix crash again", &[]);
+        second.closing_issues = vec![issue(12)];
+
+        assert!(uses_closing_issues(&config));
+        assert_eq!(
+            render_release_body(&[first, second], &CommitHistory::default(), Some(&config)),
+            "* Fix crash https://github.com/o/r/issues/12, https://github.com/o/r/issues/3\n\
+             * Fix crash again https://github.com/o/r/issues/12\n\n\
+             Closed:\n\
+             - Issue 3 (https://github.com/o/r/issues/3)\n\
+             - Issue 12 (https://github.com/o/r/issues/12)"
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark: cargo test --release -- --ignored --nocapture"]
+    fn build_changes_scales_linearly() {
+        let config = crate::config::parse_config(
+            r#"
+categories:
+  - title: Features
+    labels: [feature]
+  - title: Fixes
+    labels: [fix, bug]
+  - title: Docs
+    label: docs
+  - title: Maintenance
+    labels: [chore, ci, deps]
+exclude-labels: [skip-log]
+change-template: "* $TITLE @$AUTHOR ($NUMBER)"
+"#,
+        )
+        .unwrap();
+        let labels = ["feature", "fix", "docs", "chore", "skip-log", "question"];
+        let time = |count: u64| {
+            let pull_requests: Vec<PullRequestInfo> = (0..count)
+                .map(|number| PullRequestInfo {
+                    number,
+                    title: format!("Change number {number}"),
+                    author: "alice".to_string(),
+                    labels: vec![labels[(number % 6) as usize].to_string()],
+                    url: format!("https://github.com/o/r/pull/{number}"),
+                    merged_at: Some(format!("2024-01-01T00:00:{number:08}Z")),
+                    ..PullRequestInfo::default()
+                })
+                .collect();
+            let start = std::time::Instant::now();
+            for _ in 0..5 {
+                std::hint::black_box(build_changes(&pull_requests, &config));
+            }
+            start.elapsed()
+        };
+
+        time(1_000);
+        let small = time(5_000);
+        let large = time(50_000);
+        println!("5k PRs: {small:?}, 50k PRs: {large:?}");
+        assert!(
+            large < small * 25,
+            "expected roughly linear scaling, got {small:?} vs {large:?}"
+        );
+    }
+
     #[test]
     fn returns_marker_when_no_changes() {
         let config = base_config(false);
-        let marker = release_marker("main", None);
-        let notes = build_release_notes(&marker, &[], Some(&config));
+        let marker = release_marker("main", None, None, None);
+        let notes = build_release_notes(&marker, &[], &CommitHistory::default(), Some(&config));
 
-        assert_eq!(notes, marker);
+        assert_eq!(notes, release_header(&marker));
     }
 
     #[test]
     fn marker_includes_directory() {
-        let marker = release_marker("main", Some("crates/app"));
+        let marker = release_marker("main", Some("crates/app"), None, None);
 
         assert_eq!(marker, "<!-- breezy:branch=main directory=crates/app -->");
     }
+
+    #[test]
+    fn parses_marker_scope() {
+        let marker = release_marker("main", Some("crates/app"), Some("app"), Some("o/private"));
+        let body = format!("{marker}\n\n* Change");
+
+        let scope = parse_release_marker(&body).unwrap();
+
+        assert_eq!(scope.repository.as_deref(), Some("o/private"));
+        assert_eq!(scope.branch, "main");
+        assert_eq!(scope.directory.as_deref(), Some("crates/app"));
+        assert_eq!(scope.member.as_deref(), Some("app"));
+        assert!(parse_release_marker("Hand-written notes").is_none());
+    }
+
+    #[test]
+    fn marker_includes_source_repository() {
+        let marker = release_marker("main", None, None, Some("o/private"));
+
+        assert_eq!(marker, "<!-- breezy:repo=o/private branch=main -->");
+    }
+
+    #[test]
+    fn credits_pull_request_and_commit_authors_as_contributors() {
+        let config = crate::config::parse_config("template: \"Thanks $CONTRIBUTORS\"").unwrap();
+        let mut first = pull_request(1, "Add widgets", &[]);
+        first.author = "zed".to_string();
+        let second = pull_request(2, "Fix widgets", &[]);
+
+        assert_eq!(
+            render_release_body(
+                &[first, second],
+                &CommitHistory {
+                    authors: vec!["Bob".to_string(), "alice".to_string()],
+                    ..CommitHistory::default()
+                },
+                Some(&config)
+            ),
+            "Thanks @alice, @Bob, @zed"
+        );
+    }
+
+    #[test]
+    fn groups_changes_by_repository() {
+        let mut config = base_config(false);
+        config.categories.truncate(1);
+        config.change_template = "- $TITLE".to_string();
+        let mut api = pull_request(1, "Add endpoint", &["feature"]);
+        api.repository = Some("acme/api".to_string());
+        let mut web = pull_request(2, "Add page", &["feature"]);
+        web.repository = Some("acme/web".to_string());
+        let mut docs = pull_request(3, "Fix typo", &[]);
+        docs.repository = Some("acme/api".to_string());
+
+        let notes =
+            render_release_body(&[api, web, docs], &CommitHistory::default(), Some(&config));
+
+        assert_eq!(
+            notes,
+            "# acme/api\n\n## Features\n- Add endpoint\n\n## Other Changes\n- Fix typo\n\n# acme/web\n\n## Features\n- Add page"
+        );
+    }
+
+    #[test]
+    fn lists_direct_commits_after_changes() {
+        let mut config = base_config(false);
+        config.categories.truncate(1);
+        config.change_template = "- $TITLE @$AUTHOR".to_string();
+        config.direct_commits = true;
+        let history = CommitHistory {
+            direct_commits: vec![DirectCommit {
+                sha: "abc123".to_string(),
+                message: "Hotfix the login page\n\nUrgent.".to_string(),
+                author: Some("octo".to_string()),
+                url: "https://github.com/o/r/commit/abc123".to_string(),
+            }],
+            ..CommitHistory::default()
+        };
+
+        let notes = render_release_body(
+            &[pull_request(1, "Add widgets", &["feature"])],
+            &history,
+            Some(&config),
+        );
+
+        assert_eq!(
+            notes,
+            "## Features\n- Add widgets @alice\n\n## Direct Commits\n- Hotfix the login page @octo"
+        );
+        config.direct_commits = false;
+        assert!(!render_release_body(&[], &history, Some(&config)).contains("Hotfix"));
+    }
+
+    #[test]
+    fn drops_reverted_pull_requests_with_their_reverts() {
+        let mut linked = pull_request(3, "Revert widgets", &["feature"]);
+        linked.body = Some("Reverts o/r#1\n\nBroke the build.".to_string());
+        let pull_requests = [
+            pull_request(1, "Add widgets", &["feature"]),
+            pull_request(2, "feat: gadgets", &["feature"]),
+            linked,
+            pull_request(4, "revert: feat: gadgets", &["feature"]),
+            pull_request(5, "Revert \"Add sprockets\"", &["feature"]),
+            pull_request(6, "Add gizmos", &["feature"]),
+        ];
+
+        let numbers: Vec<u64> = drop_reverted(&pull_requests)
+            .iter()
+            .map(|pull_request| pull_request.number)
+            .collect();
+
+        assert_eq!(numbers, vec![5, 6]);
+    }
+
+    #[test]
+    fn recognizes_signatures_for_the_scope() {
+        let marker = release_marker("main", Some("crates/app"), None, None);
+        let other = release_marker("main", None, None, None);
+        let body = format!("{}\n\n* Change", release_header(&marker));
+
+        assert_eq!(
+            release_signature(&marker),
+            "<!-- breezy-signature tool=breezy schema=1 scope=branch=main directory=crates/app -->"
+        );
+        assert!(has_release_signature(&body, &marker));
+        assert!(!has_release_signature(&body, &other));
+        assert!(!has_release_signature(
+            &format!("{marker}\n\n* Pasted"),
+            &marker
+        ));
+        let future = release_signature(&marker).replace("schema=1", "schema=99");
+        assert!(!has_release_signature(&future, &marker));
+    }
+
+    #[test]
+    fn lists_new_contributors_after_the_changes() {
+        let mut config = base_config(false);
+        config.new_contributors = true;
+        let mut first = pull_request(2, "Add widgets", &[]);
+        first.author = "bob".to_string();
+        first.url = "https://github.com/o/r/pull/2".to_string();
+        first.first_contribution = true;
+        let returning = pull_request(3, "Fix widgets", &[]);
+
+        let body = render_release_body(
+            &[returning.clone(), first.clone()],
+            &CommitHistory::default(),
+            Some(&config),
+        );
+        assert!(body.ends_with(
+            "\n\n## New Contributors\n* @bob made their first contribution in https://github.com/o/r/pull/2"
+        ));
+
+        config.new_contributors = false;
+        config.template = Some("$NEW_CONTRIBUTORS".to_string());
+        let body = render_release_body(&[first], &CommitHistory::default(), Some(&config));
+        assert!(body.starts_with("## New Contributors\n* @bob"));
+        let body = render_release_body(&[returning], &CommitHistory::default(), Some(&config));
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn leaves_excluded_contributors_out_of_the_credits() {
+        let mut config = crate::config::parse_config(
+            "change-template: \"$TITLE @$AUTHOR\"\ntemplate: \"$CHANGES\\nThanks $CONTRIBUTORS\"",
+        )
+        .unwrap();
+        config.exclude_contributors = vec!["Dependabot[bot]".to_string()];
+        let mut bot = pull_request(2, "Bump serde", &[]);
+        bot.author = "dependabot[bot]".to_string();
+        bot.first_contribution = true;
+        let human = pull_request(3, "Add widgets", &[]);
+
+        let body = render_release_body(&[bot, human], &CommitHistory::default(), Some(&config));
+
+        assert!(body.contains("Bump serde @dependabot[bot]"));
+        assert!(body.ends_with("Thanks @alice"));
+    }
+
+    #[test]
+    fn links_the_full_changelog_between_tags() {
+        let mut config = base_config(false);
+        config.full_changelog = true;
+        let mut history = CommitHistory {
+            tag: "v1.1.0".to_string(),
+            previous_tag: Some("v1.0.0".to_string()),
+            repository_url: Some("https://github.com/o/r".to_string()),
+            ..CommitHistory::default()
+        };
+
+        let body = render_release_body(&[], &history, Some(&config));
+        assert_eq!(
+            body,
+            "**Full Changelog**: https://github.com/o/r/compare/v1.0.0...v1.1.0"
+        );
+
+        config.full_changelog = false;
+        config.template = Some("Since $PREVIOUS_TAG\n\n$FULL_CHANGELOG".to_string());
+        let body = render_release_body(&[], &history, Some(&config));
+        assert_eq!(
+            body,
+            "Since v1.0.0\n\n**Full Changelog**: https://github.com/o/r/compare/v1.0.0...v1.1.0"
+        );
+
+        history.previous_tag = None;
+        assert_eq!(
+            render_release_body(&[], &history, Some(&config)),
+            "Since \n\n"
+        );
+    }
+
+    #[test]
+    fn fills_in_the_release_version_and_tag() {
+        let mut config = base_config(false);
+        config.categories.clear();
+        config.change_template = "- $TITLE (new in $TAG)".to_string();
+        config.template = Some("## What's new in $RESOLVED_VERSION\n\n$CHANGES".to_string());
+        let history = CommitHistory {
+            tag: "v1.1.0".to_string(),
+            resolved_version: "1.1.0".to_string(),
+            ..CommitHistory::default()
+        };
+
+        let body = render_release_body(
+            &[pull_request(1, "Add widgets", &[])],
+            &history,
+            Some(&config),
+        );
+
+        assert_eq!(
+            body,
+            "## What's new in 1.1.0\n\n- Add widgets (new in v1.1.0)"
+        );
+    }
+
+    #[test]
+    fn sorts_changes_by_the_configured_key() {
+        let mut config = base_config(false);
+        config.categories.clear();
+        config.change_template = "$TITLE".to_string();
+        let pull_requests = [
+            pull_request(3, "banana", &[]),
+            pull_request(1, "Cherry", &[]),
+            pull_request(4, "Apple", &[]),
+            pull_request(2, "apple", &[]),
+        ];
+        let render = |config: &ReleaseConfig| {
+            render_release_body(&pull_requests, &CommitHistory::default(), Some(config))
+        };
+
+        assert_eq!(render(&config), "Cherry\napple\nbanana\nApple");
+        config.sort_by = SortBy::Title;
+        assert_eq!(render(&config), "apple\nApple\nbanana\nCherry");
+        config.sort_direction = SortDirection::Descending;
+        assert_eq!(render(&config), "Cherry\nbanana\napple\nApple");
+        config.sort_by = SortBy::Number;
+        assert_eq!(render(&config), "Apple\nbanana\napple\nCherry");
+    }
+
+    #[test]
+    fn applies_replacers_to_the_rendered_notes() {
+        let config = crate::config::parse_config(
+            "change-template: \"- $TITLE\"\nreplacers:\n  - search: '^- chore: '\n    replace: '- '\n  - search: '#(\\d+)'\n    replace: '[#$1](https://tracker.example/$1)'\n",
+        )
+        .unwrap();
+
+        let notes = build_release_notes(
+            "<!-- breezy:branch=main -->",
+            &[pull_request(1, "chore: Bump #12", &[])],
+            &CommitHistory::default(),
+            Some(&config),
+        );
+
+        assert!(notes.starts_with("<!-- breezy:branch=main -->\n<!-- breezy-signature"));
+        assert!(notes.ends_with("\n- Bump [#12](https://tracker.example/12)"));
+    }
+
+    #[test]
+    fn fills_top_level_variables_in_one_pass() {
+        let config = crate::config::parse_config(
+            "change-template: \"* $TITLE\"\ntemplate: |\n  $CHANGES\n\n  Closed:\n  $CLOSED_ISSUES\n",
+        )
+        .unwrap();
+        let mut pull_request = pull_request(1, "Explain $CONTRIBUTORS", &[]);
+        pull_request.closing_issues = vec![IssueReference {
+            number: 2,
+            title: "Document $CHANGES".to_string(),
+            url: "https://github.com/o/r/issues/2".to_string(),
+        }];
+
+        assert_eq!(
+            render_release_body(&[pull_request], &CommitHistory::default(), Some(&config)),
+            "* Explain $CONTRIBUTORS\n\n\
+             Closed:\n\
+             - Document $CHANGES (https://github.com/o/r/issues/2)"
+        );
+        assert_eq!(
+            fill_variables("$TAG $TAGGED $", &[("$TAG", "v1"), ("$TAGGED", "$TAG")]),
+            "v1 $TAG $"
+        );
+    }
 }
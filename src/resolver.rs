@@ -0,0 +1,199 @@
+use crate::release_notes::{PullRequestInfo, parse_conventional_title};
+use crate::version::SemVer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// Where each merged PR's requested bump is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BumpSource {
+    #[default]
+    Labels,
+    /// Conventional Commits titles: `feat` is minor, `fix` is patch, and `!` or a
+    /// `BREAKING CHANGE:` footer in the description is major.
+    Conventional,
+}
+
+/// Decides the next version from merged PRs, like release-drafter's
+/// `version-resolver`: the largest bump any PR asks for wins.
+#[derive(Debug, Clone)]
+pub struct VersionResolver {
+    pub source: BumpSource,
+    pub major_labels: Vec<String>,
+    pub minor_labels: Vec<String>,
+    pub patch_labels: Vec<String>,
+    pub default: Bump,
+}
+
+impl Default for VersionResolver {
+    fn default() -> Self {
+        Self {
+            source: BumpSource::Labels,
+            major_labels: vec!["major".to_string()],
+            minor_labels: vec!["minor".to_string()],
+            patch_labels: vec!["patch".to_string()],
+            default: Bump::Patch,
+        }
+    }
+}
+
+impl VersionResolver {
+    pub fn bump_for(&self, pull_requests: &[PullRequestInfo]) -> Bump {
+        let mut bump = None;
+        for pull_request in pull_requests {
+            let requested = match self.source {
+                BumpSource::Labels => self.labelled_bump(pull_request),
+                BumpSource::Conventional => conventional_bump(pull_request),
+            };
+            bump = bump.max(requested);
+        }
+        bump.unwrap_or(self.default)
+    }
+
+    fn labelled_bump(&self, pull_request: &PullRequestInfo) -> Option<Bump> {
+        let labels: Vec<String> = pull_request
+            .labels
+            .iter()
+            .map(|label| label.trim().to_lowercase())
+            .collect();
+        let has = |candidates: &[String]| candidates.iter().any(|label| labels.contains(label));
+        if has(&self.major_labels) {
+            Some(Bump::Major)
+        } else if has(&self.minor_labels) {
+            Some(Bump::Minor)
+        } else if has(&self.patch_labels) {
+            Some(Bump::Patch)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the next version after `previous` (or `0.0.0` when there is none).
+    pub fn resolve(&self, previous: Option<&SemVer>, pull_requests: &[PullRequestInfo]) -> String {
+        increment(previous, self.bump_for(pull_requests))
+    }
+}
+
+fn conventional_bump(pull_request: &PullRequestInfo) -> Option<Bump> {
+    let breaking_footer = pull_request.body.as_deref().is_some_and(|body| {
+        body.lines().any(|line| {
+            let line = line.trim_start();
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        })
+    });
+    if breaking_footer {
+        return Some(Bump::Major);
+    }
+    let title = parse_conventional_title(&pull_request.title)?;
+    match title.kind.as_str() {
+        _ if title.breaking => Some(Bump::Major),
+        "feat" => Some(Bump::Minor),
+        "fix" => Some(Bump::Patch),
+        _ => None,
+    }
+}
+
+/// Applies a SemVer bump. A prerelease base is released rather than skipped
+/// past: `2.0.0-rc.1` bumped by major, minor, or patch becomes `2.0.0`.
+pub fn increment(previous: Option<&SemVer>, bump: Bump) -> String {
+    let (major, minor, patch, prerelease) = match previous {
+        Some(version) => (
+            version.major,
+            version.minor,
+            version.patch,
+            !version.prerelease.is_empty(),
+        ),
+        None => (0, 0, 0, false),
+    };
+    let (major, minor, patch) = match bump {
+        Bump::Major if prerelease && minor == 0 && patch == 0 => (major, 0, 0),
+        Bump::Major => (major + 1, 0, 0),
+        Bump::Minor if prerelease && patch == 0 => (major, minor, 0),
+        Bump::Minor => (major, minor + 1, 0),
+        Bump::Patch if prerelease => (major, minor, patch),
+        Bump::Patch => (major, minor, patch + 1),
+    };
+    format!("{major}.{minor}.{patch}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull_request(labels: &[&str]) -> PullRequestInfo {
+        PullRequestInfo {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            ..PullRequestInfo::default()
+        }
+    }
+
+    #[test]
+    fn picks_largest_labelled_bump() {
+        let resolver = VersionResolver::default();
+        let previous = SemVer::parse("1.4.2").unwrap();
+
+        let minor = resolver.resolve(
+            Some(&previous),
+            &[pull_request(&["patch"]), pull_request(&["Minor", "docs"])],
+        );
+        let major = resolver.resolve(Some(&previous), &[pull_request(&["major"])]);
+        let default = resolver.resolve(Some(&previous), &[pull_request(&["docs"])]);
+
+        assert_eq!(minor, "1.5.0");
+        assert_eq!(major, "2.0.0");
+        assert_eq!(default, "1.4.3");
+        assert_eq!(resolver.resolve(None, &[]), "0.0.1");
+    }
+
+    #[test]
+    fn bumps_from_conventional_titles() {
+        let resolver = VersionResolver {
+            source: BumpSource::Conventional,
+            ..VersionResolver::default()
+        };
+        let previous = SemVer::parse("1.4.2").unwrap();
+        let titled = |title: &str, body: Option<&str>| PullRequestInfo {
+            title: title.to_string(),
+            body: body.map(str::to_string),
+            labels: vec!["major".to_string()],
+            ..PullRequestInfo::default()
+        };
+
+        let fix = resolver.resolve(Some(&previous), &[titled("fix: typo", None)]);
+        let feat = resolver.resolve(
+            Some(&previous),
+            &[
+                titled("fix: typo", None),
+                titled("feat(cli): add flag", None),
+            ],
+        );
+        let bang = resolver.resolve(Some(&previous), &[titled("refactor!: drop api", None)]);
+        let footer = resolver.resolve(
+            Some(&previous),
+            &[titled(
+                "fix: rename",
+                Some("Details\n\nBREAKING CHANGE: renamed"),
+            )],
+        );
+
+        assert_eq!(fix, "1.4.3");
+        assert_eq!(feat, "1.5.0");
+        assert_eq!(bang, "2.0.0");
+        assert_eq!(footer, "2.0.0");
+    }
+
+    #[test]
+    fn releases_prerelease_base() {
+        let rc = SemVer::parse("2.0.0-rc.1").unwrap();
+        let patch_rc = SemVer::parse("1.2.3-beta").unwrap();
+
+        assert_eq!(increment(Some(&rc), Bump::Major), "2.0.0");
+        assert_eq!(increment(Some(&rc), Bump::Patch), "2.0.0");
+        assert_eq!(increment(Some(&patch_rc), Bump::Minor), "1.3.0");
+        assert_eq!(increment(Some(&patch_rc), Bump::Patch), "1.2.3");
+    }
+}
@@ -1,50 +1,167 @@
-use anyhow::{Result, anyhow, bail};
+use crate::assets::wildcard_match;
+use crate::pattern::Pattern;
+use crate::xml;
+use crate::zon;
+use anyhow::{Context, Result, anyhow, bail};
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::fs;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct VersionInfo {
     pub version: String,
 }
 
-pub fn is_prerelease_version(version: &str) -> bool {
-    let trimmed = version.trim();
-    if trimmed.is_empty() {
-        return false;
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Reads manifest files relative to the version root.
+pub trait FileSource {
+    fn read_file(&self, path: &str) -> Result<Option<String>>;
+
+    /// Lists a directory's entries sorted by name; a missing directory is empty.
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>>;
+}
+
+pub struct LocalFiles {
+    root: PathBuf,
+}
+
+impl LocalFiles {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
     }
+}
 
-    let core = trimmed.split('+').next().unwrap_or("");
-    let mut core_parts = core.splitn(2, '-');
-    let core_version = core_parts.next().unwrap_or("");
-    let prerelease = core_parts.next().unwrap_or("");
-    if prerelease.is_empty() {
-        return false;
+impl FileSource for LocalFiles {
+    fn read_file(&self, path: &str) -> Result<Option<String>> {
+        let file = self.root.join(path);
+        if !file.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        Ok(Some(content))
     }
 
-    let mut numeric_parts = core_version.split('.');
-    let major = numeric_parts.next().unwrap_or("");
-    let minor = numeric_parts.next().unwrap_or("");
-    let patch = numeric_parts.next().unwrap_or("");
-    if major.is_empty() || minor.is_empty() || patch.is_empty() || numeric_parts.next().is_some() {
-        return false;
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+        let dir = self.root.join(path);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let entry = entry.with_context(|| format!("Failed to read {}", dir.display()))?;
+            entries.push(DirEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                is_dir: entry.path().is_dir(),
+            });
+        }
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(entries)
     }
+}
 
-    if !major.chars().all(|c| c.is_ascii_digit())
-        || !minor.chars().all(|c| c.is_ascii_digit())
-        || !patch.chars().all(|c| c.is_ascii_digit())
-    {
-        return false;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub prerelease: Vec<String>,
+}
+
+impl SemVer {
+    /// Parses `major.minor.patch[-prerelease][+build]`; build metadata is ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let core = value.trim().split('+').next().unwrap_or("");
+        let (numbers, prerelease) = match core.split_once('-') {
+            Some((numbers, prerelease)) => (numbers, Some(prerelease)),
+            None => (core, None),
+        };
+
+        let mut parts = numbers.split('.');
+        let mut next_number = || {
+            parts
+                .next()
+                .filter(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|part| part.parse::<u64>().ok())
+        };
+        let major = next_number()?;
+        let minor = next_number()?;
+        let patch = next_number()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let prerelease = match prerelease {
+            Some("") => return None,
+            Some(value) => value.split('.').map(|part| part.to_string()).collect(),
+            None => Vec::new(),
+        };
+
+        Some(Self {
+            major,
+            minor,
+            patch,
+            prerelease,
+        })
     }
+}
 
-    true
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| compare_prerelease(&self.prerelease, &other.prerelease))
+    }
 }
 
-fn parse_cargo_version(content: &str) -> Option<String> {
-    let mut in_package = false;
-    let mut in_workspace_package = false;
-    let mut package_version = None;
-    let mut workspace_package_version = None;
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn compare_prerelease(left: &[String], right: &[String]) -> Ordering {
+    match (left.is_empty(), right.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+
+    for (left, right) in left.iter().zip(right) {
+        let ordering = match (left.parse::<u64>(), right.parse::<u64>()) {
+            (Ok(left), Ok(right)) => left.cmp(&right),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => left.cmp(right),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    left.len().cmp(&right.len())
+}
+
+pub fn is_prerelease_version(version: &str) -> bool {
+    SemVer::parse(version).is_some_and(|version| !version.prerelease.is_empty())
+}
+
+/// Scans simple TOML for a quoted string `key` inside the `[section]` table,
+/// or among the top-level keys before any table when `section` is empty.
+fn parse_toml_string(content: &str, section: &str, key: &str) -> Option<String> {
+    let header = format!("[{section}]");
+    let mut in_section = section.is_empty();
+    let mut found = None;
 
     for line in content.lines() {
         let trimmed = line.trim();
@@ -53,16 +170,15 @@ fn parse_cargo_version(content: &str) -> Option<String> {
         }
 
         if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            in_package = trimmed == "[package]";
-            in_workspace_package = trimmed == "[workspace.package]";
+            in_section = trimmed == header;
             continue;
         }
 
-        if !in_package && !in_workspace_package {
+        if !in_section {
             continue;
         }
 
-        if let Some(rest) = trimmed.strip_prefix("version") {
+        if let Some(rest) = trimmed.strip_prefix(key) {
             let rest = rest.trim_start();
             if let Some(rest) = rest.strip_prefix('=') {
                 let value = rest.trim_start();
@@ -72,28 +188,88 @@ fn parse_cargo_version(content: &str) -> Option<String> {
                 {
                     let remainder = &value[quote_char.len_utf8()..];
                     if let Some(end) = remainder.find(quote_char) {
-                        let parsed = Some(remainder[..end].to_string());
-                        if in_package {
-                            package_version = parsed;
-                        } else if in_workspace_package {
-                            workspace_package_version = parsed;
-                        }
+                        found = Some(remainder[..end].to_string());
                     }
                 }
             }
         }
     }
 
-    package_version.or(workspace_package_version)
+    found
+}
+
+/// Reads a string array such as `members = ["a", "b/*"]` from a TOML section; the
+/// array may span several lines.
+fn parse_toml_string_array(content: &str, section: &str, key: &str) -> Vec<String> {
+    let header = format!("[{section}]");
+    let mut in_section = false;
+    let mut collecting = false;
+    let mut values = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if !collecting {
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                in_section = trimmed == header;
+                continue;
+            }
+            let Some(rest) = trimmed
+                .strip_prefix(key)
+                .map(str::trim_start)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(str::trim_start)
+                .and_then(|rest| rest.strip_prefix('['))
+                .filter(|_| in_section)
+            else {
+                continue;
+            };
+            values.clear();
+            collecting = !collect_quoted(rest, &mut values);
+        } else {
+            collecting = !collect_quoted(trimmed, &mut values);
+        }
+    }
+
+    values
+}
+
+/// Pushes the quoted strings in one line of a TOML array, returning true once the
+/// closing bracket is reached.
+fn collect_quoted(line: &str, values: &mut Vec<String>) -> bool {
+    let mut rest = line;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let remainder = &rest[1..];
+                let Some(end) = remainder.find(quote) else {
+                    return false;
+                };
+                values.push(remainder[..end].to_string());
+                rest = &remainder[end + 1..];
+            }
+            Some(']') => return true,
+            _ => return false,
+        }
+    }
+}
+
+fn parse_cargo_version(content: &str) -> Option<String> {
+    parse_toml_string(content, "package", "version")
+        .or_else(|| parse_toml_string(content, "workspace.package", "version"))
 }
 
-fn resolve_rust_version(cwd: &Path) -> Result<Option<VersionInfo>> {
-    let file = cwd.join("Cargo.toml");
-    if !file.exists() {
+fn resolve_rust_version(
+    source: &dyn FileSource,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    let Some(content) = source.read_file("Cargo.toml")? else {
         return Ok(None);
+    };
+    if let Some(name) = options.crate_name.as_deref() {
+        return resolve_cargo_member_version(source, &content, name).map(Some);
     }
 
-    let content = fs::read_to_string(&file)?;
     let version = parse_cargo_version(&content).ok_or_else(|| {
         anyhow!("Cargo.toml does not declare a [package] or [workspace.package] version.")
     })?;
@@ -101,14 +277,115 @@ fn resolve_rust_version(cwd: &Path) -> Result<Option<VersionInfo>> {
     Ok(Some(VersionInfo { version }))
 }
 
-fn resolve_node_version(cwd: &Path) -> Result<Option<VersionInfo>> {
-    let file = cwd.join("package.json");
-    if !file.exists() {
-        return Ok(None);
+/// Finds the workspace member whose `[package] name` is `name`, inheriting the
+/// `[workspace.package]` version when the member uses `version.workspace = true`.
+fn resolve_cargo_member_version(
+    source: &dyn FileSource,
+    root: &str,
+    name: &str,
+) -> Result<VersionInfo> {
+    let mut manifests = vec!["Cargo.toml".to_string()];
+    let excluded = parse_toml_string_array(root, "workspace", "exclude");
+    for pattern in parse_toml_string_array(root, "workspace", "members") {
+        for directory in expand_directory_glob(source, &pattern)? {
+            if !excluded
+                .iter()
+                .any(|exclude| normalize_relative_path(exclude) == directory)
+            {
+                manifests.push(format!("{directory}/Cargo.toml"));
+            }
+        }
+    }
+
+    for path in manifests {
+        let Some(manifest) = source.read_file(&path)? else {
+            continue;
+        };
+        if parse_toml_string(&manifest, "package", "name").as_deref() != Some(name) {
+            continue;
+        }
+        let version = match parse_toml_string(&manifest, "package", "version") {
+            Some(version) => version,
+            None if inherits_workspace_version(&manifest) => {
+                parse_toml_string(root, "workspace.package", "version").ok_or_else(|| {
+                    anyhow!(
+                        "{path} inherits the workspace version, but Cargo.toml has no [workspace.package] version."
+                    )
+                })?
+            }
+            None => bail!("{path} does not declare a [package] version."),
+        };
+        return Ok(VersionInfo { version });
+    }
+
+    bail!("No crate named {name} found in the Cargo.toml workspace members.")
+}
+
+fn inherits_workspace_version(manifest: &str) -> bool {
+    let mut in_package = false;
+    manifest.lines().any(|line| {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            return false;
+        }
+        let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+        in_package
+            && (compact.starts_with("version.workspace=true")
+                || compact.starts_with("version={workspace=true}"))
+    })
+}
+
+/// Expands `*` and `?` in each path component against the directories in `source`.
+fn expand_directory_glob(source: &dyn FileSource, pattern: &str) -> Result<Vec<String>> {
+    let mut matches = vec![String::new()];
+    for component in normalize_relative_path(pattern).split('/') {
+        let mut next = Vec::new();
+        for base in &matches {
+            let join = |name: &str| match base.as_str() {
+                "" => name.to_string(),
+                base => format!("{base}/{name}"),
+            };
+            if !component.contains(['*', '?']) {
+                next.push(join(component));
+                continue;
+            }
+            for entry in source.list_dir(base)? {
+                if entry.is_dir && wildcard_match(component, &entry.name) {
+                    next.push(join(&entry.name));
+                }
+            }
+        }
+        matches = next;
     }
+    Ok(matches)
+}
 
-    let content = fs::read_to_string(&file)?;
+fn resolve_node_version(
+    source: &dyn FileSource,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    let lerna = source
+        .read_file("lerna.json")?
+        .map(|content| serde_json::from_str::<Value>(&content))
+        .transpose()
+        .context("Failed to parse lerna.json")?;
+    let Some(content) = source.read_file("package.json")? else {
+        if let Some(name) = options.package_name.as_deref() {
+            bail!("package {name} requires a workspace root package.json.");
+        }
+        return Ok(lerna_version(lerna.as_ref()).map(|version| VersionInfo { version }));
+    };
     let json: Value = serde_json::from_str(&content)?;
+
+    if let Some(name) = options.package_name.as_deref() {
+        return resolve_node_workspace_version(source, &json, lerna.as_ref(), name).map(Some);
+    }
+    // A fixed-mode lerna.json version is the monorepo's release version.
+    if let Some(version) = lerna_version(lerna.as_ref()) {
+        return Ok(Some(VersionInfo { version }));
+    }
+
     let version = json
         .get("version")
         .and_then(|value| value.as_str())
@@ -119,100 +396,2259 @@ fn resolve_node_version(cwd: &Path) -> Result<Option<VersionInfo>> {
     }))
 }
 
-pub fn parse_languages(input: &str) -> Vec<String> {
-    input
-        .split(|c: char| c.is_whitespace() || c == ',' || c == '+')
-        .map(|value| value.trim().to_lowercase())
-        .filter(|value| !value.is_empty())
-        .collect()
+/// Returns the lerna.json `version`, unless lerna versions packages independently.
+fn lerna_version(lerna: Option<&Value>) -> Option<String> {
+    lerna?
+        .get("version")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|version| !version.is_empty() && *version != "independent")
+        .map(str::to_string)
 }
 
-pub fn resolve_version(cwd: &Path, languages: &[String]) -> Result<VersionInfo> {
-    let mut unknown = Vec::new();
-    for language in languages {
-        if !matches!(language.as_str(), "rust" | "node") {
-            unknown.push(language.clone());
+/// Finds the workspace package named `name` through the root package.json
+/// `workspaces` (or lerna.json `packages`) globs; `!` patterns exclude.
+fn resolve_node_workspace_version(
+    source: &dyn FileSource,
+    root: &Value,
+    lerna: Option<&Value>,
+    name: &str,
+) -> Result<VersionInfo> {
+    let workspaces = root
+        .get("workspaces")
+        .map(|workspaces| workspaces.get("packages").unwrap_or(workspaces))
+        .or_else(|| lerna.and_then(|lerna| lerna.get("packages")))
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            anyhow!("package.json does not declare workspaces, so package {name} cannot be found.")
+        })?;
+    let patterns: Vec<&str> = workspaces.iter().filter_map(Value::as_str).collect();
+    let excluded: Vec<String> = patterns
+        .iter()
+        .filter_map(|pattern| pattern.strip_prefix('!'))
+        .map(normalize_relative_path)
+        .collect();
+
+    for pattern in patterns.iter().filter(|pattern| !pattern.starts_with('!')) {
+        for directory in expand_directory_glob(source, pattern)? {
+            if excluded.contains(&directory) {
+                continue;
+            }
+            let path = format!("{directory}/package.json");
+            let Some(content) = source.read_file(&path)? else {
+                continue;
+            };
+            let json: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {path}"))?;
+            if json.get("name").and_then(Value::as_str) != Some(name) {
+                continue;
+            }
+            if let Some(version) = json.get("version").and_then(Value::as_str) {
+                return Ok(VersionInfo {
+                    version: version.to_string(),
+                });
+            }
+            return lerna_version(lerna)
+                .map(|version| VersionInfo { version })
+                .ok_or_else(|| anyhow!("{path} does not declare a version field."));
         }
     }
 
-    if !unknown.is_empty() {
-        bail!("Unknown language archetype(s): {}", unknown.join(", "));
+    bail!("No package named {name} found in the package.json workspaces.")
+}
+
+fn parse_pyproject_version(content: &str) -> Option<String> {
+    parse_toml_string(content, "project", "version")
+        .or_else(|| parse_toml_string(content, "tool.poetry", "version"))
+}
+
+/// Extracts a version from a manifest's contents.
+type ManifestParser = fn(&str) -> Option<String>;
+
+/// Tries each manifest in order. Returns `None` when none of them exist, so the
+/// next language can be attempted, and fails naming the files that were checked
+/// when they exist but none declares a version.
+fn resolve_from_manifests(
+    source: &dyn FileSource,
+    language: &str,
+    manifests: &[(&str, ManifestParser)],
+    expected: &str,
+) -> Result<Option<VersionInfo>> {
+    let mut checked = Vec::new();
+    for (path, parse) in manifests {
+        let Some(content) = source.read_file(path)? else {
+            continue;
+        };
+        if let Some(version) = parse(&content) {
+            return Ok(Some(VersionInfo { version }));
+        }
+        checked.push(*path);
     }
 
-    let mut attempted = Vec::new();
+    if checked.is_empty() {
+        return Ok(None);
+    }
 
-    for language in languages {
-        let result = match language.as_str() {
-            "rust" => resolve_rust_version(cwd)?,
-            "node" => resolve_node_version(cwd)?,
-            _ => None,
+    bail!(
+        "No {language} version found in {}. Expected {expected}.",
+        checked.join(", ")
+    )
+}
+
+/// Reads `key = value` (or `key: value`) from an INI `[section]`.
+fn parse_ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let header = format!("[{section}]");
+    let mut in_section = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_section = trimmed == header;
+            continue;
+        }
+
+        if !in_section {
+            continue;
+        }
+
+        if let Some((name, value)) = trimmed.split_once(['=', ':'])
+            && name.trim() == key
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+
+    None
+}
+
+fn parse_setup_cfg_version(content: &str) -> Option<String> {
+    // `attr:` and `file:` directives are resolved at build time, so there is no static version.
+    parse_ini_value(content, "metadata", "version").filter(|value| {
+        !value.is_empty() && !value.starts_with("attr:") && !value.starts_with("file:")
+    })
+}
+
+/// Finds the first `name = "value"` assignment (single or double quoted) where
+/// `name` is a whole identifier.
+fn find_quoted_assignment(content: &str, name: &str) -> Option<String> {
+    let mut search = content;
+    while let Some(position) = search.find(name) {
+        let before = search[..position].chars().next_back();
+        let rest = &search[position + name.len()..];
+        search = rest;
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
         };
+        let remainder = &value[1..];
+        if let Some(end) = remainder.find(quote) {
+            return Some(remainder[..end].to_string());
+        }
+    }
+    None
+}
 
-        if let Some(info) = result {
-            return Ok(info);
+/// Scrapes a literal `version="x.y.z"` keyword argument from setup.py.
+fn parse_setup_py_version(content: &str) -> Option<String> {
+    find_quoted_assignment(content, "version")
+}
+
+fn resolve_python_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Python",
+        &[
+            ("pyproject.toml", parse_pyproject_version),
+            ("setup.cfg", parse_setup_cfg_version),
+            ("setup.py", parse_setup_py_version),
+        ],
+        "[project] or [tool.poetry] version in pyproject.toml, [metadata] version in setup.cfg, or version=\"x.y.z\" in setup.py",
+    )
+}
+
+/// Reads a plain `VERSION` file: the first non-empty line, without a leading `v`.
+fn parse_version_file(content: &str) -> Option<String> {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let version = match line.strip_prefix('v') {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => line,
+    };
+    Some(version.to_string())
+}
+
+/// Reads `const Version = "x.y.z"`, also inside a `const (...)` block and
+/// with an explicit `string` type.
+fn parse_go_version(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.trim();
+        let line = line.strip_prefix("const").map_or(line, str::trim_start);
+        let rest = line.strip_prefix("Version")?;
+        if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            return None;
         }
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix("string").unwrap_or(rest).trim_start();
+        let value = rest.strip_prefix('=')?.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '`')?;
+        let remainder = &value[1..];
+        let end = remainder.find(quote)?;
+        Some(remainder[..end].to_string())
+    })
+}
 
-        attempted.push(language.clone());
+fn resolve_go_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Go",
+        &[
+            ("VERSION", parse_version_file),
+            ("version.go", parse_go_version),
+            ("internal/version/version.go", parse_go_version),
+        ],
+        "a VERSION file or const Version = \"x.y.z\" in version.go",
+    )
+}
+
+/// Reads `version=` from a Java properties file such as gradle.properties.
+fn parse_gradle_properties_version(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.trim_start();
+        if line.starts_with('#') || line.starts_with('!') {
+            return None;
+        }
+        let (key, value) = line.split_once(['=', ':'])?;
+        let value = value.trim();
+        (key.trim() == "version" && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn parse_gradle_build_version(content: &str) -> Option<String> {
+    find_quoted_assignment(content, "version")
+}
+
+fn resolve_gradle_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Gradle",
+        &[
+            ("gradle.properties", parse_gradle_properties_version),
+            ("build.gradle", parse_gradle_build_version),
+            ("build.gradle.kts", parse_gradle_build_version),
+        ],
+        "version= in gradle.properties or version = \"x.y.z\" in build.gradle or build.gradle.kts",
+    )
+}
+
+const MAX_RUBY_LIB_DEPTH: usize = 3;
+
+/// Reads `VERSION = "x.y.z"` (optionally `.freeze`d) from a Ruby version file.
+fn parse_ruby_version_constant(content: &str) -> Option<String> {
+    find_quoted_assignment(content, "VERSION")
+}
+
+/// Reads a literal `spec.version = "x.y.z"` from a gemspec.
+fn parse_gemspec_version(content: &str) -> Option<String> {
+    find_quoted_assignment(content, "version")
+}
+
+/// Returns the `Foo::Bar::VERSION` constant a gemspec assigns to `spec.version`.
+fn gemspec_version_constant(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| {
+        let (target, value) = line.split_once('=')?;
+        if !target.trim_end().ends_with(".version") {
+            return None;
+        }
+        let value = value.trim();
+        let constant = value
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .next()?;
+        constant.ends_with("::VERSION").then_some(constant)
+    })
+}
+
+/// Maps `MyGem::Cli::VERSION` to `lib/my_gem/cli/version.rb`.
+fn ruby_constant_path(constant: &str) -> String {
+    let modules: Vec<String> = constant
+        .trim_end_matches("::VERSION")
+        .split("::")
+        .map(snake_case)
+        .collect();
+    format!("lib/{}/version.rb", modules.join("/"))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut output = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                output.push('_');
+            }
+            output.extend(c.to_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Finds `version.rb` files under `lib/`, shallowest first.
+fn find_ruby_version_files(source: &dyn FileSource) -> Result<Vec<String>> {
+    let mut found = Vec::new();
+    let mut directories = vec!["lib".to_string()];
+    for _ in 0..MAX_RUBY_LIB_DEPTH {
+        let mut next = Vec::new();
+        for directory in &directories {
+            for entry in source.list_dir(directory)? {
+                let path = format!("{directory}/{}", entry.name);
+                if entry.is_dir {
+                    next.push(path);
+                } else if entry.name == "version.rb" {
+                    found.push(path);
+                }
+            }
+        }
+        directories = next;
+    }
+    Ok(found)
+}
+
+fn resolve_ruby_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let mut checked = Vec::new();
+    let mut preferred = None;
+
+    for entry in source.list_dir("")? {
+        if entry.is_dir || !entry.name.ends_with(".gemspec") {
+            continue;
+        }
+        let Some(content) = source.read_file(&entry.name)? else {
+            continue;
+        };
+        if let Some(version) = parse_gemspec_version(&content) {
+            return Ok(Some(VersionInfo { version }));
+        }
+        preferred =
+            preferred.or_else(|| gemspec_version_constant(&content).map(ruby_constant_path));
+        checked.push(entry.name);
+    }
+
+    let mut candidates: Vec<String> = preferred.into_iter().collect();
+    for path in find_ruby_version_files(source)? {
+        if !candidates.contains(&path) {
+            candidates.push(path);
+        }
+    }
+    for path in candidates {
+        let Some(content) = source.read_file(&path)? else {
+            continue;
+        };
+        if let Some(version) = parse_ruby_version_constant(&content) {
+            return Ok(Some(VersionInfo { version }));
+        }
+        checked.push(path);
+    }
+
+    if checked.is_empty() {
+        return Ok(None);
     }
 
     bail!(
-        "Unable to determine version from {}. Ensure the expected version file exists.",
-        attempted.join(", ")
+        "No Ruby version found in {}. Expected spec.version in a *.gemspec or VERSION = \"x.y.z\" in lib/**/version.rb.",
+        checked.join(", ")
     )
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{is_prerelease_version, parse_cargo_version};
+const MAX_POM_PARENT_DEPTH: usize = 8;
 
-    #[test]
-    fn parse_package_version() {
-        let content = r#"
-[package]
-name = "demo"
-version = "0.1.0"
-"#;
-        assert_eq!(parse_cargo_version(content), Some("0.1.0".to_string()));
-    }
+fn resolve_maven_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let Some(content) = source.read_file("pom.xml")? else {
+        return Ok(None);
+    };
 
-    #[test]
-    fn parse_workspace_package_version() {
-        let content = r#"
-[workspace]
-members = ["crate-a"]
+    let version = resolve_pom_version(source, "pom.xml", &content, 0)?;
+    Ok(Some(VersionInfo { version }))
+}
 
-[workspace.package]
-version = "1.0.0-alpha.1"
-"#;
-        assert_eq!(
-            parse_cargo_version(content),
-            Some("1.0.0-alpha.1".to_string())
-        );
+/// Reads `<project><version>`, inheriting from `<parent>` when absent: first the
+/// parent's declared version, then the parent pom at `relativePath` (default `../pom.xml`).
+/// `${property}` placeholders are resolved from `<properties>`.
+fn resolve_pom_version(
+    source: &dyn FileSource,
+    path: &str,
+    content: &str,
+    depth: usize,
+) -> Result<String> {
+    let project = xml::parse(content).with_context(|| format!("Failed to parse {path}"))?;
+    if project.name != "project" {
+        bail!("{path} does not have a <project> root element.");
     }
+    let parent = project.child("parent");
 
-    #[test]
-    fn prefer_package_over_workspace_package() {
-        let content = r#"
-[workspace]
-members = ["crate-a"]
+    let version = match project
+        .child_text("version")
+        .or_else(|| parent.and_then(|parent| parent.child_text("version")))
+    {
+        Some(version) => version.to_string(),
+        None => {
+            let Some(parent) = parent else {
+                bail!("{path} does not declare a project or parent version.");
+            };
+            if depth >= MAX_POM_PARENT_DEPTH {
+                bail!("Parent pom chain is too deep while resolving {path}.");
+            }
+            let relative = parent.child_text("relativePath").unwrap_or("../pom.xml");
+            let relative = if relative.ends_with(".xml") {
+                relative.to_string()
+            } else {
+                format!("{}/pom.xml", relative.trim_end_matches('/'))
+            };
+            let parent_path = join_relative_path(path, &relative);
+            let parent_content = source.read_file(&parent_path)?.ok_or_else(|| {
+                anyhow!("{path} inherits its version but parent pom {parent_path} was not found.")
+            })?;
+            resolve_pom_version(source, &parent_path, &parent_content, depth + 1)?
+        }
+    };
 
-[workspace.package]
-version = "2.0.0"
+    expand_pom_properties(&version, &project)
+        .ok_or_else(|| anyhow!("{path} version {version} references an undefined property."))
+}
 
-[package]
-name = "demo"
-version = "3.1.4"
-"#;
-        assert_eq!(parse_cargo_version(content), Some("3.1.4".to_string()));
+fn expand_pom_properties(value: &str, project: &xml::Element) -> Option<String> {
+    let mut output = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let end = rest[start..].find('}')? + start;
+        let name = &rest[start + 2..end];
+        let resolved = project
+            .child("properties")
+            .and_then(|properties| properties.child_text(name))?;
+        output.push_str(resolved);
+        rest = &rest[end + 1..];
     }
+    output.push_str(rest);
+    Some(output)
+}
 
-    #[test]
-    fn prerelease_detection() {
-        assert!(is_prerelease_version("0.1.0-a.1"));
+/// Joins `relative` onto the directory containing `base`, collapsing `.` and
+/// `..` segments. Leading `..` segments that climb above the root are kept.
+pub fn join_relative_path(base: &str, relative: &str) -> String {
+    let directory = base.rsplit_once('/').map_or("", |(directory, _)| directory);
+    normalize_relative_path(&format!("{directory}/{relative}"))
+}
+
+pub fn normalize_relative_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|last| *last != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Counts the directories a relative path descends below its base.
+pub fn directory_depth(path: &str) -> usize {
+    normalize_relative_path(path)
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "..")
+        .count()
+}
+
+fn resolve_php_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let Some(content) = source.read_file("composer.json")? else {
+        return Ok(None);
+    };
+
+    let json: Value = serde_json::from_str(&content).context("Failed to parse composer.json")?;
+    let version = json
+        .get("version")
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| {
+            anyhow!(
+                "composer.json does not declare a version field. Composer projects often omit it and rely on VCS tags; add the field or use the git-tag language instead."
+            )
+        })?;
+
+    Ok(Some(VersionInfo {
+        version: version.to_string(),
+    }))
+}
+
+/// Reads `version: "x.y.z"` from mix.exs, following a `version: @attr`
+/// reference to its `@attr "x.y.z"` module attribute.
+fn parse_mix_version(content: &str) -> Option<String> {
+    let code: String = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut search = code.as_str();
+    while let Some(position) = search.find("version:") {
+        let before = search[..position].chars().next_back();
+        let value = search[position + "version:".len()..].trim_start();
+        search = &search[position + "version:".len()..];
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        if let Some(literal) = elixir_string(value) {
+            return Some(literal);
+        }
+        if let Some(attribute) = value.strip_prefix('@') {
+            let name: String = attribute
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            return mix_attribute(&code, &name);
+        }
+    }
+    None
+}
+
+fn mix_attribute(code: &str, name: &str) -> Option<String> {
+    let definition = format!("@{name}");
+    code.lines().find_map(|line| {
+        let rest = line.trim_start().strip_prefix(&definition)?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        elixir_string(rest.trim_start())
+    })
+}
+
+fn elixir_string(value: &str) -> Option<String> {
+    let remainder = value.strip_prefix('"')?;
+    let end = remainder.find('"')?;
+    Some(remainder[..end].to_string())
+}
+
+fn resolve_elixir_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Elixir",
+        &[("mix.exs", parse_mix_version)],
+        "version: \"x.y.z\" or version: @version with a literal @version attribute in mix.exs",
+    )
+}
+
+const MSBUILD_VERSION_PROPERTIES: [&str; 3] = ["Version", "VersionPrefix", "VersionSuffix"];
+
+/// Collects version properties from every `<PropertyGroup>`; later groups win,
+/// as in MSBuild.
+fn collect_msbuild_properties(
+    project: &xml::Element,
+    properties: &mut Vec<(&'static str, String)>,
+) {
+    for group in project.children_named("PropertyGroup") {
+        for name in MSBUILD_VERSION_PROPERTIES {
+            if let Some(value) = group.child_text(name) {
+                properties.retain(|(key, _)| *key != name);
+                properties.push((name, value.to_string()));
+            }
+        }
+    }
+}
+
+/// Whether a Directory.Build.props explicitly imports the one above it, as in
+/// `<Import Project="$([MSBuild]::GetPathOfFileAbove('Directory.Build.props', ...))" />`.
+fn imports_parent_build_props(project: &xml::Element) -> bool {
+    project.children_named("Import").any(|import| {
+        import
+            .attribute("Project")
+            .is_some_and(|path| path.contains("Directory.Build.props"))
+    })
+}
+
+/// Computes the effective version: `Version`, else `VersionPrefix` with an
+/// optional `-VersionSuffix`. `$(VersionPrefix)`-style references are expanded.
+fn msbuild_version(properties: &[(&'static str, String)]) -> Option<String> {
+    let lookup = |name: &str| {
+        properties
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let version = match (lookup("Version"), lookup("VersionPrefix")) {
+        (Some(version), _) => version.to_string(),
+        (None, Some(prefix)) => match lookup("VersionSuffix") {
+            Some(suffix) => format!("{prefix}-{suffix}"),
+            None => prefix.to_string(),
+        },
+        (None, None) => return None,
+    };
+
+    let mut expanded = version;
+    for name in MSBUILD_VERSION_PROPERTIES {
+        let reference = format!("$({name})");
+        if expanded.contains(&reference) {
+            expanded = expanded.replace(&reference, lookup(name).unwrap_or(""));
+        }
+    }
+    (!expanded.contains("$(") && !expanded.is_empty()).then_some(expanded)
+}
+
+fn resolve_dotnet_version(
+    source: &dyn FileSource,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    let mut checked = Vec::new();
+    let mut properties = Vec::new();
+
+    // MSBuild imports only the closest Directory.Build.props up to the repository
+    // root; it reaches further only when that file imports its parent.
+    let mut props = Vec::new();
+    for depth in 0..=options.directory_depth {
+        let path = format!("{}Directory.Build.props", "../".repeat(depth));
+        let Some(content) = source.read_file(&path)? else {
+            continue;
+        };
+        let project = xml::parse(&content).with_context(|| format!("Failed to parse {path}"))?;
+        let imports_parent = imports_parent_build_props(&project);
+        props.push((path, project));
+        if !imports_parent {
+            break;
+        }
+    }
+    for (path, project) in props.iter().rev() {
+        collect_msbuild_properties(project, &mut properties);
+        checked.push(normalize_relative_path(path));
+    }
+
+    let projects: Vec<String> = source
+        .list_dir("")?
+        .into_iter()
+        .filter(|entry| !entry.is_dir && entry.name.ends_with(".csproj"))
+        .map(|entry| entry.name)
+        .collect();
+    if projects.len() > 1 && msbuild_version(&properties).is_none() {
+        bail!(
+            "Found multiple project files ({}). Set the version in Directory.Build.props or use the directory input to pick one project.",
+            projects.join(", ")
+        );
+    }
+    for path in projects {
+        if let Some(content) = source.read_file(&path)? {
+            let project =
+                xml::parse(&content).with_context(|| format!("Failed to parse {path}"))?;
+            collect_msbuild_properties(&project, &mut properties);
+            checked.push(path);
+        }
+    }
+
+    if checked.is_empty() {
+        return Ok(None);
+    }
+
+    let version = msbuild_version(&properties).ok_or_else(|| {
+        anyhow!(
+            "No .NET version found in {}. Expected <Version> or <VersionPrefix> in a *.csproj or Directory.Build.props.",
+            checked.join(", ")
+        )
+    })?;
+    Ok(Some(VersionInfo { version }))
+}
+
+/// Reads `version:` from pubspec.yaml. Flutter's `x.y.z+build` form is already
+/// SemVer build metadata, so the build number never marks a prerelease.
+fn parse_pubspec_version(content: &str) -> Option<String> {
+    yaml_string_field(content, "version")
+}
+
+/// Reads a top-level scalar from a YAML document as a string.
+fn yaml_string_field(content: &str, key: &str) -> Option<String> {
+    let document: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    let version = match document.get(key)? {
+        serde_yaml::Value::String(version) => version.trim().to_string(),
+        // An unquoted `version: 1.0` is parsed as a float.
+        serde_yaml::Value::Number(version) => version.to_string(),
+        _ => return None,
+    };
+    (!version.is_empty()).then_some(version)
+}
+
+fn resolve_dart_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Dart",
+        &[("pubspec.yaml", parse_pubspec_version)],
+        "a version: field in pubspec.yaml",
+    )
+}
+
+fn parse_chart_version(content: &str) -> Option<String> {
+    yaml_string_field(content, "version")
+}
+
+fn parse_chart_app_version(content: &str) -> Option<String> {
+    yaml_string_field(content, "appVersion")
+}
+
+fn resolve_helm_version(
+    source: &dyn FileSource,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    if options.helm_app_version {
+        resolve_from_manifests(
+            source,
+            "Helm",
+            &[("Chart.yaml", parse_chart_app_version)],
+            "an appVersion: field in Chart.yaml",
+        )
+    } else {
+        resolve_from_manifests(
+            source,
+            "Helm",
+            &[("Chart.yaml", parse_chart_version)],
+            "a version: field in Chart.yaml",
+        )
+    }
+}
+
+const DEFAULT_VERSION_FILENAME: &str = "VERSION";
+
+fn resolve_file_version(
+    source: &dyn FileSource,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    let path = options
+        .version_filename
+        .as_deref()
+        .unwrap_or(DEFAULT_VERSION_FILENAME);
+    let Some(content) = source.read_file(path)? else {
+        return Ok(None);
+    };
+
+    let version = parse_version_file(&content).ok_or_else(|| anyhow!("{path} is empty."))?;
+    Ok(Some(VersionInfo { version }))
+}
+
+/// Removes `//` and `/* */` comments and trailing commas so JSONC parses as JSON.
+fn strip_jsonc(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|next| *next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            (',', _) => {
+                let rest: String = chars.clone().collect();
+                if !rest.trim_start().starts_with(['}', ']']) {
+                    output.push(c);
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+fn parse_deno_version(content: &str) -> Option<String> {
+    let json: Value = serde_json::from_str(&strip_jsonc(content)).ok()?;
+    json.get("version")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(str::to_string)
+}
+
+fn resolve_deno_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Deno",
+        &[
+            ("deno.json", parse_deno_version),
+            ("deno.jsonc", parse_deno_version),
+            ("jsr.json", parse_deno_version),
+        ],
+        "a version field in deno.json, deno.jsonc, or jsr.json",
+    )
+}
+
+/// Reads a `// version: x.y.z` comment, the convention for Swift packages since
+/// Package.swift has no version field.
+fn parse_swift_version_comment(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let comment = line.trim().strip_prefix("//")?.trim();
+        let (key, value) = comment.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("version") {
+            return None;
+        }
+        value
+            .split_whitespace()
+            .next()
+            .map(|version| version.to_string())
+    })
+}
+
+fn parse_xcconfig_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.split("//").next().unwrap_or("");
+        let (name, value) = line.split_once('=')?;
+        let value = value.trim().trim_end_matches(';').trim();
+        (name.trim() == key && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Reads the `<string>` following `<key>{key}</key>` in a plist's top-level dict.
+fn parse_plist_string(content: &str, key: &str) -> Option<String> {
+    let root = xml::parse(content).ok()?;
+    let dict = if root.name == "dict" {
+        &root
+    } else {
+        root.child("dict")?
+    };
+    let position = dict
+        .children
+        .iter()
+        .position(|child| child.name == "key" && child.text.trim() == key)?;
+    let value = dict.children.get(position + 1)?;
+    let text = value.text.trim();
+    (value.name == "string" && !text.is_empty()).then(|| text.to_string())
+}
+
+/// Lists files called `name` (or ending in `name` when it starts with `.`) at the
+/// root and one directory down.
+fn find_shallow_files(source: &dyn FileSource, name: &str) -> Result<Vec<String>> {
+    let matches = |file: &str| match name.strip_prefix('.') {
+        Some(_) => file.ends_with(name),
+        None => file == name,
+    };
+    let mut found = Vec::new();
+    let mut directories = Vec::new();
+    for entry in source.list_dir("")? {
+        if entry.is_dir {
+            directories.push(entry.name);
+        } else if matches(&entry.name) {
+            found.push(entry.name);
+        }
+    }
+    for directory in directories {
+        for entry in source.list_dir(&directory)? {
+            if !entry.is_dir && matches(&entry.name) {
+                found.push(format!("{directory}/{}", entry.name));
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn resolve_swift_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let mut checked = Vec::new();
+    if let Some(content) = source.read_file("Package.swift")? {
+        if let Some(version) = parse_swift_version_comment(&content) {
+            return Ok(Some(VersionInfo { version }));
+        }
+        checked.push("Package.swift".to_string());
+    }
+
+    let mut marketing_version = None;
+    for path in find_shallow_files(source, ".xcconfig")? {
+        if let Some(content) = source.read_file(&path)? {
+            marketing_version =
+                marketing_version.or_else(|| parse_xcconfig_value(&content, "MARKETING_VERSION"));
+            checked.push(path);
+        }
+    }
+
+    for path in find_shallow_files(source, "Info.plist")? {
+        let Some(content) = source.read_file(&path)? else {
+            continue;
+        };
+        checked.push(path);
+        let Some(version) = parse_plist_string(&content, "CFBundleShortVersionString") else {
+            continue;
+        };
+        // Xcode projects usually point the plist at the build setting.
+        if !version.starts_with('$') {
+            return Ok(Some(VersionInfo { version }));
+        }
+    }
+    if let Some(version) = marketing_version {
+        return Ok(Some(VersionInfo { version }));
+    }
+
+    if checked.is_empty() {
+        return Ok(None);
+    }
+    bail!(
+        "No Swift version found in {}. Expected a // version: comment in Package.swift, MARKETING_VERSION in an .xcconfig, or CFBundleShortVersionString in Info.plist.",
+        checked.join(", ")
+    )
+}
+
+/// Returns the byte range of the arguments of the first top-level `name(...)` call,
+/// skipping `#` comments and parentheses inside strings.
+fn find_call_arguments(content: &str, name: &str) -> Option<Range<usize>> {
+    let mut offset = 0;
+    while let Some(found) = content[offset..].find(name) {
+        let start = offset + found;
+        offset = start + name.len();
+        let before = content[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            continue;
+        }
+        let line_start = content[..start].rfind('\n').map_or(0, |index| index + 1);
+        if content[line_start..start].contains('#') {
+            continue;
+        }
+        let Some(arguments) = content[offset..].trim_start().strip_prefix('(') else {
+            continue;
+        };
+        let begin = content.len() - arguments.len();
+
+        let (mut depth, mut quote, mut comment) = (1, None, false);
+        for (index, c) in arguments.char_indices() {
+            match (quote, c) {
+                _ if comment => comment = c != '\n',
+                (Some(open), _) if open == c => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '#') => comment = true,
+                (None, '(') => depth += 1,
+                (None, ')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(begin..begin + index);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+    None
+}
+
+fn parse_bazel_module_version(content: &str) -> Option<String> {
+    find_quoted_assignment(&content[find_call_arguments(content, "module")?], "version")
+        .filter(|version| !version.is_empty())
+}
+
+fn resolve_bazel_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Bazel",
+        &[("MODULE.bazel", parse_bazel_module_version)],
+        "a version argument to module() in MODULE.bazel",
+    )
+}
+
+/// Reads `VERSION x.y.z` from the `project()` call; CMake commands are case-insensitive.
+fn parse_cmake_project_version(content: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so the range applies to the original.
+    let arguments = find_call_arguments(&content.to_ascii_lowercase(), "project")?;
+    let mut tokens = content[arguments]
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(str::split_whitespace);
+    tokens.find(|token| token.eq_ignore_ascii_case("VERSION"))?;
+    let version = tokens.next()?.trim_matches('"');
+    (!version.is_empty() && !version.contains("${")).then(|| version.to_string())
+}
+
+fn resolve_cmake_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "CMake",
+        &[("CMakeLists.txt", parse_cmake_project_version)],
+        "project(<name> VERSION x.y.z) in CMakeLists.txt",
+    )
+}
+
+/// Reads `version := "x.y.z"`, including the `ThisBuild / version` and legacy
+/// `version in ThisBuild` scoped forms.
+fn parse_sbt_version(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.split("//").next().unwrap_or("");
+        let mut search = line;
+        while let Some(position) = search.find("version") {
+            let before = search[..position].chars().next_back();
+            let rest = &search[position + "version".len()..];
+            search = rest;
+            if before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                || rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+            {
+                continue;
+            }
+            let rest = rest.trim_start();
+            let rest = rest
+                .strip_prefix("in ThisBuild")
+                .map_or(rest, str::trim_start);
+            let Some(value) = rest.strip_prefix(":=") else {
+                continue;
+            };
+            let value = value.trim_start().strip_prefix('"')?;
+            return value
+                .find('"')
+                .map(|end| value[..end].to_string())
+                .filter(|version| !version.is_empty());
+        }
+        None
+    })
+}
+
+fn resolve_scala_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Scala",
+        &[
+            ("build.sbt", parse_sbt_version),
+            ("version.sbt", parse_sbt_version),
+        ],
+        "version := \"x.y.z\" in build.sbt or version.sbt",
+    )
+}
+
+/// Reads the top-level `version:` field of a .cabal file; field names are
+/// case-insensitive and `cabal-version` is a different field.
+fn parse_cabal_version(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        if line.starts_with(char::is_whitespace) || line.trim_start().starts_with("--") {
+            return None;
+        }
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        (key.trim().eq_ignore_ascii_case("version") && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn parse_hpack_version(content: &str) -> Option<String> {
+    yaml_string_field(content, "version")
+}
+
+fn resolve_haskell_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let cabal = source
+        .list_dir("")?
+        .into_iter()
+        .find(|entry| !entry.is_dir && entry.name.ends_with(".cabal"));
+    let mut checked = Vec::new();
+    if let Some(entry) = cabal
+        && let Some(content) = source.read_file(&entry.name)?
+    {
+        if let Some(version) = parse_cabal_version(&content) {
+            return Ok(Some(VersionInfo { version }));
+        }
+        checked.push(entry.name);
+    }
+
+    match resolve_from_manifests(
+        source,
+        "Haskell",
+        &[("package.yaml", parse_hpack_version)],
+        "a version: field in the .cabal file or package.yaml",
+    ) {
+        Ok(None) if !checked.is_empty() => bail!(
+            "No Haskell version found in {}. Expected a version: field in the .cabal file or package.yaml.",
+            checked.join(", ")
+        ),
+        result => result,
+    }
+}
+
+fn parse_zon_version(content: &str) -> Option<String> {
+    zon::parse(content)
+        .ok()?
+        .field("version")?
+        .as_str()
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(str::to_string)
+}
+
+fn resolve_zig_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Zig",
+        &[("build.zig.zon", parse_zon_version)],
+        "a .version field in build.zig.zon",
+    )
+}
+
+fn parse_julia_version(content: &str) -> Option<String> {
+    parse_toml_string(content, "", "version")
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
+fn resolve_julia_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Julia",
+        &[
+            ("JuliaProject.toml", parse_julia_version),
+            ("Project.toml", parse_julia_version),
+        ],
+        "a top-level version in Project.toml",
+    )
+}
+
+/// Reads the `Version:` field from a Debian-control style DESCRIPTION file, where
+/// indented lines continue the previous field.
+fn parse_description_version(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        if line.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let (field, value) = line.split_once(':')?;
+        let value = value.trim();
+        (field == "Version" && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn resolve_r_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "R",
+        &[("DESCRIPTION", parse_description_version)],
+        "a Version: field in DESCRIPTION",
+    )
+}
+
+fn parse_shard_version(content: &str) -> Option<String> {
+    yaml_string_field(content, "version")
+}
+
+fn resolve_crystal_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    resolve_from_manifests(
+        source,
+        "Crystal",
+        &[("shard.yml", parse_shard_version)],
+        "a version: field in shard.yml",
+    )
+}
+
+/// Reads `version = "x.y.z"` from the first `*.nimble` file, ignoring `#` comments.
+fn parse_nimble_version(content: &str) -> Option<String> {
+    let code: String = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+    find_quoted_assignment(&code, "version").filter(|version| !version.is_empty())
+}
+
+fn resolve_nim_version(source: &dyn FileSource) -> Result<Option<VersionInfo>> {
+    let Some(entry) = source
+        .list_dir("")?
+        .into_iter()
+        .find(|entry| !entry.is_dir && entry.name.ends_with(".nimble"))
+    else {
+        return Ok(None);
+    };
+    let Some(content) = source.read_file(&entry.name)? else {
+        return Ok(None);
+    };
+    let version = parse_nimble_version(&content).ok_or_else(|| {
+        anyhow!(
+            "No Nim version found in {}. Expected version = \"x.y.z\".",
+            entry.name
+        )
+    })?;
+    Ok(Some(VersionInfo { version }))
+}
+
+const LANGUAGES: &[&str] = &[
+    "rust", "node", "python", "go", "maven", "java", "gradle", "ruby", "php", "elixir", "dotnet",
+    "dart", "flutter", "helm", "file", "git-tag", "deno", "swift", "bazel", "cmake", "scala",
+    "sbt", "haskell", "zig", "julia", "r", "crystal", "nim",
+];
+
+pub fn parse_languages(input: &str) -> Vec<String> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '+')
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// A config-defined version source: the first capture group of `pattern` in `path`.
+#[derive(Debug, Clone)]
+pub struct VersionFile {
+    pub path: String,
+    pub pattern: Pattern,
+}
+
+fn resolve_custom_version(source: &dyn FileSource, file: &VersionFile) -> Result<VersionInfo> {
+    let content = source
+        .read_file(&file.path)?
+        .ok_or_else(|| anyhow!("version-file {} was not found.", file.path))?;
+    let version = file
+        .pattern
+        .captures(&content)
+        .and_then(|captures| captures.get(1))
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .ok_or_else(|| anyhow!("version-file pattern did not match in {}.", file.path))?;
+    Ok(VersionInfo {
+        version: version.to_string(),
+    })
+}
+
+/// A config-defined version source: the value at a JSON `pointer` in a JSON or
+/// YAML file (chosen by extension).
+#[derive(Debug, Clone)]
+pub struct VersionSource {
+    pub path: String,
+    pub pointer: String,
+}
+
+fn resolve_pointer_version(source: &dyn FileSource, file: &VersionSource) -> Result<VersionInfo> {
+    let content = source
+        .read_file(&file.path)?
+        .ok_or_else(|| anyhow!("version-source {} was not found.", file.path))?;
+    let lower = file.path.to_lowercase();
+    let document: serde_json::Value = if lower.ends_with(".yaml") || lower.ends_with(".yml") {
+        serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", file.path))?
+    } else {
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", file.path))?
+    };
+    let version = match document.pointer(&file.pointer) {
+        Some(serde_json::Value::String(value)) => value.trim().to_string(),
+        Some(serde_json::Value::Number(value)) => value.to_string(),
+        Some(_) => bail!(
+            "version-source pointer {} in {} is not a string.",
+            file.pointer,
+            file.path
+        ),
+        None => bail!(
+            "version-source pointer {} was not found in {}.",
+            file.pointer,
+            file.path
+        ),
+    };
+    if version.is_empty() {
+        bail!(
+            "version-source pointer {} in {} is empty.",
+            file.pointer,
+            file.path
+        );
+    }
+    Ok(VersionInfo { version })
+}
+
+/// Lists repository tag names for the `git-tag` archetype.
+pub trait TagSource {
+    fn tag_names(&self) -> Result<Vec<String>>;
+}
+
+/// The highest SemVer tag named `prefix` plus a version, below `current_tag`
+/// when that is a version tag too. The `git-tag` archetype passes no current
+/// tag to find the latest; `previous-from: tags` passes the manifest's.
+pub fn select_previous_tag<'a, T>(
+    tags: &'a [T],
+    tag_name: impl Fn(&T) -> &str,
+    prefix: &str,
+    current_tag: &str,
+) -> Option<&'a T> {
+    let current = current_tag.strip_prefix(prefix).and_then(SemVer::parse);
+    tags.iter()
+        .filter(|tag| tag_name(tag) != current_tag)
+        .filter_map(|tag| {
+            let version = SemVer::parse(tag_name(tag).strip_prefix(prefix)?)?;
+            Some((version, tag))
+        })
+        .filter(|(version, _)| current.as_ref().is_none_or(|current| version < current))
+        .max_by(|left, right| left.0.cmp(&right.0))
+        .map(|(_, tag)| tag)
+}
+
+fn resolve_git_tag_version(options: &VersionOptions) -> Result<Option<VersionInfo>> {
+    let tags = options
+        .tags
+        .ok_or_else(|| anyhow!("The git-tag language requires access to repository tags."))?;
+    let names = tags.tag_names()?;
+    Ok(
+        select_previous_tag(&names, String::as_str, &options.tag_prefix, "")
+            .and_then(|name| name.strip_prefix(options.tag_prefix.as_str()))
+            .map(|version| VersionInfo {
+                version: version.to_string(),
+            }),
+    )
+}
+
+/// Archetype-specific settings for version resolution.
+#[derive(Default)]
+pub struct VersionOptions<'a> {
+    /// Takes precedence over every language archetype when set.
+    pub version_file: Option<VersionFile>,
+    /// Used when there is no `version_file`, ahead of every language archetype.
+    pub version_source: Option<VersionSource>,
+    /// Cargo workspace member whose version the `rust` archetype reads.
+    pub crate_name: Option<String>,
+    /// npm workspace package whose version the `node` archetype reads.
+    pub package_name: Option<String>,
+    /// Read `appVersion` rather than `version` from a Helm Chart.yaml.
+    pub helm_app_version: bool,
+    /// File read by the `file` archetype instead of `VERSION`.
+    pub version_filename: Option<String>,
+    /// Tags and prefix searched by the `git-tag` archetype.
+    pub tags: Option<&'a dyn TagSource>,
+    pub tag_prefix: String,
+    /// How many directories the source sits below the repository root, which
+    /// bounds the Directory.Build.props lookup.
+    pub directory_depth: usize,
+    /// Resolve every listed language and fail if their versions disagree,
+    /// instead of taking the first match.
+    pub strict: bool,
+}
+
+pub fn resolve_version(
+    source: &dyn FileSource,
+    languages: &[String],
+    options: &VersionOptions,
+) -> Result<VersionInfo> {
+    if let Some(file) = &options.version_file {
+        return resolve_custom_version(source, file).with_context(|| {
+            format!(
+                "Failed to resolve the version from version-file {}",
+                file.path
+            )
+        });
+    }
+    if let Some(file) = &options.version_source {
+        return resolve_pointer_version(source, file);
+    }
+
+    let mut unknown = Vec::new();
+    for language in languages {
+        if !LANGUAGES.contains(&language.as_str()) {
+            unknown.push(language.clone());
+        }
+    }
+
+    if !unknown.is_empty() {
+        bail!("Unknown language archetype(s): {}", unknown.join(", "));
+    }
+
+    if options.strict {
+        return resolve_consistent_version(source, languages, options);
+    }
+
+    let mut attempted = Vec::new();
+
+    for language in languages {
+        if let Some(info) = resolve_language_version(source, language, options)? {
+            return Ok(info);
+        }
+
+        attempted.push(language.clone());
+    }
+
+    bail!(
+        "Unable to determine version from {}. Ensure the expected version file exists.",
+        attempted.join(", ")
+    )
+}
+
+fn resolve_language_version(
+    source: &dyn FileSource,
+    language: &str,
+    options: &VersionOptions,
+) -> Result<Option<VersionInfo>> {
+    Ok(match language {
+        "rust" => resolve_rust_version(source, options)?,
+        "node" => resolve_node_version(source, options)?,
+        "python" => resolve_python_version(source)?,
+        "go" => resolve_go_version(source)?,
+        "maven" | "java" => resolve_maven_version(source)?,
+        "gradle" => resolve_gradle_version(source)?,
+        "ruby" => resolve_ruby_version(source)?,
+        "php" => resolve_php_version(source)?,
+        "elixir" => resolve_elixir_version(source)?,
+        "dotnet" => resolve_dotnet_version(source, options)?,
+        "dart" | "flutter" => resolve_dart_version(source)?,
+        "helm" => resolve_helm_version(source, options)?,
+        "file" => resolve_file_version(source, options)?,
+        "deno" => resolve_deno_version(source)?,
+        "swift" => resolve_swift_version(source)?,
+        "bazel" => resolve_bazel_version(source)?,
+        "cmake" => resolve_cmake_version(source)?,
+        "scala" | "sbt" => resolve_scala_version(source)?,
+        "haskell" => resolve_haskell_version(source)?,
+        "zig" => resolve_zig_version(source)?,
+        "julia" => resolve_julia_version(source)?,
+        "r" => resolve_r_version(source)?,
+        "crystal" => resolve_crystal_version(source)?,
+        "nim" => resolve_nim_version(source)?,
+        "git-tag" => resolve_git_tag_version(options)?,
+        _ => None,
+    })
+}
+
+/// Resolves every language whose manifest is present and reports any that
+/// disagree with the first one as a diff.
+fn resolve_consistent_version(
+    source: &dyn FileSource,
+    languages: &[String],
+    options: &VersionOptions,
+) -> Result<VersionInfo> {
+    let mut found: Vec<(&str, VersionInfo)> = Vec::new();
+    for language in languages {
+        if let Some(info) = resolve_language_version(source, language, options)? {
+            found.push((language, info));
+        }
+    }
+
+    let Some((first_language, first)) = found.first() else {
+        bail!(
+            "Unable to determine version from {}. Ensure the expected version file exists.",
+            languages.join(", ")
+        );
+    };
+    if found.iter().all(|(_, info)| info.version == first.version) {
+        return Ok(VersionInfo {
+            version: first.version.clone(),
+        });
+    }
+
+    let mut report = format!("- {first_language}: {}", first.version);
+    for (language, info) in &found[1..] {
+        let marker = if info.version == first.version {
+            ' '
+        } else {
+            '+'
+        };
+        report.push_str(&format!("\n{marker} {language}: {}", info.version));
+    }
+    bail!("Language archetypes disagree on the version:\n{report}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct MemoryFiles(HashMap<&'static str, &'static str>);
+
+    impl FileSource for MemoryFiles {
+        fn read_file(&self, path: &str) -> Result<Option<String>> {
+            Ok(self.0.get(path).map(|content| content.to_string()))
+        }
+
+        fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+            let prefix = if path.is_empty() {
+                String::new()
+            } else {
+                format!("{path}/")
+            };
+            let mut entries: Vec<DirEntry> = Vec::new();
+            for file in self.0.keys() {
+                let Some(rest) = file.strip_prefix(&prefix) else {
+                    continue;
+                };
+                let (name, is_dir) = match rest.split_once('/') {
+                    Some((directory, _)) => (directory, true),
+                    None => (rest, false),
+                };
+                if !entries.iter().any(|entry| entry.name == name) {
+                    entries.push(DirEntry {
+                        name: name.to_string(),
+                        is_dir,
+                    });
+                }
+            }
+            entries.sort_by(|left, right| left.name.cmp(&right.name));
+            Ok(entries)
+        }
+    }
+
+    #[test]
+    fn parse_package_version() {
+        let content = r#"
+[package]
+name = "demo"
+version = "0.1.0"
+"#;
+        assert_eq!(parse_cargo_version(content), Some("0.1.0".to_string()));
+    }
+
+    #[test]
+    fn parse_workspace_package_version() {
+        let content = r#"
+[workspace]
+members = ["crate-a"]
+
+[workspace.package]
+version = "1.0.0-alpha.1"
+"#;
+        assert_eq!(
+            parse_cargo_version(content),
+            Some("1.0.0-alpha.1".to_string())
+        );
+    }
+
+    #[test]
+    fn prefer_package_over_workspace_package() {
+        let content = r#"
+[workspace]
+members = ["crate-a"]
+
+[workspace.package]
+version = "2.0.0"
+
+[package]
+name = "demo"
+version = "3.1.4"
+"#;
+        assert_eq!(parse_cargo_version(content), Some("3.1.4".to_string()));
+    }
+
+    #[test]
+    fn resolves_named_workspace_member() {
+        let files = MemoryFiles(HashMap::from([
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\n  \"crates/*\", # all crates\n  \"tools/cli\",\n]\nexclude = [\"crates/old\"]\n\n[workspace.package]\nversion = \"2.0.0\"\n",
+            ),
+            (
+                "crates/app/Cargo.toml",
+                "[package]\nname = \"app\"\nversion.workspace = true\n",
+            ),
+            (
+                "crates/worker/Cargo.toml",
+                "[package]\nname = \"worker\"\nversion = \"0.3.1\"\n",
+            ),
+            (
+                "crates/old/Cargo.toml",
+                "[package]\nname = \"old\"\nversion = \"0.0.1\"\n",
+            ),
+            (
+                "tools/cli/Cargo.toml",
+                "[package]\nname = \"cli\"\nversion = { workspace = true }\n",
+            ),
+        ]));
+        let languages = vec!["rust".to_string()];
+        let member = |name: &str| VersionOptions {
+            crate_name: Some(name.to_string()),
+            ..VersionOptions::default()
+        };
+
+        let version = |name: &str| resolve_version(&files, &languages, &member(name));
+
+        assert_eq!(version("app").unwrap().version, "2.0.0");
+        assert_eq!(version("worker").unwrap().version, "0.3.1");
+        assert_eq!(version("cli").unwrap().version, "2.0.0");
+        assert!(version("old").is_err());
+        assert!(version("missing").is_err());
+    }
+
+    #[test]
+    fn resolves_lerna_and_workspace_packages() {
+        let files = MemoryFiles(HashMap::from([
+            (
+                "package.json",
+                r#"{"private": true, "workspaces": ["packages/*", "!packages/legacy"]}"#,
+            ),
+            ("lerna.json", r#"{"version": "4.2.0"}"#),
+            (
+                "packages/ui/package.json",
+                r#"{"name": "@acme/ui", "version": "1.1.0"}"#,
+            ),
+            ("packages/cli/package.json", r#"{"name": "@acme/cli"}"#),
+            (
+                "packages/legacy/package.json",
+                r#"{"name": "legacy", "version": "0.1.0"}"#,
+            ),
+        ]));
+        let languages = vec!["node".to_string()];
+        let package = |name: &str| VersionOptions {
+            package_name: Some(name.to_string()),
+            ..VersionOptions::default()
+        };
+
+        let root = resolve_version(&files, &languages, &VersionOptions::default()).unwrap();
+        let ui = resolve_version(&files, &languages, &package("@acme/ui")).unwrap();
+        let cli = resolve_version(&files, &languages, &package("@acme/cli")).unwrap();
+
+        assert_eq!(root.version, "4.2.0");
+        assert_eq!(ui.version, "1.1.0");
+        assert_eq!(cli.version, "4.2.0");
+        assert!(resolve_version(&files, &languages, &package("legacy")).is_err());
+        assert_eq!(
+            lerna_version(Some(&serde_json::json!({"version": "independent"}))),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_pyproject_versions() {
+        let pep621 = r#"
+[build-system]
+requires = ["hatchling"]
+
+[project]
+name = "service"
+version = "2.4.0"
+"#;
+        let poetry = r#"
+[tool.poetry]
+name = "service"
+version = '0.9.1b2'
+
+[tool.poetry.dependencies]
+version = "ignored"
+"#;
+        assert_eq!(parse_pyproject_version(pep621), Some("2.4.0".to_string()));
+        assert_eq!(parse_pyproject_version(poetry), Some("0.9.1b2".to_string()));
+        assert_eq!(
+            parse_pyproject_version("[project]\ndynamic = [\"version\"]\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_setuptools_versions() {
+        let cfg = "[metadata]\nname = service\nversion = 1.4.2\n\n[options]\nversion = 9.9.9\n";
+        let setup_py = "from setuptools import setup\n\nsetup(\n    name=\"service\",\n    __version__=\"0.0.0\",\n    version = '3.0.1',\n)\n";
+        assert_eq!(parse_setup_cfg_version(cfg), Some("1.4.2".to_string()));
+        assert_eq!(
+            parse_setup_cfg_version("[metadata]\nversion = attr: service.__version__\n"),
+            None
+        );
+        assert_eq!(parse_setup_py_version(setup_py), Some("3.0.1".to_string()));
+        assert_eq!(parse_setup_py_version("setup(version=VERSION)"), None);
+    }
+
+    #[test]
+    fn parse_go_versions() {
+        let block =
+            "package version\n\nconst (\n\tName = \"svc\"\n\tVersion string = \"1.8.0\"\n)\n";
+        assert_eq!(
+            parse_go_version("package main\n\nconst Version = \"0.3.0\"\n"),
+            Some("0.3.0".to_string())
+        );
+        assert_eq!(parse_go_version(block), Some("1.8.0".to_string()));
+        assert_eq!(parse_go_version("const VersionSuffix = \"dev\"\n"), None);
+        assert_eq!(parse_version_file("\nv2.1.0\n"), Some("2.1.0".to_string()));
+    }
+
+    #[test]
+    fn resolve_maven_versions() {
+        let pom = |content| MemoryFiles(HashMap::from([("pom.xml", content)]));
+        let direct = pom(r#"<project>
+  <parent><groupId>org</groupId><version>9.0.0</version></parent>
+  <version>1.2.0</version>
+  <dependencies><dependency><version>5.0.0</version></dependency></dependencies>
+</project>"#);
+        let inherited = pom("<project><parent><version>3.1.0</version></parent></project>");
+        let property = pom(
+            "<project><version>${revision}</version><properties><revision>2.0.0-SNAPSHOT</revision></properties></project>",
+        );
+        let version = |files: &MemoryFiles| resolve_maven_version(files).unwrap().unwrap().version;
+
+        assert_eq!(version(&direct), "1.2.0");
+        assert_eq!(version(&inherited), "3.1.0");
+        assert_eq!(version(&property), "2.0.0-SNAPSHOT");
+    }
+
+    #[test]
+    fn resolve_maven_version_from_parent_pom() {
+        let files = MemoryFiles(HashMap::from([
+            (
+                "pom.xml",
+                "<project><parent><relativePath>build</relativePath></parent></project>",
+            ),
+            (
+                "build/pom.xml",
+                "<project><version>4.5.6</version></project>",
+            ),
+        ]));
+
+        let version = resolve_maven_version(&files).unwrap().unwrap().version;
+
+        assert_eq!(version, "4.5.6");
+        assert_eq!(
+            join_relative_path("services/api/pom.xml", "../../pom.xml"),
+            "pom.xml"
+        );
+        assert_eq!(normalize_relative_path("a/../../b"), "../b");
+        assert_eq!(directory_depth("services/./api/"), 2);
+        assert_eq!(directory_depth(""), 0);
+    }
+
+    #[test]
+    fn parse_gradle_versions() {
+        let properties = "# build settings\norg.gradle.jvmargs=-Xmx2g\nversion = 1.3.0\n";
+        let kotlin = "plugins {\n    kotlin(\"jvm\") version \"1.9.0\"\n}\n\nval kotlinVersion = \"1.9.0\"\nversion = \"0.7.2\"\n";
+        let groovy =
+            "plugins {\n    id 'java'\n}\n\ngroup = 'com.example'\nversion = '2.0.0-rc.1'\n";
+
+        assert_eq!(
+            parse_gradle_properties_version(properties),
+            Some("1.3.0".to_string())
+        );
+        assert_eq!(parse_gradle_properties_version("group=com.example\n"), None);
+        assert_eq!(
+            parse_gradle_build_version(kotlin),
+            Some("0.7.2".to_string())
+        );
+        assert_eq!(
+            parse_gradle_build_version(groovy),
+            Some("2.0.0-rc.1".to_string())
+        );
+    }
+
+    #[test]
+    fn resolve_ruby_versions() {
+        let literal = MemoryFiles(HashMap::from([(
+            "widget.gemspec",
+            "Gem::Specification.new do |spec|\n  spec.name = \"widget\"\n  spec.version = \"0.4.0\"\n  spec.required_ruby_version = \">= 3.0\"\nend\n",
+        )]));
+        let constant = MemoryFiles(HashMap::from([
+            (
+                "my_gem.gemspec",
+                "require_relative \"lib/my_gem/version\"\n\nGem::Specification.new do |s|\n  s.version = MyGem::VERSION\nend\n",
+            ),
+            (
+                "lib/my_gem/version.rb",
+                "module MyGem\n  VERSION = \"2.3.1\".freeze\nend\n",
+            ),
+            ("lib/other/version.rb", "VERSION = \"9.9.9\"\n"),
+        ]));
+        let version = |files: &MemoryFiles| resolve_ruby_version(files).unwrap().unwrap().version;
+
+        assert_eq!(version(&literal), "0.4.0");
+        assert_eq!(version(&constant), "2.3.1");
+        assert!(
+            resolve_ruby_version(&MemoryFiles(HashMap::new()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn resolve_php_versions() {
+        let declared = MemoryFiles(HashMap::from([(
+            "composer.json",
+            r#"{"name": "acme/api", "version": "1.6.0"}"#,
+        )]));
+        let omitted = MemoryFiles(HashMap::from([(
+            "composer.json",
+            r#"{"name": "acme/api"}"#,
+        )]));
+
+        let version = resolve_php_version(&declared).unwrap().unwrap().version;
+        let error = resolve_php_version(&omitted).unwrap_err().to_string();
+
+        assert_eq!(version, "1.6.0");
+        assert!(error.contains("git-tag"));
+    }
+
+    #[test]
+    fn parse_mix_versions() {
+        let literal = r#"defmodule Demo.MixProject do
+  use Mix.Project
+
+  def project do
+    [app: :demo, version: "0.5.0", elixir: "~> 1.15"]
+  end
+end
+"#;
+        let attribute = r#"defmodule Demo.MixProject do
+  use Mix.Project
+
+  # version: "0.0.1" is the old one
+  @version "1.4.0-rc.0"
+  @versioned "ignored"
+
+  def project do
+    [
+      app: :demo,
+      version: @version,
+      docs: [source_ref: "v#{@version}"]
+    ]
+  end
+end
+"#;
+
+        assert_eq!(parse_mix_version(literal), Some("0.5.0".to_string()));
+        assert_eq!(parse_mix_version(attribute), Some("1.4.0-rc.0".to_string()));
+        assert_eq!(
+            parse_mix_version("@version File.read!(\"VERSION\")\n[version: @version]"),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_dotnet_versions() {
+        let project = MemoryFiles(HashMap::from([(
+            "Api.csproj",
+            r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFramework>net8.0</TargetFramework>
+    <VersionPrefix>2.1.0</VersionPrefix>
+    <VersionSuffix>beta.1</VersionSuffix>
+  </PropertyGroup>
+  <ItemGroup>
+    <PackageReference Include="Serilog" Version="3.1.1" />
+  </ItemGroup>
+</Project>"#,
+        )]));
+        let central = MemoryFiles(HashMap::from([
+            (
+                "../Directory.Build.props",
+                "<Project><PropertyGroup><Version>4.0.0</Version></PropertyGroup></Project>",
+            ),
+            ("Api.csproj", "<Project Sdk=\"Microsoft.NET.Sdk\" />"),
+            ("Worker.csproj", "<Project Sdk=\"Microsoft.NET.Sdk\" />"),
+        ]));
+        let options = VersionOptions {
+            directory_depth: 2,
+            ..VersionOptions::default()
+        };
+        let version = |files: &MemoryFiles| {
+            resolve_dotnet_version(files, &options)
+                .unwrap()
+                .unwrap()
+                .version
+        };
+
+        assert_eq!(version(&project), "2.1.0-beta.1");
+        assert_eq!(version(&central), "4.0.0");
+
+        let above_root = MemoryFiles(HashMap::from([
+            (
+                "../Directory.Build.props",
+                "<Project><PropertyGroup><Version>4.0.0</Version></PropertyGroup></Project>",
+            ),
+            ("Api.csproj", "<Project Sdk=\"Microsoft.NET.Sdk\" />"),
+        ]));
+        assert!(resolve_dotnet_version(&above_root, &VersionOptions::default()).is_err());
+
+        let nearest = MemoryFiles(HashMap::from([
+            (
+                "../../Directory.Build.props",
+                "<Project><PropertyGroup><VersionSuffix>rc.1</VersionSuffix></PropertyGroup></Project>",
+            ),
+            (
+                "../Directory.Build.props",
+                "<Project><PropertyGroup><VersionPrefix>4.0.0</VersionPrefix></PropertyGroup></Project>",
+            ),
+            ("Api.csproj", "<Project Sdk=\"Microsoft.NET.Sdk\" />"),
+        ]));
+        assert_eq!(version(&nearest), "4.0.0");
+
+        let chained = MemoryFiles(HashMap::from([
+            (
+                "../../Directory.Build.props",
+                "<Project><PropertyGroup><VersionSuffix>rc.1</VersionSuffix></PropertyGroup></Project>",
+            ),
+            (
+                "../Directory.Build.props",
+                r#"<Project>
+  <Import Project="$([MSBuild]::GetPathOfFileAbove('Directory.Build.props', '$(MSBuildThisFileDirectory)../'))" />
+  <PropertyGroup><VersionPrefix>4.0.0</VersionPrefix></PropertyGroup>
+</Project>"#,
+            ),
+            ("Api.csproj", "<Project Sdk=\"Microsoft.NET.Sdk\" />"),
+        ]));
+        assert_eq!(version(&chained), "4.0.0-rc.1");
+    }
+
+    #[test]
+    fn parse_pubspec_versions() {
+        let flutter = "name: app\ndescription: Demo\nversion: 1.4.2+17\n\nenvironment:\n  sdk: '>=3.0.0 <4.0.0'\n";
+        let version = parse_pubspec_version(flutter).unwrap();
+
+        assert_eq!(version, "1.4.2+17");
+        assert!(!is_prerelease_version(&version));
+        assert_eq!(
+            parse_pubspec_version("version: 2.0.0-dev.3+007\n"),
+            Some("2.0.0-dev.3+007".to_string())
+        );
+        assert_eq!(parse_pubspec_version("name: app\n"), None);
+    }
+
+    #[test]
+    fn resolve_helm_versions() {
+        let files = MemoryFiles(HashMap::from([(
+            "Chart.yaml",
+            "apiVersion: v2\nname: api\nversion: 0.3.1\nappVersion: \"2.8.0\"\n",
+        )]));
+        let languages = vec!["helm".to_string()];
+        let app = VersionOptions {
+            helm_app_version: true,
+            ..VersionOptions::default()
+        };
+
+        let chart = resolve_version(&files, &languages, &VersionOptions::default()).unwrap();
+        let app = resolve_version(&files, &languages, &app).unwrap();
+
+        assert_eq!(chart.version, "0.3.1");
+        assert_eq!(app.version, "2.8.0");
+    }
+
+    #[test]
+    fn resolve_file_versions() {
+        let files = MemoryFiles(HashMap::from([
+            ("VERSION", "3.2.1\n"),
+            ("release/version.txt", "v0.9.0\n"),
+        ]));
+        let languages = vec!["file".to_string()];
+        let custom = VersionOptions {
+            version_filename: Some("release/version.txt".to_string()),
+            ..VersionOptions::default()
+        };
+
+        let default = resolve_version(&files, &languages, &VersionOptions::default()).unwrap();
+        let custom = resolve_version(&files, &languages, &custom).unwrap();
+
+        assert_eq!(default.version, "3.2.1");
+        assert_eq!(custom.version, "0.9.0");
+    }
+
+    #[test]
+    fn prefers_config_version_file() {
+        let files = MemoryFiles(HashMap::from([
+            ("Cargo.toml", "[package]\nversion = \"1.0.0\"\n"),
+            ("src/app/__init__.py", "__version__ = \"2.5.0\"\n"),
+        ]));
+        let options = VersionOptions {
+            version_file: Some(VersionFile {
+                path: "src/app/__init__.py".to_string(),
+                pattern: Pattern::new(r#"__version__\s*=\s*"([^"]+)""#).unwrap(),
+            }),
+            ..VersionOptions::default()
+        };
+        let missing = VersionOptions {
+            version_file: Some(VersionFile {
+                path: "Makefile".to_string(),
+                pattern: Pattern::new(r"VERSION\s*=\s*(\S+)").unwrap(),
+            }),
+            ..VersionOptions::default()
+        };
+        let languages = vec!["rust".to_string()];
+
+        let version = resolve_version(&files, &languages, &options).unwrap();
+
+        assert_eq!(version.version, "2.5.0");
+        assert!(resolve_version(&files, &languages, &missing).is_err());
+    }
+
+    #[test]
+    fn reads_version_source_pointer() {
+        let files = MemoryFiles(HashMap::from([
+            ("app/manifest.json", r#"{"meta": {"version": "3.1.0"}}"#),
+            ("deploy/values.yaml", "image:\n  tags: [\"1.9.2\"]\n"),
+        ]));
+        let source = |path: &str, pointer: &str| VersionOptions {
+            version_source: Some(VersionSource {
+                path: path.to_string(),
+                pointer: pointer.to_string(),
+            }),
+            ..VersionOptions::default()
+        };
+
+        let json = resolve_version(&files, &[], &source("app/manifest.json", "/meta/version"));
+        let yaml = resolve_version(&files, &[], &source("deploy/values.yaml", "/image/tags/0"));
+        let missing = resolve_version(&files, &[], &source("app/manifest.json", "/version"));
+
+        assert_eq!(json.unwrap().version, "3.1.0");
+        assert_eq!(yaml.unwrap().version, "1.9.2");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn resolves_latest_git_tag() {
+        struct Tags;
+
+        impl TagSource for Tags {
+            fn tag_names(&self) -> Result<Vec<String>> {
+                Ok(
+                    ["v1.2.0", "v1.10.0", "v2.0.0-rc.1", "api-v9.0.0", "nightly"]
+                        .map(str::to_string)
+                        .to_vec(),
+                )
+            }
+        }
+
+        let options = VersionOptions {
+            tags: Some(&Tags),
+            tag_prefix: "v".to_string(),
+            ..VersionOptions::default()
+        };
+        let scoped = VersionOptions {
+            tags: Some(&Tags),
+            tag_prefix: "api-v".to_string(),
+            ..VersionOptions::default()
+        };
+        let languages = vec!["git-tag".to_string()];
+        let empty = MemoryFiles(HashMap::new());
+
+        let version = resolve_version(&empty, &languages, &options).unwrap();
+        let scoped = resolve_version(&empty, &languages, &scoped).unwrap();
+
+        assert_eq!(version.version, "2.0.0-rc.1");
+        assert_eq!(scoped.version, "9.0.0");
+    }
+
+    #[test]
+    fn prerelease_detection() {
+        assert!(is_prerelease_version("0.1.0-a.1"));
         assert!(is_prerelease_version("5.9.0-beta.3"));
         assert!(is_prerelease_version("1.2.3-rc.1+build.7"));
         assert!(!is_prerelease_version("1.2.3"));
         assert!(!is_prerelease_version("1.2.3+build.7"));
         assert!(!is_prerelease_version("1.2"));
     }
+
+    #[test]
+    fn semver_precedence() {
+        let parse = |value| SemVer::parse(value).unwrap();
+        assert!(parse("1.0.0-alpha") < parse("1.0.0-alpha.1"));
+        assert!(parse("1.0.0-alpha.1") < parse("1.0.0-beta"));
+        assert!(parse("1.0.0-beta.2") < parse("1.0.0-beta.11"));
+        assert!(parse("1.0.0-rc.1") < parse("1.0.0"));
+        assert!(parse("1.9.0") < parse("1.10.0"));
+        assert_eq!(parse("1.2.3+build.1"), parse("1.2.3"));
+        assert!(SemVer::parse("1.2").is_none());
+        assert!(SemVer::parse("1.2.x").is_none());
+    }
+
+    #[test]
+    fn strict_mode_reports_disagreeing_languages() {
+        let files = MemoryFiles(HashMap::from([
+            ("Cargo.toml", "[package]\nversion = \"1.2.0\"\n"),
+            ("package.json", r#"{"version": "1.3.0"}"#),
+        ]));
+        let languages = parse_languages("rust,node,python");
+        let strict = VersionOptions {
+            strict: true,
+            ..VersionOptions::default()
+        };
+
+        let error = resolve_version(&files, &languages, &strict).unwrap_err();
+        let first = resolve_version(&files, &languages, &VersionOptions::default()).unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Language archetypes disagree on the version:\n- rust: 1.2.0\n+ node: 1.3.0"
+        );
+        assert_eq!(first.version, "1.2.0");
+    }
+
+    #[test]
+    fn resolve_deno_versions() {
+        let jsonc = MemoryFiles(HashMap::from([(
+            "deno.jsonc",
+            "{\n  // package metadata\n  \"name\": \"@acme/http\", /* scoped */\n  \"url\": \"https://jsr.io//x\",\n  \"version\": \"0.9.1\",\n}\n",
+        )]));
+        let jsr = MemoryFiles(HashMap::from([
+            ("deno.json", r#"{"tasks": {}}"#),
+            ("jsr.json", r#"{"version": "2.0.0"}"#),
+        ]));
+        let languages = vec!["deno".to_string()];
+        let options = VersionOptions::default();
+
+        let commented = resolve_version(&jsonc, &languages, &options).unwrap();
+        let fallback = resolve_version(&jsr, &languages, &options).unwrap();
+        let missing = MemoryFiles(HashMap::from([("deno.json", r#"{"tasks": {}}"#)]));
+
+        assert_eq!(commented.version, "0.9.1");
+        assert_eq!(fallback.version, "2.0.0");
+        assert!(resolve_version(&missing, &languages, &options).is_err());
+    }
+
+    #[test]
+    fn resolve_swift_versions() {
+        let package = MemoryFiles(HashMap::from([(
+            "Package.swift",
+            "// swift-tools-version: 5.9\n// version: 1.4.0\nimport PackageDescription\n",
+        )]));
+        let app = MemoryFiles(HashMap::from([
+            (
+                "Config/Shared.xcconfig",
+                "// shared settings\nMARKETING_VERSION = 3.2.1\n",
+            ),
+            (
+                "App/Info.plist",
+                "<?xml version=\"1.0\"?>\n<plist version=\"1.0\"><dict>\n<key>CFBundleName</key><string>App</string>\n<key>CFBundleShortVersionString</key><string>$(MARKETING_VERSION)</string>\n</dict></plist>\n",
+            ),
+        ]));
+        let plist = MemoryFiles(HashMap::from([(
+            "Info.plist",
+            "<plist><dict><key>CFBundleShortVersionString</key><string>2.0</string></dict></plist>",
+        )]));
+        let languages = vec!["swift".to_string()];
+        let options = VersionOptions::default();
+        let version = |files: &MemoryFiles| resolve_version(files, &languages, &options);
+
+        assert_eq!(version(&package).unwrap().version, "1.4.0");
+        assert_eq!(version(&app).unwrap().version, "3.2.1");
+        assert_eq!(version(&plist).unwrap().version, "2.0");
+    }
+
+    #[test]
+    fn parse_bazel_module_versions() {
+        let content = r#"
+bazel_dep(name = "rules_cc", version = "0.0.9")
+# module(version = "0.0.0")
+module(
+    name = "acme_tools",  # (main module)
+    version = "1.3.0",
+    compatibility_level = 1,
+)
+"#;
+        assert_eq!(
+            parse_bazel_module_version(content),
+            Some("1.3.0".to_string())
+        );
+        assert_eq!(
+            parse_bazel_module_version("module(name = \"x\")\nbazel_dep(version = \"2.0\")"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_cmake_project_versions() {
+        let content = "cmake_minimum_required(VERSION 3.20)\nPROJECT(acme\n  VERSION \"2.4.1\" # release\n  LANGUAGES CXX)\n";
+        assert_eq!(
+            parse_cmake_project_version(content),
+            Some("2.4.1".to_string())
+        );
+        assert_eq!(
+            parse_cmake_project_version("project(acme LANGUAGES C)"),
+            None
+        );
+        assert_eq!(
+            parse_cmake_project_version("project(acme VERSION ${ACME_VERSION})"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_sbt_versions() {
+        let plain = "name := \"acme\"\nversion := \"0.4.2\"\nscalaVersion := \"3.3.1\"\n";
+        let this_build = "// version := \"0.0.0\"\nThisBuild / scalaVersion := \"2.13.12\"\nThisBuild / version := \"1.2.0-SNAPSHOT\"\n";
+        let legacy = "version in ThisBuild := \"2.1.0\"\n";
+
+        assert_eq!(parse_sbt_version(plain), Some("0.4.2".to_string()));
+        assert_eq!(
+            parse_sbt_version(this_build),
+            Some("1.2.0-SNAPSHOT".to_string())
+        );
+        assert_eq!(parse_sbt_version(legacy), Some("2.1.0".to_string()));
+        assert_eq!(parse_sbt_version("scalaVersion := \"3.3.1\"\n"), None);
+    }
+
+    #[test]
+    fn resolve_haskell_versions() {
+        let cabal = MemoryFiles(HashMap::from([(
+            "acme.cabal",
+            "cabal-version: 2.4\n-- version: 0.0.0\nname: acme\nVersion:  0.3.1.0\n\nlibrary\n  version: 9.9\n",
+        )]));
+        let hpack = MemoryFiles(HashMap::from([(
+            "package.yaml",
+            "name: acme\nversion: 1.0.2\n",
+        )]));
+        let empty = MemoryFiles(HashMap::from([("acme.cabal", "name: acme\n")]));
+        let languages = vec!["haskell".to_string()];
+        let options = VersionOptions::default();
+
+        let version = |files: &MemoryFiles| resolve_version(files, &languages, &options);
+
+        assert_eq!(version(&cabal).unwrap().version, "0.3.1.0");
+        assert_eq!(version(&hpack).unwrap().version, "1.0.2");
+        assert!(version(&empty).is_err());
+    }
+
+    #[test]
+    fn parse_zig_versions() {
+        let content = ".{\n    .name = \"acme\",\n    // .version = \"0.0.0\",\n    .version = \"0.3.0\",\n    .paths = .{\"\"},\n}\n";
+        assert_eq!(parse_zon_version(content), Some("0.3.0".to_string()));
+        assert_eq!(parse_zon_version(".{ .name = \"acme\" }"), None);
+    }
+
+    #[test]
+    fn parse_julia_versions() {
+        let content = "name = \"Acme\"\nuuid = \"7876af07-990d-54b4-ab0e-23690620f79a\"\nversion = \"0.5.3\"\n\n[deps]\nversion = \"not this one\"\n";
+        assert_eq!(parse_julia_version(content), Some("0.5.3".to_string()));
+        assert_eq!(parse_julia_version("[compat]\nversion = \"1.6\"\n"), None);
+    }
+
+    #[test]
+    fn parse_r_description_versions() {
+        let content = "Package: acme\nTitle: Tools\nDescription: Helpers for\n    Version: control files.\nVersion: 1.0.3.9000\nLicense: MIT\n";
+        assert_eq!(
+            parse_description_version(content),
+            Some("1.0.3.9000".to_string())
+        );
+        assert_eq!(parse_description_version("Package: acme\n"), None);
+    }
+
+    #[test]
+    fn resolve_crystal_versions() {
+        let files = MemoryFiles(HashMap::from([(
+            "shard.yml",
+            "name: acme\nversion: 0.8.1\ndependencies:\n  kemal:\n    github: kemalcr/kemal\n    version: ~> 1.4\n",
+        )]));
+        let missing = MemoryFiles(HashMap::from([("shard.yml", "name: acme\n")]));
+        let languages = vec!["crystal".to_string()];
+        let options = VersionOptions::default();
+
+        let version = resolve_version(&files, &languages, &options).unwrap();
+
+        assert_eq!(version.version, "0.8.1");
+        assert!(resolve_version(&missing, &languages, &options).is_err());
+    }
+
+    #[test]
+    fn resolve_nim_versions() {
+        let files = MemoryFiles(HashMap::from([
+            (
+                "acme.nimble",
+                "# Package\n# version = \"0.0.0\"\nversion       = \"0.2.4\"\nauthor        = \"Acme\"\nrequires \"nim >= 2.0.0\"\n",
+            ),
+            ("src/acme.nim", "echo \"hi\"\n"),
+        ]));
+        let missing = MemoryFiles(HashMap::from([("acme.nimble", "author = \"Acme\"\n")]));
+        let languages = vec!["nim".to_string()];
+        let options = VersionOptions::default();
+
+        let version = resolve_version(&files, &languages, &options).unwrap();
+
+        assert_eq!(version.version, "0.2.4");
+        assert!(resolve_version(&missing, &languages, &options).is_err());
+    }
 }
@@ -0,0 +1,221 @@
+use anyhow::{Result, anyhow, bail};
+
+/// A minimal XML element tree: enough to read manifests like `pom.xml` and
+/// `.csproj`. Namespace prefixes are stripped from element names, and text is
+/// the element's own character data.
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    pub children: Vec<Element>,
+    pub text: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Element {
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the trimmed text of the first child called `name`, if non-empty.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name)
+            .map(|child| child.text.trim())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Parses a document and returns its root element.
+pub fn parse(content: &str) -> Result<Element> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    let mut rest = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            if let Some(current) = stack.last_mut() {
+                current.text.push_str(&decode_entities(rest));
+            }
+            break;
+        };
+        if let Some(current) = stack.last_mut() {
+            current.text.push_str(&decode_entities(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = skip_past(after, "-->")?;
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after
+                .find("]]>")
+                .ok_or_else(|| anyhow!("Unterminated CDATA section."))?;
+            if let Some(current) = stack.last_mut() {
+                current.text.push_str(&after[..end]);
+            }
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            rest = skip_past(after, "?>")?;
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            rest = skip_past(after, ">")?;
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after
+                .find('>')
+                .ok_or_else(|| anyhow!("Unterminated closing tag."))?;
+            let name = local_name(after[..end].trim());
+            let element = stack
+                .pop()
+                .ok_or_else(|| anyhow!("Unexpected closing tag </{name}>."))?;
+            if element.name != name {
+                bail!("Mismatched closing tag </{name}> for <{}>.", element.name);
+            }
+            rest = &after[end + 1..];
+            close(element, &mut stack, &mut root);
+        } else {
+            let end = tag_end(rest).ok_or_else(|| anyhow!("Unterminated tag."))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag)?;
+            if self_closing {
+                close(element, &mut stack, &mut root);
+            } else {
+                stack.push(element);
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        bail!("Unclosed element <{}>.", open.name);
+    }
+    root.ok_or_else(|| anyhow!("Document has no root element."))
+}
+
+fn close(element: Element, stack: &mut [Element], root: &mut Option<Element>) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(element),
+        None => {
+            root.get_or_insert(element);
+        }
+    }
+}
+
+fn skip_past<'a>(content: &'a str, terminator: &str) -> Result<&'a str> {
+    let end = content
+        .find(terminator)
+        .ok_or_else(|| anyhow!("Expected {terminator} before end of document."))?;
+    Ok(&content[end + terminator.len()..])
+}
+
+/// Finds the `>` closing a start tag, ignoring any inside quoted attribute values.
+fn tag_end(content: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in content.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_tag(tag: &str) -> Result<Element> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = local_name(&tag[..name_end]);
+    if name.is_empty() {
+        bail!("Empty tag name.");
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let Some(equals) = rest.find('=') else {
+            bail!("Malformed attribute in <{name}>.");
+        };
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| anyhow!("Unquoted attribute {key} in <{name}>."))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| anyhow!("Unterminated attribute {key} in <{name}>."))?;
+        attributes.push((key.to_string(), decode_entities(&value[1..end + 1])));
+        rest = value[end + 2..].trim_start();
+    }
+
+    Ok(Element {
+        name: name.to_string(),
+        attributes,
+        ..Element::default()
+    })
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_elements() {
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- comment with <tags> -->
+<project xmlns="http://maven.apache.org/POM/4.0.0" a='x > y'>
+  <version>1.0.0</version>
+  <empty/>
+  <dependencies>
+    <dependency><version><![CDATA[2.0]]></version></dependency>
+  </dependencies>
+  <name>Tom &amp; Jerry</name>
+</project>
+"#;
+
+        let root = parse(document).unwrap();
+
+        assert_eq!(root.name, "project");
+        assert_eq!(root.attribute("a"), Some("x > y"));
+        assert_eq!(root.attribute("missing"), None);
+        assert_eq!(root.child_text("version"), Some("1.0.0"));
+        assert_eq!(root.child_text("name"), Some("Tom & Jerry"));
+        assert_eq!(root.child_text("empty"), None);
+        let dependency = root.child("dependencies").unwrap().child("dependency");
+        assert_eq!(dependency.unwrap().child_text("version"), Some("2.0"));
+    }
+
+    #[test]
+    fn rejects_mismatched_tags() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::{Result, anyhow, bail};
+
+/// A parsed Zig object notation value, enough to read `build.zig.zon`.
+/// Numbers, enum literals, and identifiers are kept as their source text.
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Struct(Vec<(String, Value)>),
+    Tuple(Vec<Value>),
+    String(String),
+    Literal(String),
+}
+
+impl Value {
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Struct(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<Value> {
+    let mut parser = Parser {
+        rest: content.strip_prefix('\u{feff}').unwrap_or(content),
+    };
+    let value = parser.value()?;
+    parser.skip_trivia();
+    if !parser.rest.is_empty() {
+        bail!("Unexpected content after the top-level value.");
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_trivia(&mut self) {
+        loop {
+            self.rest = self.rest.trim_start();
+            match self.rest.strip_prefix("//") {
+                Some(comment) => self.rest = comment.split_once('\n').map_or("", |(_, rest)| rest),
+                None => return,
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_trivia();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_trivia();
+        if self.eat(".{") {
+            return self.container();
+        }
+        if self.rest.starts_with('"') {
+            return self.string().map(Value::String);
+        }
+        if self.rest.starts_with("\\\\") {
+            return Ok(Value::String(self.multiline_string()));
+        }
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+' | '@')))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            bail!("Unexpected character in ZON value.");
+        }
+        let (literal, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(Value::Literal(literal.to_string()))
+    }
+
+    /// Parses the body of `.{ ... }`, which is a struct when its first entry is
+    /// `.name = value` and a tuple otherwise.
+    fn container(&mut self) -> Result<Value> {
+        let mut fields = Vec::new();
+        let mut items = Vec::new();
+        loop {
+            if self.eat("}") {
+                break;
+            }
+            if let Some(name) = self.field_name()? {
+                if !self.eat("=") {
+                    bail!("Expected = after .{name}.");
+                }
+                fields.push((name, self.value()?));
+            } else {
+                items.push(self.value()?);
+            }
+            if !self.eat(",") {
+                if !self.eat("}") {
+                    bail!("Expected , or }} in ZON container.");
+                }
+                break;
+            }
+        }
+        match (fields.is_empty(), items.is_empty()) {
+            (_, true) => Ok(Value::Struct(fields)),
+            (true, false) => Ok(Value::Tuple(items)),
+            (false, false) => bail!("ZON container mixes fields and values."),
+        }
+    }
+
+    /// Consumes `.name` or `.@"name"` when followed by `=`; leaves enum literals alone.
+    fn field_name(&mut self) -> Result<Option<String>> {
+        self.skip_trivia();
+        let Some(after) = self.rest.strip_prefix('.') else {
+            return Ok(None);
+        };
+        let saved = self.rest;
+        self.rest = after;
+        let name = if self.rest.starts_with("@\"") {
+            self.rest = &self.rest[1..];
+            self.string()?
+        } else {
+            let end = self
+                .rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(self.rest.len());
+            let (name, rest) = self.rest.split_at(end);
+            self.rest = rest;
+            name.to_string()
+        };
+        self.skip_trivia();
+        if name.is_empty() || !self.rest.starts_with('=') {
+            self.rest = saved;
+            return Ok(None);
+        }
+        Ok(Some(name))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let mut chars = self.rest[1..].char_indices();
+        let mut value = String::new();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[index + 2..];
+                    return Ok(value);
+                }
+                '\\' => {
+                    let (_, escaped) = chars
+                        .next()
+                        .ok_or_else(|| anyhow!("Unterminated ZON string."))?;
+                    value.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        other => other,
+                    });
+                }
+                '\n' => break,
+                other => value.push(other),
+            }
+        }
+        bail!("Unterminated ZON string.")
+    }
+
+    /// Joins consecutive `\\` lines, as Zig does for multiline string literals.
+    fn multiline_string(&mut self) -> String {
+        let mut lines = Vec::new();
+        while let Some(line) = self.rest.trim_start().strip_prefix("\\\\") {
+            let (text, rest) = line.split_once('\n').unwrap_or((line, ""));
+            lines.push(text.trim_end_matches('\r'));
+            self.rest = rest;
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_build_zig_zon() {
+        let document = r#"
+// Package manifest
+.{
+    .name = .acme,
+    .version = "0.14.0-dev.1", // follows SemVer
+    .@"minimum_zig_version" = "0.13.0",
+    .dependencies = .{
+        .known_folders = .{
+            .url = "https://example.com/kf.tar.gz",
+            .hash = "1220abc",
+        },
+    },
+    .paths = .{ "build.zig", "src" },
+}
+"#;
+
+        let root = parse(document).unwrap();
+
+        assert_eq!(
+            root.field("version").and_then(Value::as_str),
+            Some("0.14.0-dev.1")
+        );
+        assert_eq!(
+            root.field("name"),
+            Some(&Value::Literal(".acme".to_string()))
+        );
+        assert_eq!(
+            root.field("minimum_zig_version").and_then(Value::as_str),
+            Some("0.13.0")
+        );
+        assert!(matches!(root.field("paths"), Some(Value::Tuple(paths)) if paths.len() == 2));
+        assert!(
+            root.field("dependencies")
+                .and_then(|deps| deps.field("known_folders"))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_zon() {
+        assert!(parse(".{ .version = \"1.0 }").is_err());
+        assert!(parse(".{ .version = }").is_err());
+        assert!(parse(".{ .a = 1 } extra").is_err());
+    }
+}